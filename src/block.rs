@@ -5,10 +5,13 @@ use rlp::{DecoderError, Rlp, RlpStream};
 use sha3::{Digest, Keccak256};
 
 use crate::{
-	enveloped::{EnvelopedDecodable, EnvelopedEncodable},
-	header::{Header, PartialHeader},
+	enveloped::{
+		append_enveloped_list, decode_enveloped_list, EnvelopedDecodable, EnvelopedEncodable,
+	},
+	header::{Header, PartialHeader, SealedHeader},
 	transaction::{TransactionAny, TransactionV0, TransactionV1, TransactionV2},
 	util::ordered_trie_root,
+	withdrawal::{withdrawals_root, Withdrawal},
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -21,55 +24,316 @@ pub struct Block<T> {
 	pub header: Header,
 	pub transactions: Vec<T>,
 	pub ommers: Vec<Header>,
+	/// Withdrawals, since Shanghai (EIP-4895).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub withdrawals: Option<Vec<Withdrawal>>,
 }
 
 impl<T: EnvelopedEncodable> rlp::Encodable for Block<T> {
 	fn rlp_append(&self, s: &mut RlpStream) {
-		s.begin_list(3);
+		s.begin_list(3 + usize::from(self.withdrawals.is_some()));
 		s.append(&self.header);
-		s.append_list::<Vec<u8>, _>(
-			&self
-				.transactions
-				.iter()
-				.map(|tx| EnvelopedEncodable::encode(tx).to_vec())
-				.collect::<Vec<_>>(),
-		);
+		append_enveloped_list(s, &self.transactions);
 		s.append_list(&self.ommers);
+		if let Some(withdrawals) = &self.withdrawals {
+			s.append_list(withdrawals);
+		}
 	}
 }
 
 impl<T: EnvelopedDecodable> rlp::Decodable for Block<T> {
 	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		let withdrawals = match rlp.item_count()? {
+			3 => None,
+			4 => Some(rlp.list_at(3)?),
+			_ => return Err(DecoderError::RlpIncorrectListLen),
+		};
+
 		Ok(Self {
 			header: rlp.val_at(0)?,
-			transactions: rlp
-				.list_at::<Vec<u8>>(1)?
-				.into_iter()
-				.map(|raw_tx| {
-					EnvelopedDecodable::decode(&raw_tx)
-						.map_err(|_| DecoderError::Custom("decode enveloped transaction failed"))
-				})
-				.collect::<Result<Vec<_>, _>>()?,
+			transactions: decode_enveloped_list(&rlp.at(1)?)?,
 			ommers: rlp.list_at(2)?,
+			withdrawals,
 		})
 	}
 }
 
+/// Maximum size of an RLP encoded block, as defined by EIP-7934.
+pub const MAX_RLP_BLOCK_SIZE: usize = 10_485_760 - 2_097_152;
+
+/// Error returned when a block body does not match its header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BlockValidationError {
+	/// Transactions root in the header does not match the transactions.
+	TransactionsRootMismatch { expected: H256, computed: H256 },
+	/// Ommers hash in the header does not match the ommers.
+	OmmersHashMismatch { expected: H256, computed: H256 },
+	/// Withdrawals root in the header does not match the withdrawals. `None`
+	/// stands for a missing root or missing withdrawals.
+	WithdrawalsRootMismatch {
+		expected: Option<H256>,
+		computed: Option<H256>,
+	},
+	/// RLP encoded block exceeds the EIP-7934 size limit.
+	BlockTooLarge { size: usize, limit: usize },
+}
+
 impl<T: EnvelopedEncodable> Block<T> {
 	pub fn new(partial_header: PartialHeader, transactions: Vec<T>, ommers: Vec<Header>) -> Self {
-		let ommers_hash =
-			H256::from_slice(Keccak256::digest(&rlp::encode_list(&ommers)[..]).as_slice());
-		let transactions_root = ordered_trie_root(
-			transactions
-				.iter()
-				.map(|r| EnvelopedEncodable::encode(r).freeze()),
-		);
+		let ommers_hash = ommers_hash(&ommers);
+		let transactions_root = transactions_root(&transactions);
 
 		Self {
 			header: Header::new(partial_header, ommers_hash, transactions_root),
 			transactions,
 			ommers,
+			withdrawals: None,
+		}
+	}
+
+	/// Create a block with withdrawals, computing its withdrawals root.
+	pub fn new_with_withdrawals(
+		partial_header: PartialHeader,
+		transactions: Vec<T>,
+		ommers: Vec<Header>,
+		withdrawals: Vec<Withdrawal>,
+	) -> Self {
+		let mut block = Self::new(partial_header, transactions, ommers);
+		block.header.withdrawals_root = Some(withdrawals_root(&withdrawals));
+		block.withdrawals = Some(withdrawals);
+		block
+	}
+
+	/// Check that the body of the block is consistent with its header.
+	///
+	/// This recomputes the transactions root, the ommers hash and the
+	/// withdrawals root, and checks the EIP-7934 limit on the RLP encoded block
+	/// size.
+	pub fn validate_body(&self) -> Result<(), BlockValidationError> {
+		let computed = transactions_root(&self.transactions);
+		if computed != self.header.transactions_root {
+			return Err(BlockValidationError::TransactionsRootMismatch {
+				expected: self.header.transactions_root,
+				computed,
+			});
+		}
+
+		let computed = ommers_hash(&self.ommers);
+		if computed != self.header.ommers_hash {
+			return Err(BlockValidationError::OmmersHashMismatch {
+				expected: self.header.ommers_hash,
+				computed,
+			});
+		}
+
+		let computed = self.withdrawals.as_deref().map(withdrawals_root);
+		if computed != self.header.withdrawals_root {
+			return Err(BlockValidationError::WithdrawalsRootMismatch {
+				expected: self.header.withdrawals_root,
+				computed,
+			});
+		}
+
+		let size = self.encoded_len();
+		if size > MAX_RLP_BLOCK_SIZE {
+			return Err(BlockValidationError::BlockTooLarge {
+				size,
+				limit: MAX_RLP_BLOCK_SIZE,
+			});
+		}
+
+		Ok(())
+	}
+}
+
+/// Ommers hash of a block without ommers.
+pub const EMPTY_OMMERS_HASH: H256 = H256([
+	0x1d, 0xcc, 0x4d, 0xe8, 0xde, 0xc7, 0x5d, 0x7a, 0xab, 0x85, 0xb5, 0x67, 0xb6, 0xcc, 0xd4, 0x1a,
+	0xd3, 0x12, 0x45, 0x1b, 0x94, 0x8a, 0x74, 0x13, 0xf0, 0xa1, 0x42, 0xfd, 0x40, 0xd4, 0x93, 0x47,
+]);
+
+/// Compute the ommers hash of a list of ommer headers.
+pub fn ommers_hash(ommers: &[Header]) -> H256 {
+	H256::from_slice(Keccak256::digest(&rlp::encode_list(ommers)[..]).as_slice())
+}
+
+/// Compute the transactions root of a list of transactions.
+pub fn transactions_root<T: EnvelopedEncodable>(transactions: &[T]) -> H256 {
+	ordered_trie_root(
+		transactions
+			.iter()
+			.map(|r| EnvelopedEncodable::encode(r).freeze()),
+	)
+}
+
+/// Block together with the precomputed hashes of its header and transactions.
+///
+/// Deserializing and SCALE decoding check the hashes against the header and
+/// transactions, but not the body against the header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize))]
+pub struct SealedBlock<T> {
+	header: SealedHeader,
+	transactions: Vec<T>,
+	transaction_hashes: Vec<H256>,
+	ommers: Vec<Header>,
+	withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl<T: EnvelopedEncodable> SealedBlock<T> {
+	/// Seal the block by computing the header and transaction hashes.
+	///
+	/// The body is not checked against the header, use `TryFrom` for that.
+	#[must_use]
+	pub fn seal(block: Block<T>) -> Self {
+		let transaction_hashes = block.transactions.iter().map(transaction_hash).collect();
+
+		Self {
+			header: SealedHeader::seal(block.header),
+			transactions: block.transactions,
+			transaction_hashes,
+			ommers: block.ommers,
+			withdrawals: block.withdrawals,
+		}
+	}
+
+	#[cfg(any(feature = "with-scale", feature = "with-serde"))]
+	fn check_transaction_hashes(&self) -> bool {
+		self.transaction_hashes.len() == self.transactions.len()
+			&& self
+				.transactions
+				.iter()
+				.zip(&self.transaction_hashes)
+				.all(|(tx, hash)| transaction_hash(tx) == *hash)
+	}
+}
+
+fn transaction_hash<T: EnvelopedEncodable>(transaction: &T) -> H256 {
+	H256::from_slice(Keccak256::digest(EnvelopedEncodable::encode(transaction)).as_slice())
+}
+
+#[cfg(feature = "with-scale")]
+impl<T> scale_codec::Decode for SealedBlock<T>
+where
+	T: scale_codec::Decode + EnvelopedEncodable,
+{
+	fn decode<I: scale_codec::Input>(value: &mut I) -> Result<Self, scale_codec::Error> {
+		let (header, transactions, transaction_hashes, ommers, withdrawals) =
+			scale_codec::Decode::decode(value)?;
+		let block = Self {
+			header,
+			transactions,
+			transaction_hashes,
+			ommers,
+			withdrawals,
+		};
+		if !block.check_transaction_hashes() {
+			return Err(scale_codec::Error::from("Transaction hash mismatch"));
+		}
+
+		Ok(block)
+	}
+}
+
+#[cfg(feature = "with-serde")]
+impl<'de, T> serde::Deserialize<'de> for SealedBlock<T>
+where
+	T: serde::Deserialize<'de> + EnvelopedEncodable,
+{
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		#[derive(serde::Deserialize)]
+		struct Unchecked<T> {
+			header: SealedHeader,
+			transactions: Vec<T>,
+			transaction_hashes: Vec<H256>,
+			ommers: Vec<Header>,
+			withdrawals: Option<Vec<Withdrawal>>,
+		}
+
+		let unchecked = Unchecked::deserialize(deserializer)?;
+		let block = Self {
+			header: unchecked.header,
+			transactions: unchecked.transactions,
+			transaction_hashes: unchecked.transaction_hashes,
+			ommers: unchecked.ommers,
+			withdrawals: unchecked.withdrawals,
+		};
+		if !block.check_transaction_hashes() {
+			return Err(serde::de::Error::custom("transaction hash mismatch"));
 		}
+
+		Ok(block)
+	}
+}
+
+impl<T> SealedBlock<T> {
+	#[must_use]
+	pub fn header(&self) -> &SealedHeader {
+		&self.header
+	}
+
+	#[must_use]
+	pub fn hash(&self) -> H256 {
+		self.header.hash()
+	}
+
+	#[must_use]
+	pub fn transactions(&self) -> &[T] {
+		&self.transactions
+	}
+
+	#[must_use]
+	pub fn transaction_hashes(&self) -> &[H256] {
+		&self.transaction_hashes
+	}
+
+	#[must_use]
+	pub fn ommers(&self) -> &[Header] {
+		&self.ommers
+	}
+
+	#[must_use]
+	pub fn withdrawals(&self) -> Option<&[Withdrawal]> {
+		self.withdrawals.as_deref()
+	}
+
+	/// Iterate over the transactions together with their hashes.
+	pub fn transactions_with_hashes(&self) -> impl Iterator<Item = (&T, H256)> {
+		self.transactions
+			.iter()
+			.zip(self.transaction_hashes.iter().copied())
+	}
+
+	#[must_use]
+	pub fn unseal(self) -> Block<T> {
+		Block {
+			header: self.header.into_inner(),
+			transactions: self.transactions,
+			ommers: self.ommers,
+			withdrawals: self.withdrawals,
+		}
+	}
+}
+
+impl<T: EnvelopedEncodable> TryFrom<Block<T>> for SealedBlock<T> {
+	type Error = BlockValidationError;
+
+	fn try_from(block: Block<T>) -> Result<Self, Self::Error> {
+		block.validate_body()?;
+		Ok(Self::seal(block))
+	}
+}
+
+impl<T> From<SealedBlock<T>> for Block<T> {
+	fn from(sealed: SealedBlock<T>) -> Self {
+		sealed.unseal()
 	}
 }
 
@@ -87,6 +351,7 @@ where
 			header: t.header,
 			transactions: t.transactions.into_iter().map(|t| t.into()).collect(),
 			ommers: t.ommers,
+			withdrawals: t.withdrawals,
 		}
 	}
 }
@@ -97,6 +362,151 @@ impl From<BlockV1> for BlockV2 {
 			header: t.header,
 			transactions: t.transactions.into_iter().map(|t| t.into()).collect(),
 			ommers: t.ommers,
+			withdrawals: t.withdrawals,
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::transaction::{LegacyTransaction, TransactionAction, TransactionSignature};
+	use ethereum_types::{H160, U256};
+	use hex_literal::hex;
+
+	fn block() -> BlockV2 {
+		let partial_header = PartialHeader {
+			gas_used: 21000.into(),
+			..partial_header()
+		};
+		let transaction = TransactionV2::Legacy(LegacyTransaction {
+			nonce: 12.into(),
+			gas_price: 20_000_000_000_u64.into(),
+			gas_limit: 21000.into(),
+			action: TransactionAction::Call(
+				hex!("727fc6a68321b754475c668a6abfb6e9e71c169a").into(),
+			),
+			value: U256::from(10) * 1_000_000_000 * 1_000_000_000,
+			input: vec![],
+			signature: TransactionSignature::new(
+				38,
+				hex!("be67e0a07db67da8d446f76add590e54b6e92cb6b8f9835aeb67540579a27717").into(),
+				hex!("2d690516512020171c1ec870f6ff45398cc8609250326be89915fb538e7bd718").into(),
+			)
+			.unwrap(),
+		});
+
+		Block::new(partial_header, vec![transaction], vec![])
+	}
+
+	#[test]
+	fn validate_body() {
+		let block = block();
+		assert_eq!(block.header.ommers_hash, EMPTY_OMMERS_HASH);
+		assert_eq!(block.validate_body(), Ok(()));
+
+		let mut invalid = block.clone();
+		invalid.transactions.clear();
+		assert!(matches!(
+			invalid.validate_body(),
+			Err(BlockValidationError::TransactionsRootMismatch { .. })
+		));
+
+		let mut invalid = block;
+		invalid.ommers.push(invalid.header.clone());
+		assert!(matches!(
+			invalid.validate_body(),
+			Err(BlockValidationError::OmmersHashMismatch { .. })
+		));
+	}
+
+	#[test]
+	fn sealed_block() {
+		let block = block();
+		let sealed = SealedBlock::try_from(block.clone()).unwrap();
+		assert_eq!(sealed.hash(), block.header.hash());
+		assert_eq!(sealed.transaction_hashes(), &[block.transactions[0].hash()]);
+		assert_eq!(Block::from(sealed), block);
+
+		let mut invalid = block;
+		invalid.transactions.clear();
+		assert!(SealedBlock::try_from(invalid).is_err());
+	}
+
+	#[test]
+	fn withdrawals() {
+		let block = block();
+		let withdrawal = Withdrawal {
+			index: 1,
+			validator_index: 2,
+			address: H160::repeat_byte(3),
+			amount: 4,
+		};
+		let mut with = Block::new_with_withdrawals(
+			PartialHeader {
+				base_fee_per_gas: Some(7.into()),
+				..PartialHeader::from(block.header.clone())
+			},
+			block.transactions.clone(),
+			Vec::new(),
+			vec![withdrawal],
+		);
+		assert_eq!(with.validate_body(), Ok(()));
+		let bytes = rlp::encode(&with);
+		assert_eq!(Rlp::new(&bytes).item_count(), Ok(4));
+		assert_eq!(rlp::decode::<BlockV2>(&bytes), Ok(with.clone()));
+		assert_eq!(rlp::decode::<BlockV2>(&rlp::encode(&block)), Ok(block));
+
+		let root = with.header.withdrawals_root;
+		with.withdrawals = Some(Vec::new());
+		assert_eq!(
+			with.validate_body(),
+			Err(BlockValidationError::WithdrawalsRootMismatch {
+				expected: root,
+				computed: Some(withdrawals_root(&[])),
+			})
+		);
+
+		with.withdrawals = None;
+		assert_eq!(
+			with.validate_body(),
+			Err(BlockValidationError::WithdrawalsRootMismatch {
+				expected: root,
+				computed: None,
+			})
+		);
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn deserialize_checks_transaction_hashes() {
+		let sealed = SealedBlock::seal(block());
+		let mut json = serde_json::to_value(&sealed).unwrap();
+		assert_eq!(
+			serde_json::from_value::<SealedBlock<TransactionV2>>(json.clone()).unwrap(),
+			sealed
+		);
+
+		json["transaction_hashes"][0] = serde_json::to_value(H256::zero()).unwrap();
+		assert!(serde_json::from_value::<SealedBlock<TransactionV2>>(json.clone()).is_err());
+
+		json["transaction_hashes"] = serde_json::json!([]);
+		assert!(serde_json::from_value::<SealedBlock<TransactionV2>>(json).is_err());
+	}
+
+	#[cfg(feature = "with-scale")]
+	#[test]
+	fn scale_decode_checks_transaction_hashes() {
+		use scale_codec::{Decode, Encode};
+
+		let mut sealed = SealedBlock::seal(block());
+		let bytes = sealed.encode();
+		assert_eq!(SealedBlock::decode(&mut &bytes[..]), Ok(sealed.clone()));
+
+		// More transactions than hashes.
+		sealed.transaction_hashes.clear();
+		let bytes = sealed.encode();
+		assert!(SealedBlock::<TransactionV2>::decode(&mut &bytes[..]).is_err());
+	}
+}
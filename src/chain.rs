@@ -0,0 +1,321 @@
+//! Reader and writer for chain export files.
+//!
+//! A chain export file, as written by `geth export` or used as `chain.rlp` by
+//! hive, is a plain concatenation of RLP encoded blocks.
+//!
+//! Blocks of any fork up to Prague are supported. Blocks and headers with an
+//! unexpected number of items are rejected rather than partially decoded.
+
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use rlp::DecoderError;
+
+use crate::{block::Block, transaction::TransactionAny};
+
+/// Error returned when reading a chain export file.
+///
+/// The offset is the position in the file of the block being read.
+#[derive(Debug)]
+pub enum ChainFileError {
+	/// Underlying reader failed, or the file ended within a block.
+	Io { offset: u64, error: io::Error },
+	/// Block is not valid RLP or not a valid block.
+	Rlp { offset: u64, error: DecoderError },
+}
+
+impl ChainFileError {
+	/// Position in the file of the block that failed.
+	#[must_use]
+	pub fn offset(&self) -> u64 {
+		match self {
+			Self::Io { offset, .. } | Self::Rlp { offset, .. } => *offset,
+		}
+	}
+}
+
+/// Streaming reader of a chain export file.
+///
+/// Only one block is held in memory at a time. The reader is not buffered, so
+/// wrapping files in a [`std::io::BufReader`] is recommended.
+pub struct ChainReader<R> {
+	reader: R,
+	offset: u64,
+	done: bool,
+}
+
+impl<R: Read> ChainReader<R> {
+	pub fn new(reader: R) -> Self {
+		Self {
+			reader,
+			offset: 0,
+			done: false,
+		}
+	}
+
+	/// Position in the file of the next block.
+	#[must_use]
+	pub fn offset(&self) -> u64 {
+		self.offset
+	}
+
+	#[must_use]
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	/// Read the RLP list header of the next block, returning it along with
+	/// the payload length, or `None` at the end of the file.
+	fn read_list_header(&mut self) -> Result<Option<(Vec<u8>, usize)>, ChainFileError> {
+		let offset = self.offset;
+		let io_error = |error| ChainFileError::Io { offset, error };
+		let rlp_error = |error| ChainFileError::Rlp { offset, error };
+
+		let mut prefix = [0];
+		loop {
+			match self.reader.read(&mut prefix) {
+				Ok(0) => return Ok(None),
+				Ok(_) => break,
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+				Err(e) => return Err(io_error(e)),
+			}
+		}
+
+		let mut header = vec![prefix[0]];
+		let len = match prefix[0] {
+			0..=0xbf => return Err(rlp_error(DecoderError::RlpExpectedToBeList)),
+			0xc0..=0xf7 => usize::from(prefix[0] - 0xc0),
+			0xf8..=0xff => {
+				let len_of_len = usize::from(prefix[0] - 0xf7);
+				let mut bytes = [0; 8];
+				self.reader
+					.read_exact(&mut bytes[8 - len_of_len..])
+					.map_err(io_error)?;
+				header.extend_from_slice(&bytes[8 - len_of_len..]);
+
+				if bytes[8 - len_of_len] == 0 {
+					return Err(rlp_error(DecoderError::RlpDataLenWithZeroPrefix));
+				}
+				let len = u64::from_be_bytes(bytes);
+				if len < 56 {
+					return Err(rlp_error(DecoderError::RlpInvalidIndirection));
+				}
+				usize::try_from(len).map_err(|_| rlp_error(DecoderError::RlpIsTooBig))?
+			}
+		};
+
+		Ok(Some((header, len)))
+	}
+
+	fn read_block(&mut self) -> Result<Option<Block<TransactionAny>>, ChainFileError> {
+		let offset = self.offset;
+		let Some((mut item, len)) = self.read_list_header()? else {
+			return Ok(None);
+		};
+
+		// The payload is read incrementally rather than allocated upfront, so
+		// that a corrupted length does not cause a huge allocation.
+		let header_len = item.len();
+		self.reader
+			.by_ref()
+			.take(len as u64)
+			.read_to_end(&mut item)
+			.map_err(|error| ChainFileError::Io { offset, error })?;
+		if item.len() != header_len + len {
+			return Err(ChainFileError::Io {
+				offset,
+				error: io::ErrorKind::UnexpectedEof.into(),
+			});
+		}
+
+		let block = rlp::decode(&item).map_err(|error| ChainFileError::Rlp { offset, error })?;
+		self.offset += item.len() as u64;
+		Ok(Some(block))
+	}
+}
+
+impl<R: Read> Iterator for ChainReader<R> {
+	type Item = Result<Block<TransactionAny>, ChainFileError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		let next = self.read_block().transpose();
+		if !matches!(next, Some(Ok(_))) {
+			self.done = true;
+		}
+		next
+	}
+}
+
+/// Writer of a chain export file.
+pub struct ChainWriter<W> {
+	writer: W,
+}
+
+impl<W: Write> ChainWriter<W> {
+	pub fn new(writer: W) -> Self {
+		Self { writer }
+	}
+
+	/// Append a block to the file.
+	pub fn write(&mut self, block: &Block<TransactionAny>) -> io::Result<()> {
+		self.writer.write_all(&rlp::encode(block))
+	}
+
+	/// Flush and return the underlying writer.
+	pub fn finish(mut self) -> io::Result<W> {
+		self.writer.flush()?;
+		Ok(self.writer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::{
+		header::PartialHeader,
+		transaction::{
+			EIP1559Transaction, LegacyTransaction, TransactionAction, TransactionSignature,
+		},
+		withdrawal::withdrawals_root,
+	};
+	use ethereum_types::{H160, H256, U256};
+	use rlp::{Rlp, RlpStream};
+
+	fn block(number: u64, extra_data: Vec<u8>) -> Block<TransactionAny> {
+		let partial_header = PartialHeader {
+			parent_hash: H256::repeat_byte(1),
+			number: number.into(),
+			gas_used: 42_000.into(),
+			timestamp: number,
+			extra_data,
+			base_fee_per_gas: Some(7.into()),
+			..partial_header()
+		};
+		let transactions = vec![
+			TransactionAny::Legacy(LegacyTransaction {
+				nonce: number.into(),
+				gas_price: 1.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Call(H160::repeat_byte(2)),
+				value: 1.into(),
+				input: Vec::new(),
+				signature: TransactionSignature::new(
+					27,
+					H256::repeat_byte(3),
+					H256::repeat_byte(4),
+				)
+				.unwrap(),
+			}),
+			TransactionAny::EIP1559(EIP1559Transaction {
+				chain_id: 1,
+				nonce: number.into(),
+				max_priority_fee_per_gas: 1.into(),
+				max_fee_per_gas: 2.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Create,
+				value: U256::zero(),
+				input: vec![0x60, 0x00],
+				access_list: Vec::new(),
+				odd_y_parity: true,
+				r: H256::repeat_byte(3),
+				s: H256::repeat_byte(4),
+			}),
+		];
+
+		Block::new(partial_header, transactions, Vec::new())
+	}
+
+	#[test]
+	fn write_and_read() {
+		let mut shanghai = block(3, Vec::new());
+		shanghai.withdrawals = Some(Vec::new());
+		shanghai.header.withdrawals_root = Some(withdrawals_root(&[]));
+		let blocks = vec![block(1, Vec::new()), block(2, vec![0xab; 32]), shanghai];
+		let mut writer = ChainWriter::new(Vec::new());
+		for block in &blocks {
+			writer.write(block).unwrap();
+		}
+		let file = writer.finish().unwrap();
+
+		let mut reader = ChainReader::new(&file[..]);
+		let read: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+		assert_eq!(read, blocks);
+		assert_eq!(reader.offset(), file.len() as u64);
+	}
+
+	#[test]
+	fn report_offsets() {
+		let first = rlp::encode(&block(1, Vec::new()));
+		let mut file = first.to_vec();
+		file.extend_from_slice(&rlp::encode(&block(2, Vec::new())));
+		file.truncate(file.len() - 1);
+
+		let results: Vec<_> = ChainReader::new(&file[..]).collect();
+		assert_eq!(results.len(), 2);
+		assert!(results[0].is_ok());
+		assert!(matches!(
+			&results[1],
+			Err(ChainFileError::Io { offset, error })
+				if *offset == first.len() as u64 && error.kind() == io::ErrorKind::UnexpectedEof
+		));
+
+		let mut file = first.to_vec();
+		file.extend_from_slice(&[0x82, 0x01, 0x02]);
+		let err = ChainReader::new(&file[..]).nth(1).unwrap().unwrap_err();
+		assert_eq!(err.offset(), first.len() as u64);
+		assert!(matches!(
+			err,
+			ChainFileError::Rlp {
+				error: DecoderError::RlpExpectedToBeList,
+				..
+			}
+		));
+	}
+
+	#[test]
+	fn reject_unexpected_items() {
+		let block = block(1, Vec::new());
+
+		// Header with an unknown field after the Prague requests hash.
+		let mut header = RlpStream::new_list(22);
+		let encoded = rlp::encode(&block.header);
+		for item in Rlp::new(&encoded).iter() {
+			header.append_raw(item.as_raw(), 1);
+		}
+		for _ in 0..6 {
+			header.append_empty_data();
+		}
+		let mut s = RlpStream::new_list(3);
+		s.append_raw(&header.out(), 1);
+		s.begin_list(0);
+		s.begin_list(0);
+		let file = s.out();
+		assert!(matches!(
+			ChainReader::new(&file[..]).next(),
+			Some(Err(ChainFileError::Rlp {
+				offset: 0,
+				error: DecoderError::RlpIncorrectListLen,
+			}))
+		));
+
+		// Block with an unknown field after the withdrawals.
+		let mut s = RlpStream::new_list(5);
+		s.append(&block.header);
+		for _ in 0..4 {
+			s.begin_list(0);
+		}
+		let file = s.out();
+		assert!(matches!(
+			ChainReader::new(&file[..]).next(),
+			Some(Err(ChainFileError::Rlp {
+				offset: 0,
+				error: DecoderError::RlpIncorrectListLen,
+			}))
+		));
+	}
+}
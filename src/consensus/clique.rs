@@ -0,0 +1,339 @@
+use alloc::vec::Vec;
+
+use ethereum_types::{H160, H256, H64, U256};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+use crate::{block::EMPTY_OMMERS_HASH, header::Header};
+
+/// Length of the signer vanity prefix of the extra data.
+pub const EXTRA_VANITY: usize = 32;
+/// Length of the signer seal suffix of the extra data.
+pub const EXTRA_SEAL: usize = 65;
+/// Default number of blocks between checkpoints.
+pub const DEFAULT_EPOCH: u64 = 30_000;
+/// Difficulty of a block sealed by the in-turn signer.
+pub const DIFF_IN_TURN: u64 = 2;
+/// Difficulty of a block sealed by an out-of-turn signer.
+pub const DIFF_NO_TURN: u64 = 1;
+/// Nonce voting to authorize the beneficiary as a signer.
+pub const NONCE_AUTH_VOTE: H64 = H64([0xff; 8]);
+/// Nonce voting to deauthorize the beneficiary as a signer.
+pub const NONCE_DROP_VOTE: H64 = H64([0x00; 8]);
+
+/// Clique chain configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CliqueConfig {
+	/// Number of blocks between checkpoints, `DEFAULT_EPOCH` if zero.
+	pub epoch: u64,
+}
+
+impl Default for CliqueConfig {
+	fn default() -> Self {
+		Self {
+			epoch: DEFAULT_EPOCH,
+		}
+	}
+}
+
+impl CliqueConfig {
+	/// Whether the given block number is a checkpoint block.
+	#[must_use]
+	pub fn is_checkpoint(&self, number: U256) -> bool {
+		// A zero epoch falls back to the default, as in geth.
+		let epoch = match self.epoch {
+			0 => DEFAULT_EPOCH,
+			epoch => epoch,
+		};
+		(number % epoch).is_zero()
+	}
+}
+
+/// Error returned when a header violates the Clique rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CliqueError {
+	/// Extra data is too short to hold the vanity and the seal.
+	MissingVanityOrSeal,
+	/// Signer list of a checkpoint is not a multiple of the address length.
+	InvalidCheckpointSigners,
+	/// Non-checkpoint block contains a signer list.
+	UnexpectedSigners,
+	/// Checkpoint block has a non-zero beneficiary.
+	CheckpointBeneficiary,
+	/// Nonce is neither an authorize nor a drop vote.
+	InvalidVote,
+	/// Checkpoint block has a non-drop nonce.
+	CheckpointVote,
+	/// Mix hash is not zero.
+	NonZeroMixHash,
+	/// Ommers hash is not the empty ommers hash.
+	NonEmptyOmmers,
+	/// Difficulty is neither in-turn nor out-of-turn.
+	InvalidDifficulty,
+	/// Seal signature could not be recovered.
+	InvalidSignature,
+}
+
+/// Signer vote cast by a header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Vote {
+	/// Address voted on.
+	pub target: H160,
+	/// Whether the vote authorizes or deauthorizes the target.
+	pub authorize: bool,
+}
+
+/// Hash of the header signed by the Clique signer, excluding the seal.
+///
+/// Optional fields set since London follow the other fields, as in the header
+/// encoding.
+pub fn seal_hash(header: &Header) -> Result<H256, CliqueError> {
+	let extra_len = header
+		.extra_data
+		.len()
+		.checked_sub(EXTRA_SEAL)
+		.ok_or(CliqueError::MissingVanityOrSeal)?;
+
+	let optional = header.optional_fields();
+	let mut s = RlpStream::new_list(15 + optional);
+	s.append(&header.parent_hash);
+	s.append(&header.ommers_hash);
+	s.append(&header.beneficiary);
+	s.append(&header.state_root);
+	s.append(&header.transactions_root);
+	s.append(&header.receipts_root);
+	s.append(&header.logs_bloom);
+	s.append(&header.difficulty);
+	s.append(&header.number);
+	s.append(&header.gas_limit);
+	s.append(&header.gas_used);
+	s.append(&header.timestamp);
+	s.append(&&header.extra_data[..extra_len]);
+	s.append(&header.mix_hash);
+	s.append(&header.nonce);
+	header.append_optional_fields(&mut s, optional);
+	Ok(H256::from_slice(Keccak256::digest(s.out()).as_slice()))
+}
+
+/// Recover the signer address from the seal at the end of the extra data.
+pub fn recover_signer(header: &Header) -> Result<H160, CliqueError> {
+	let hash = seal_hash(header)?;
+	let seal = &header.extra_data[header.extra_data.len() - EXTRA_SEAL..];
+
+	let signature =
+		Signature::from_slice(&seal[..64]).map_err(|_| CliqueError::InvalidSignature)?;
+	let recovery_id = RecoveryId::from_byte(seal[64]).ok_or(CliqueError::InvalidSignature)?;
+	let key = VerifyingKey::recover_from_prehash(hash.as_bytes(), &signature, recovery_id)
+		.map_err(|_| CliqueError::InvalidSignature)?;
+
+	let public = key.to_encoded_point(false);
+	let digest = Keccak256::digest(&public.as_bytes()[1..]);
+	Ok(H160::from_slice(&digest[12..]))
+}
+
+/// Parse the signer list stored between the vanity and the seal of a checkpoint header.
+// `usize::is_multiple_of` is only available since Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+pub fn checkpoint_signers(header: &Header) -> Result<Vec<H160>, CliqueError> {
+	if header.extra_data.len() < EXTRA_VANITY + EXTRA_SEAL {
+		return Err(CliqueError::MissingVanityOrSeal);
+	}
+
+	let signers = &header.extra_data[EXTRA_VANITY..header.extra_data.len() - EXTRA_SEAL];
+	if signers.len() % H160::len_bytes() != 0 {
+		return Err(CliqueError::InvalidCheckpointSigners);
+	}
+
+	Ok(signers
+		.chunks_exact(H160::len_bytes())
+		.map(H160::from_slice)
+		.collect())
+}
+
+/// Vote cast by the header on its beneficiary, if any.
+pub fn vote(header: &Header) -> Result<Option<Vote>, CliqueError> {
+	let authorize = if header.nonce == NONCE_AUTH_VOTE {
+		true
+	} else if header.nonce == NONCE_DROP_VOTE {
+		false
+	} else {
+		return Err(CliqueError::InvalidVote);
+	};
+
+	if header.beneficiary.is_zero() {
+		return Ok(None);
+	}
+
+	Ok(Some(Vote {
+		target: header.beneficiary,
+		authorize,
+	}))
+}
+
+/// Check the Clique rules that do not depend on the signer set.
+// `usize::is_multiple_of` is only available since Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+pub fn verify_header(header: &Header, config: &CliqueConfig) -> Result<(), CliqueError> {
+	let checkpoint = config.is_checkpoint(header.number);
+
+	if checkpoint && !header.beneficiary.is_zero() {
+		return Err(CliqueError::CheckpointBeneficiary);
+	}
+
+	vote(header)?;
+	if checkpoint && header.nonce != NONCE_DROP_VOTE {
+		return Err(CliqueError::CheckpointVote);
+	}
+
+	if header.extra_data.len() < EXTRA_VANITY + EXTRA_SEAL {
+		return Err(CliqueError::MissingVanityOrSeal);
+	}
+
+	let signers_len = header.extra_data.len() - EXTRA_VANITY - EXTRA_SEAL;
+	if !checkpoint && signers_len != 0 {
+		return Err(CliqueError::UnexpectedSigners);
+	}
+	if checkpoint && signers_len % H160::len_bytes() != 0 {
+		return Err(CliqueError::InvalidCheckpointSigners);
+	}
+
+	if !header.mix_hash.is_zero() {
+		return Err(CliqueError::NonZeroMixHash);
+	}
+
+	if header.ommers_hash != EMPTY_OMMERS_HASH {
+		return Err(CliqueError::NonEmptyOmmers);
+	}
+
+	if header.difficulty != DIFF_IN_TURN.into() && header.difficulty != DIFF_NO_TURN.into() {
+		return Err(CliqueError::InvalidDifficulty);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::header::PartialHeader;
+	use crate::test_utils::partial_header;
+
+	use k256::ecdsa::SigningKey;
+
+	fn sealed_header(number: u64, signers: &[H160], key: &SigningKey) -> Header {
+		let mut extra_data = vec![0x11; EXTRA_VANITY];
+		for signer in signers {
+			extra_data.extend_from_slice(signer.as_bytes());
+		}
+		extra_data.extend_from_slice(&[0; EXTRA_SEAL]);
+
+		let partial_header = PartialHeader {
+			parent_hash: H256::repeat_byte(1),
+			state_root: H256::repeat_byte(2),
+			difficulty: DIFF_IN_TURN.into(),
+			number: number.into(),
+			gas_limit: 8_000_000.into(),
+			timestamp: 1_600_000_000,
+			extra_data,
+			nonce: NONCE_DROP_VOTE,
+			..partial_header()
+		};
+		let mut header = Header::new(partial_header, EMPTY_OMMERS_HASH, H256::zero());
+
+		let hash = seal_hash(&header).unwrap();
+		let (signature, recovery_id) = key.sign_prehash_recoverable(hash.as_bytes()).unwrap();
+		let seal_start = header.extra_data.len() - EXTRA_SEAL;
+		header.extra_data[seal_start..seal_start + 64].copy_from_slice(&signature.to_bytes());
+		header.extra_data[seal_start + 64] = recovery_id.to_byte();
+		header
+	}
+
+	fn address(key: &SigningKey) -> H160 {
+		let public = key.verifying_key().to_encoded_point(false);
+		H160::from_slice(&Keccak256::digest(&public.as_bytes()[1..])[12..])
+	}
+
+	#[test]
+	fn recover_seal_signer() {
+		let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+		let header = sealed_header(1, &[], &key);
+
+		assert_eq!(recover_signer(&header), Ok(address(&key)));
+		assert_eq!(verify_header(&header, &CliqueConfig::default()), Ok(()));
+	}
+
+	#[test]
+	fn seal_hash_optional_fields() {
+		let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+		let mut header = sealed_header(1, &[], &key);
+		header.base_fee_per_gas = Some(1_000_000_000.into());
+
+		// The seal hash is the hash of the header encoding without the seal.
+		let mut unsealed = header.clone();
+		unsealed.extra_data.truncate(EXTRA_VANITY);
+		assert_eq!(seal_hash(&header), Ok(unsealed.hash()));
+
+		let mut frontier = header.clone();
+		frontier.base_fee_per_gas = None;
+		assert_ne!(seal_hash(&header), seal_hash(&frontier));
+		assert_ne!(recover_signer(&header), Ok(address(&key)));
+	}
+
+	#[test]
+	fn parse_checkpoint() {
+		let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+		let signers = [address(&key), H160::repeat_byte(0xaa)];
+		let header = sealed_header(DEFAULT_EPOCH, &signers, &key);
+
+		assert_eq!(verify_header(&header, &CliqueConfig::default()), Ok(()));
+		assert_eq!(checkpoint_signers(&header), Ok(signers.to_vec()));
+		assert_eq!(recover_signer(&header), Ok(address(&key)));
+
+		let mut invalid = header.clone();
+		invalid.number = (DEFAULT_EPOCH + 1).into();
+		assert_eq!(
+			verify_header(&invalid, &CliqueConfig::default()),
+			Err(CliqueError::UnexpectedSigners)
+		);
+	}
+
+	#[test]
+	fn header_votes() {
+		let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+		let mut header = sealed_header(1, &[], &key);
+		assert_eq!(vote(&header), Ok(None));
+
+		header.beneficiary = H160::repeat_byte(0xbb);
+		header.nonce = NONCE_AUTH_VOTE;
+		assert_eq!(
+			vote(&header),
+			Ok(Some(Vote {
+				target: H160::repeat_byte(0xbb),
+				authorize: true,
+			}))
+		);
+
+		header.nonce = H64::repeat_byte(1);
+		assert_eq!(vote(&header), Err(CliqueError::InvalidVote));
+
+		header.nonce = NONCE_AUTH_VOTE;
+		header.number = DEFAULT_EPOCH.into();
+		assert_eq!(
+			verify_header(&header, &CliqueConfig::default()),
+			Err(CliqueError::CheckpointBeneficiary)
+		);
+	}
+
+	#[test]
+	fn checkpoints() {
+		let config = CliqueConfig { epoch: 10 };
+		assert!(config.is_checkpoint(0.into()));
+		assert!(config.is_checkpoint(20.into()));
+		assert!(!config.is_checkpoint(21.into()));
+
+		let config = CliqueConfig { epoch: 0 };
+		assert!(config.is_checkpoint(DEFAULT_EPOCH.into()));
+		assert!(!config.is_checkpoint(10.into()));
+	}
+}
@@ -0,0 +1,209 @@
+use ethereum_types::U256;
+
+use crate::{block::EMPTY_OMMERS_HASH, header::Header};
+
+/// Lower bound of the proof-of-work difficulty.
+pub const MINIMUM_DIFFICULTY: u64 = 131_072;
+/// Bound divisor of the difficulty change between two blocks.
+pub const DIFFICULTY_BOUND_DIVISOR: u64 = 2048;
+/// Number of blocks of a difficulty bomb period.
+pub const EXP_DIFFICULTY_PERIOD: u64 = 100_000;
+
+/// Pre-merge forks that changed the difficulty calculation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DifficultyFork {
+	Frontier,
+	Homestead,
+	Byzantium,
+	Constantinople,
+	MuirGlacier,
+	London,
+	ArrowGlacier,
+	GrayGlacier,
+}
+
+impl DifficultyFork {
+	/// Fork active at the given block number on Ethereum mainnet.
+	#[must_use]
+	pub fn mainnet(number: U256) -> Self {
+		const FORKS: [(u64, DifficultyFork); 7] = [
+			(15_050_000, DifficultyFork::GrayGlacier),
+			(13_773_000, DifficultyFork::ArrowGlacier),
+			(12_965_000, DifficultyFork::London),
+			(9_200_000, DifficultyFork::MuirGlacier),
+			(7_280_000, DifficultyFork::Constantinople),
+			(4_370_000, DifficultyFork::Byzantium),
+			(1_150_000, DifficultyFork::Homestead),
+		];
+
+		FORKS
+			.iter()
+			.find(|(block, _)| number >= U256::from(*block))
+			.map_or(DifficultyFork::Frontier, |(_, fork)| *fork)
+	}
+
+	/// Number of blocks the difficulty bomb is delayed by.
+	#[must_use]
+	pub fn bomb_delay(self) -> u64 {
+		match self {
+			Self::Frontier | Self::Homestead => 0,
+			Self::Byzantium => 3_000_000,
+			Self::Constantinople => 5_000_000,
+			Self::MuirGlacier => 9_000_000,
+			Self::London => 9_700_000,
+			Self::ArrowGlacier => 10_700_000,
+			Self::GrayGlacier => 11_400_000,
+		}
+	}
+}
+
+/// Calculate the expected difficulty of a header given its parent.
+#[must_use]
+pub fn calc_difficulty(header: &Header, parent: &Header, fork: DifficultyFork) -> U256 {
+	let time_delta = header.timestamp.saturating_sub(parent.timestamp);
+
+	// Adjustment factor applied to `parent.difficulty / 2048`, capped at -99.
+	let factor: i64 = match fork {
+		DifficultyFork::Frontier => {
+			if time_delta < 13 {
+				1
+			} else {
+				-1
+			}
+		}
+		DifficultyFork::Homestead => 1 - (time_delta / 10).min(100) as i64,
+		_ => {
+			let base = if parent.ommers_hash == EMPTY_OMMERS_HASH {
+				1
+			} else {
+				2
+			};
+			base - (time_delta / 9).min(101) as i64
+		}
+	}
+	.max(-99);
+
+	let adjustment =
+		parent.difficulty / DIFFICULTY_BOUND_DIVISOR * U256::from(factor.unsigned_abs());
+	let mut difficulty = if factor >= 0 {
+		parent.difficulty.saturating_add(adjustment)
+	} else {
+		parent.difficulty.saturating_sub(adjustment)
+	}
+	.max(MINIMUM_DIFFICULTY.into());
+
+	// The bomb uses a fake block number, lagging behind by the delay of the fork.
+	let fake_number = match fork {
+		DifficultyFork::Frontier | DifficultyFork::Homestead => {
+			parent.number.saturating_add(U256::one())
+		}
+		_ => parent
+			.number
+			.saturating_sub(U256::from(fork.bomb_delay() - 1)),
+	};
+	let period = fake_number / EXP_DIFFICULTY_PERIOD;
+	if period > U256::one() {
+		let exponent = period - 2;
+		let bomb = if exponent < U256::from(256) {
+			U256::one() << exponent.as_usize()
+		} else {
+			U256::MAX
+		};
+		difficulty = difficulty.saturating_add(bomb);
+	}
+
+	difficulty
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::header::PartialHeader;
+	use crate::test_utils::partial_header;
+	use ethereum_types::H256;
+
+	fn header(number: u64, timestamp: u64, difficulty: U256) -> Header {
+		let partial_header = PartialHeader {
+			difficulty,
+			number: number.into(),
+			gas_limit: 5000.into(),
+			timestamp,
+			..partial_header()
+		};
+		Header::new(partial_header, EMPTY_OMMERS_HASH, H256::zero())
+	}
+
+	#[test]
+	fn frontier_block_one() {
+		let genesis = header(0, 0, 17_179_869_184_u64.into());
+		let block = header(1, 1_438_269_988, U256::zero());
+
+		assert_eq!(
+			calc_difficulty(&block, &genesis, DifficultyFork::mainnet(block.number)),
+			17_171_480_576_u64.into()
+		);
+	}
+
+	#[test]
+	fn homestead_adjustment() {
+		let difficulty = U256::from(2_048_000_000_u64);
+		let parent = header(2_000_000, 1000, difficulty);
+		let step = difficulty / DIFFICULTY_BOUND_DIVISOR;
+		let bomb = U256::one() << 18;
+
+		let fast = header(2_000_001, 1009, U256::zero());
+		assert_eq!(
+			calc_difficulty(&fast, &parent, DifficultyFork::Homestead),
+			difficulty + step + bomb
+		);
+
+		let slow = header(2_000_001, 1025, U256::zero());
+		assert_eq!(
+			calc_difficulty(&slow, &parent, DifficultyFork::Homestead),
+			difficulty - step + bomb
+		);
+
+		let stalled = header(2_000_001, 1_000_000, U256::zero());
+		assert_eq!(
+			calc_difficulty(&stalled, &parent, DifficultyFork::Homestead),
+			difficulty - step * 99 + bomb
+		);
+	}
+
+	#[test]
+	fn byzantium_ommers_and_bomb_delay() {
+		let difficulty = U256::from(2_048_000_000_u64);
+		let step = difficulty / DIFFICULTY_BOUND_DIVISOR;
+		let mut parent = header(4_500_000, 1000, difficulty);
+		let block = header(4_500_001, 1010, U256::zero());
+
+		// Fake block number is 1_500_001, so the bomb adds 2^13.
+		assert_eq!(
+			calc_difficulty(&block, &parent, DifficultyFork::Byzantium),
+			difficulty + (U256::one() << 13)
+		);
+
+		parent.ommers_hash = H256::repeat_byte(1);
+		assert_eq!(
+			calc_difficulty(&block, &parent, DifficultyFork::Byzantium),
+			difficulty + step + (U256::one() << 13)
+		);
+
+		// The bomb is fully delayed from Constantinople onwards at this height.
+		assert_eq!(
+			calc_difficulty(&block, &parent, DifficultyFork::Constantinople),
+			difficulty + step
+		);
+	}
+
+	#[test]
+	fn minimum_difficulty() {
+		let parent = header(10, 1000, MINIMUM_DIFFICULTY.into());
+		let block = header(11, 2000, U256::zero());
+
+		assert_eq!(
+			calc_difficulty(&block, &parent, DifficultyFork::GrayGlacier),
+			MINIMUM_DIFFICULTY.into()
+		);
+	}
+}
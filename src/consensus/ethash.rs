@@ -0,0 +1,408 @@
+use alloc::vec::Vec;
+
+use ethereum_types::{H256, H64, U256, U512};
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256, Keccak512};
+
+use crate::header::Header;
+
+/// Number of blocks per Ethash epoch.
+pub const EPOCH_LENGTH: u64 = 30_000;
+/// First epoch not supported, as in geth.
+pub const MAX_EPOCH: u64 = 2048;
+
+const DATASET_BYTES_INIT: u64 = 1 << 30;
+const DATASET_BYTES_GROWTH: u64 = 1 << 23;
+const CACHE_BYTES_INIT: u64 = 1 << 24;
+const CACHE_BYTES_GROWTH: u64 = 1 << 17;
+const MIX_BYTES: u64 = 128;
+const HASH_BYTES: u64 = 64;
+const HASH_WORDS: usize = 16;
+const MIX_WORDS: usize = 32;
+const DATASET_PARENTS: u32 = 256;
+const CACHE_ROUNDS: usize = 3;
+const ACCESSES: u32 = 64;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// Default number of epoch caches kept by `Ethash`.
+pub const DEFAULT_CACHE_CAPACITY: usize = 3;
+
+type Node = [u32; HASH_WORDS];
+
+/// Error returned when an Ethash seal is invalid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EthashError {
+	/// Header difficulty is zero.
+	ZeroDifficulty,
+	/// Mix hash in the header does not match the computed mix digest.
+	MixHashMismatch { expected: H256, computed: H256 },
+	/// Proof-of-work result is above the difficulty target.
+	DifficultyNotMet,
+	/// Epoch is not below `MAX_EPOCH`.
+	EpochTooHigh { epoch: u64, max: u64 },
+}
+
+fn check_epoch(epoch: u64) -> Result<(), EthashError> {
+	if epoch >= MAX_EPOCH {
+		return Err(EthashError::EpochTooHigh {
+			epoch,
+			max: MAX_EPOCH,
+		});
+	}
+
+	Ok(())
+}
+
+/// Hash of the header without the `mix_hash` and `nonce` seal fields.
+///
+/// Optional fields set since London follow the other fields, as in the header
+/// encoding.
+#[must_use]
+pub fn seal_hash(header: &Header) -> H256 {
+	let optional = header.optional_fields();
+	let mut s = RlpStream::new_list(13 + optional);
+	s.append(&header.parent_hash);
+	s.append(&header.ommers_hash);
+	s.append(&header.beneficiary);
+	s.append(&header.state_root);
+	s.append(&header.transactions_root);
+	s.append(&header.receipts_root);
+	s.append(&header.logs_bloom);
+	s.append(&header.difficulty);
+	s.append(&header.number);
+	s.append(&header.gas_limit);
+	s.append(&header.gas_used);
+	s.append(&header.timestamp);
+	s.append(&header.extra_data);
+	header.append_optional_fields(&mut s, optional);
+	H256::from_slice(Keccak256::digest(s.out()).as_slice())
+}
+
+/// Epoch of the given block number.
+#[must_use]
+pub fn epoch(number: u64) -> u64 {
+	number / EPOCH_LENGTH
+}
+
+/// Seed hash of the given epoch.
+pub fn seed_hash(epoch: u64) -> Result<H256, EthashError> {
+	check_epoch(epoch)?;
+	let mut seed = [0_u8; 32];
+	for _ in 0..epoch {
+		seed = Keccak256::digest(seed).into();
+	}
+	Ok(H256(seed))
+}
+
+/// Size in bytes of the verification cache of the given epoch.
+pub fn cache_size(epoch: u64) -> Result<u64, EthashError> {
+	check_epoch(epoch)?;
+	let mut size = CACHE_BYTES_INIT + CACHE_BYTES_GROWTH * epoch - HASH_BYTES;
+	while !is_prime(size / HASH_BYTES) {
+		size -= 2 * HASH_BYTES;
+	}
+	Ok(size)
+}
+
+/// Size in bytes of the full dataset of the given epoch.
+pub fn dataset_size(epoch: u64) -> Result<u64, EthashError> {
+	check_epoch(epoch)?;
+	let mut size = DATASET_BYTES_INIT + DATASET_BYTES_GROWTH * epoch - MIX_BYTES;
+	while !is_prime(size / MIX_BYTES) {
+		size -= 2 * MIX_BYTES;
+	}
+	Ok(size)
+}
+
+// `u64::is_multiple_of` is only available since Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+fn is_prime(n: u64) -> bool {
+	if n < 2 {
+		return false;
+	}
+	let mut i = 2;
+	while i * i <= n {
+		if n % i == 0 {
+			return false;
+		}
+		i += 1;
+	}
+	true
+}
+
+fn fnv(a: u32, b: u32) -> u32 {
+	a.wrapping_mul(FNV_PRIME) ^ b
+}
+
+fn keccak512(data: &[u8]) -> Node {
+	let digest = Keccak512::digest(data);
+	let mut node = [0_u32; HASH_WORDS];
+	for (word, bytes) in node.iter_mut().zip(digest.chunks_exact(4)) {
+		*word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+	}
+	node
+}
+
+fn node_bytes(node: &Node) -> [u8; HASH_BYTES as usize] {
+	let mut out = [0_u8; HASH_BYTES as usize];
+	for (bytes, word) in out.chunks_exact_mut(4).zip(node.iter()) {
+		bytes.copy_from_slice(&word.to_le_bytes());
+	}
+	out
+}
+
+/// Ethash verification cache of a single epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthashCache {
+	epoch: u64,
+	dataset_size: u64,
+	nodes: Vec<Node>,
+}
+
+impl EthashCache {
+	/// Generate the verification cache of the given epoch.
+	pub fn new(epoch: u64) -> Result<Self, EthashError> {
+		let n = (cache_size(epoch)? / HASH_BYTES) as usize;
+		let mut nodes = Vec::with_capacity(n);
+
+		nodes.push(keccak512(seed_hash(epoch)?.as_bytes()));
+		for i in 1..n {
+			let node = keccak512(&node_bytes(&nodes[i - 1]));
+			nodes.push(node);
+		}
+
+		for _ in 0..CACHE_ROUNDS {
+			for i in 0..n {
+				let v = nodes[i][0] as usize % n;
+				let mut data = nodes[(i + n - 1) % n];
+				for (a, b) in data.iter_mut().zip(nodes[v].iter()) {
+					*a ^= *b;
+				}
+				nodes[i] = keccak512(&node_bytes(&data));
+			}
+		}
+
+		Ok(Self {
+			epoch,
+			dataset_size: dataset_size(epoch)?,
+			nodes,
+		})
+	}
+
+	#[must_use]
+	pub fn epoch(&self) -> u64 {
+		self.epoch
+	}
+
+	fn dataset_item(&self, index: u32) -> Node {
+		let n = self.nodes.len();
+		let mut mix = self.nodes[index as usize % n];
+		mix[0] ^= index;
+		mix = keccak512(&node_bytes(&mix));
+
+		for j in 0..DATASET_PARENTS {
+			let parent = fnv(index ^ j, mix[j as usize % HASH_WORDS]) as usize % n;
+			for (a, b) in mix.iter_mut().zip(self.nodes[parent].iter()) {
+				*a = fnv(*a, *b);
+			}
+		}
+
+		keccak512(&node_bytes(&mix))
+	}
+
+	/// Compute the mix digest and result of the given seal hash and nonce.
+	#[must_use]
+	pub fn hashimoto_light(&self, seal_hash: H256, nonce: H64) -> (H256, H256) {
+		let mut seed = [0_u8; 40];
+		seed[..32].copy_from_slice(seal_hash.as_bytes());
+		// The nonce is a big-endian integer, hashed in little-endian order.
+		for (dst, src) in seed[32..].iter_mut().zip(nonce.as_bytes().iter().rev()) {
+			*dst = *src;
+		}
+		let seed = keccak512(&seed);
+
+		let mut mix = [0_u32; MIX_WORDS];
+		mix[..HASH_WORDS].copy_from_slice(&seed);
+		mix[HASH_WORDS..].copy_from_slice(&seed);
+
+		let pages = (self.dataset_size / MIX_BYTES) as u32;
+		for i in 0..ACCESSES {
+			let page = fnv(i ^ seed[0], mix[i as usize % MIX_WORDS]) % pages;
+			for (chunk, offset) in mix.chunks_exact_mut(HASH_WORDS).zip(0..) {
+				let item = self.dataset_item(page * 2 + offset);
+				for (a, b) in chunk.iter_mut().zip(item.iter()) {
+					*a = fnv(*a, *b);
+				}
+			}
+		}
+
+		let mut digest = [0_u8; 32];
+		for (bytes, words) in digest.chunks_exact_mut(4).zip(mix.chunks_exact(4)) {
+			let word = fnv(fnv(fnv(words[0], words[1]), words[2]), words[3]);
+			bytes.copy_from_slice(&word.to_le_bytes());
+		}
+
+		let mut result = Keccak256::new();
+		result.update(node_bytes(&seed));
+		result.update(digest);
+
+		(H256(digest), H256::from_slice(result.finalize().as_slice()))
+	}
+}
+
+/// Ethash light verifier, keeping the caches of recently used epochs.
+#[derive(Clone, Debug)]
+pub struct Ethash {
+	capacity: usize,
+	caches: Vec<EthashCache>,
+}
+
+impl Default for Ethash {
+	fn default() -> Self {
+		Self::new(DEFAULT_CACHE_CAPACITY)
+	}
+}
+
+impl Ethash {
+	/// Create a verifier keeping at most `capacity` epoch caches.
+	#[must_use]
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			capacity: capacity.max(1),
+			caches: Vec::new(),
+		}
+	}
+
+	/// Get the cache of the given epoch, generating it if necessary.
+	pub fn cache(&mut self, epoch: u64) -> Result<&EthashCache, EthashError> {
+		match self.caches.iter().position(|cache| cache.epoch == epoch) {
+			Some(index) => {
+				let cache = self.caches.remove(index);
+				self.caches.push(cache);
+			}
+			None => {
+				let cache = EthashCache::new(epoch)?;
+				if self.caches.len() >= self.capacity {
+					self.caches.remove(0);
+				}
+				self.caches.push(cache);
+			}
+		}
+
+		Ok(self.caches.last().expect("cache was just inserted; qed"))
+	}
+
+	/// Verify the `mix_hash` and `nonce` of the header against its difficulty.
+	pub fn verify_seal(&mut self, header: &Header) -> Result<(), EthashError> {
+		if header.difficulty.is_zero() {
+			return Err(EthashError::ZeroDifficulty);
+		}
+
+		// Numbers beyond `u64` are far past the last supported epoch.
+		let number = header.number.min(u64::MAX.into()).low_u64();
+		let (mix_hash, result) = self
+			.cache(epoch(number))?
+			.hashimoto_light(seal_hash(header), header.nonce);
+
+		if mix_hash != header.mix_hash {
+			return Err(EthashError::MixHashMismatch {
+				expected: header.mix_hash,
+				computed: mix_hash,
+			});
+		}
+
+		// The result must not exceed `2^256 / difficulty`.
+		let result = U256::from_big_endian(result.as_bytes());
+		if result.full_mul(header.difficulty) > U512::one() << 256 {
+			return Err(EthashError::DifficultyNotMet);
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::mainnet_block_one;
+
+	use hex_literal::hex;
+
+	#[test]
+	fn epoch_parameters() {
+		assert_eq!(cache_size(0), Ok(16_776_896));
+		assert_eq!(dataset_size(0), Ok(1_073_739_904));
+		assert_eq!(seed_hash(0), Ok(H256::zero()));
+		assert_eq!(
+			seed_hash(1),
+			Ok(H256::from(hex!(
+				"290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+			)))
+		);
+
+		let too_high = EthashError::EpochTooHigh {
+			epoch: MAX_EPOCH,
+			max: MAX_EPOCH,
+		};
+		assert_eq!(seed_hash(MAX_EPOCH), Err(too_high.clone()));
+		assert_eq!(cache_size(MAX_EPOCH), Err(too_high.clone()));
+		assert_eq!(dataset_size(MAX_EPOCH), Err(too_high.clone()));
+		assert_eq!(EthashCache::new(MAX_EPOCH), Err(too_high));
+	}
+
+	#[test]
+	fn seal_hash_optional_fields() {
+		// The seal hash covers the header encoding without its mix hash and
+		// nonce items.
+		fn expected(header: &Header) -> H256 {
+			let encoded = rlp::encode(header);
+			let rlp = rlp::Rlp::new(&encoded);
+			let mut s = RlpStream::new_list(rlp.item_count().unwrap() - 2);
+			for (index, item) in rlp.iter().enumerate() {
+				if index != 13 && index != 14 {
+					s.append_raw(item.as_raw(), 1);
+				}
+			}
+			H256::from_slice(Keccak256::digest(s.out()).as_slice())
+		}
+
+		let mut header = mainnet_block_one();
+		assert_eq!(seal_hash(&header), expected(&header));
+
+		header.base_fee_per_gas = Some(1_000_000_000.into());
+		let london = seal_hash(&header);
+		assert_eq!(london, expected(&header));
+		assert_ne!(london, seal_hash(&mainnet_block_one()));
+	}
+
+	#[test]
+	#[ignore = "builds the 16 MB epoch 0 cache"]
+	fn verify_mainnet_block_one() {
+		let header = mainnet_block_one();
+		assert_eq!(
+			header.hash(),
+			H256::from(hex!(
+				"88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6"
+			))
+		);
+
+		let mut ethash = Ethash::default();
+		assert_eq!(ethash.verify_seal(&header), Ok(()));
+
+		let mut invalid = header;
+		invalid.nonce = H64::zero();
+		assert!(matches!(
+			ethash.verify_seal(&invalid),
+			Err(EthashError::MixHashMismatch { .. })
+		));
+
+		invalid.number = U256::MAX;
+		assert_eq!(
+			ethash.verify_seal(&invalid),
+			Err(EthashError::EpochTooHigh {
+				epoch: u64::MAX / EPOCH_LENGTH,
+				max: MAX_EPOCH,
+			})
+		);
+	}
+}
@@ -0,0 +1,538 @@
+//! Consensus rules for Ethereum headers.
+
+#[cfg(feature = "clique")]
+pub mod clique;
+mod difficulty;
+#[cfg(feature = "ethash")]
+pub mod ethash;
+
+use ethereum_types::{H256, H64, U256};
+
+pub use self::difficulty::{
+	calc_difficulty, DifficultyFork, DIFFICULTY_BOUND_DIVISOR, EXP_DIFFICULTY_PERIOD,
+	MINIMUM_DIFFICULTY,
+};
+use crate::{block::EMPTY_OMMERS_HASH, header::Header};
+
+/// Minimum gas limit of a block.
+pub const MIN_GAS_LIMIT: u64 = 5000;
+/// Maximum gas limit of a block.
+pub const MAX_GAS_LIMIT: u64 = 0x7fff_ffff_ffff_ffff;
+/// Bound divisor of the gas limit change between two blocks.
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+/// Gas target elasticity multiplier introduced by EIP-1559.
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+/// Bound divisor of the base fee change between two blocks (EIP-1559).
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+/// Base fee of the first London block (EIP-1559).
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+/// Target blob gas per block since Cancun (EIP-4844).
+pub const TARGET_BLOB_GAS_PER_BLOCK_CANCUN: u64 = 393_216;
+/// Target blob gas per block since Prague (EIP-7691).
+pub const TARGET_BLOB_GAS_PER_BLOCK_PRAGUE: u64 = 786_432;
+/// Maximum size of the header extra data.
+pub const MAX_EXTRA_DATA_SIZE: usize = 32;
+
+/// Fork activation configuration of a chain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChainConfig {
+	/// First block of the London fork, if activated.
+	pub london_block: Option<U256>,
+	/// First proof-of-stake block, if the chain has gone through the merge.
+	pub merge_block: Option<U256>,
+	/// Activation timestamp of the Cancun fork, if scheduled.
+	pub cancun_time: Option<u64>,
+	/// Activation timestamp of the Prague fork, if scheduled.
+	pub prague_time: Option<u64>,
+}
+
+impl ChainConfig {
+	/// Ethereum mainnet configuration.
+	#[must_use]
+	pub fn mainnet() -> Self {
+		Self {
+			london_block: Some(12_965_000.into()),
+			merge_block: Some(15_537_394.into()),
+			cancun_time: Some(1_710_338_135),
+			prague_time: Some(1_746_612_311),
+		}
+	}
+
+	/// Whether the London fork is active at the given block number.
+	#[must_use]
+	pub fn is_london(&self, number: U256) -> bool {
+		self.london_block.is_some_and(|block| number >= block)
+	}
+
+	/// Whether the given block number is a proof-of-stake block.
+	#[must_use]
+	pub fn is_merged(&self, number: U256) -> bool {
+		self.merge_block.is_some_and(|block| number >= block)
+	}
+
+	/// Whether the Cancun fork is active at the given timestamp.
+	#[must_use]
+	pub fn is_cancun(&self, timestamp: u64) -> bool {
+		self.cancun_time.is_some_and(|time| timestamp >= time)
+	}
+
+	/// Whether the Prague fork is active at the given timestamp.
+	#[must_use]
+	pub fn is_prague(&self, timestamp: u64) -> bool {
+		self.prague_time.is_some_and(|time| timestamp >= time)
+	}
+}
+
+/// Error returned when a header is invalid with respect to its parent.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HeaderValidationError {
+	/// Header number is not the parent number plus one.
+	NumberMismatch { expected: U256, found: U256 },
+	/// Header parent hash is not the hash of the parent.
+	ParentHashMismatch { expected: H256, found: H256 },
+	/// Header timestamp is not greater than the parent timestamp.
+	TimestampNotIncreasing { parent: u64, found: u64 },
+	/// Gas limit changed by more than the allowed bound.
+	GasLimitOutOfBounds { parent: U256, found: U256 },
+	/// Gas limit is below the minimum.
+	GasLimitTooLow { min: U256, found: U256 },
+	/// Gas limit is above the maximum.
+	GasLimitTooHigh { max: U256, found: U256 },
+	/// Gas used is greater than the gas limit.
+	GasUsedExceedsGasLimit { gas_used: U256, gas_limit: U256 },
+	/// Extra data is longer than allowed.
+	ExtraDataTooLong { max: usize, found: usize },
+	/// Difficulty of a proof-of-stake header is not zero.
+	NonZeroDifficulty { found: U256 },
+	/// Nonce of a proof-of-stake header is not zero.
+	NonZeroNonce { found: H64 },
+	/// Ommers hash of a proof-of-stake header is not the empty ommers hash.
+	NonEmptyOmmers { found: H256 },
+	/// Base fee is missing from a London header.
+	MissingBaseFee,
+	/// Base fee is set on a pre-London header.
+	UnexpectedBaseFee { found: U256 },
+	/// Base fee is not the one derived from the parent.
+	BaseFeeMismatch { expected: U256, found: U256 },
+	/// Blob gas used or excess blob gas is missing from a Cancun header.
+	MissingBlobGasFields,
+	/// Blob gas used or excess blob gas is set on a pre-Cancun header.
+	UnexpectedBlobGasFields,
+	/// Excess blob gas is not the one derived from the parent.
+	ExcessBlobGasMismatch { expected: u64, found: u64 },
+}
+
+/// Validate a header against its parent header.
+pub fn validate_header(
+	header: &Header,
+	parent: &Header,
+	config: &ChainConfig,
+) -> Result<(), HeaderValidationError> {
+	let expected = parent.number.saturating_add(U256::one());
+	if header.number != expected {
+		return Err(HeaderValidationError::NumberMismatch {
+			expected,
+			found: header.number,
+		});
+	}
+
+	let expected = parent.hash();
+	if header.parent_hash != expected {
+		return Err(HeaderValidationError::ParentHashMismatch {
+			expected,
+			found: header.parent_hash,
+		});
+	}
+
+	if header.timestamp <= parent.timestamp {
+		return Err(HeaderValidationError::TimestampNotIncreasing {
+			parent: parent.timestamp,
+			found: header.timestamp,
+		});
+	}
+
+	if header.extra_data.len() > MAX_EXTRA_DATA_SIZE {
+		return Err(HeaderValidationError::ExtraDataTooLong {
+			max: MAX_EXTRA_DATA_SIZE,
+			found: header.extra_data.len(),
+		});
+	}
+
+	if header.gas_used > header.gas_limit {
+		return Err(HeaderValidationError::GasUsedExceedsGasLimit {
+			gas_used: header.gas_used,
+			gas_limit: header.gas_limit,
+		});
+	}
+
+	validate_gas_limit(header, parent, config)?;
+	validate_base_fee(header, parent, config)?;
+	validate_blob_gas(header, parent, config)?;
+
+	if config.is_merged(header.number) {
+		if !header.difficulty.is_zero() {
+			return Err(HeaderValidationError::NonZeroDifficulty {
+				found: header.difficulty,
+			});
+		}
+
+		if !header.nonce.is_zero() {
+			return Err(HeaderValidationError::NonZeroNonce {
+				found: header.nonce,
+			});
+		}
+
+		if header.ommers_hash != EMPTY_OMMERS_HASH {
+			return Err(HeaderValidationError::NonEmptyOmmers {
+				found: header.ommers_hash,
+			});
+		}
+	}
+
+	Ok(())
+}
+
+fn validate_gas_limit(
+	header: &Header,
+	parent: &Header,
+	config: &ChainConfig,
+) -> Result<(), HeaderValidationError> {
+	if header.gas_limit > U256::from(MAX_GAS_LIMIT) {
+		return Err(HeaderValidationError::GasLimitTooHigh {
+			max: MAX_GAS_LIMIT.into(),
+			found: header.gas_limit,
+		});
+	}
+
+	if header.gas_limit < U256::from(MIN_GAS_LIMIT) {
+		return Err(HeaderValidationError::GasLimitTooLow {
+			min: MIN_GAS_LIMIT.into(),
+			found: header.gas_limit,
+		});
+	}
+
+	// The gas target stays the same at the London fork, so the parent gas
+	// limit is scaled by the elasticity multiplier for the first block.
+	let parent_gas_limit = if config.london_block == Some(header.number) {
+		parent
+			.gas_limit
+			.saturating_mul(ELASTICITY_MULTIPLIER.into())
+	} else {
+		parent.gas_limit
+	};
+
+	let diff = if header.gas_limit > parent_gas_limit {
+		header.gas_limit - parent_gas_limit
+	} else {
+		parent_gas_limit - header.gas_limit
+	};
+	if diff >= parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR {
+		return Err(HeaderValidationError::GasLimitOutOfBounds {
+			parent: parent_gas_limit,
+			found: header.gas_limit,
+		});
+	}
+
+	Ok(())
+}
+
+fn validate_base_fee(
+	header: &Header,
+	parent: &Header,
+	config: &ChainConfig,
+) -> Result<(), HeaderValidationError> {
+	if !config.is_london(header.number) {
+		return match header.base_fee_per_gas {
+			Some(found) => Err(HeaderValidationError::UnexpectedBaseFee { found }),
+			None => Ok(()),
+		};
+	}
+
+	let found = header
+		.base_fee_per_gas
+		.ok_or(HeaderValidationError::MissingBaseFee)?;
+	let expected = calc_base_fee(parent, config);
+	if found != expected {
+		return Err(HeaderValidationError::BaseFeeMismatch { expected, found });
+	}
+
+	Ok(())
+}
+
+fn validate_blob_gas(
+	header: &Header,
+	parent: &Header,
+	config: &ChainConfig,
+) -> Result<(), HeaderValidationError> {
+	if !config.is_cancun(header.timestamp) {
+		if header.blob_gas_used.is_some() || header.excess_blob_gas.is_some() {
+			return Err(HeaderValidationError::UnexpectedBlobGasFields);
+		}
+		return Ok(());
+	}
+
+	let (Some(_), Some(found)) = (header.blob_gas_used, header.excess_blob_gas) else {
+		return Err(HeaderValidationError::MissingBlobGasFields);
+	};
+	let expected = calc_excess_blob_gas(parent, header.timestamp, config);
+	if found != expected {
+		return Err(HeaderValidationError::ExcessBlobGasMismatch { expected, found });
+	}
+
+	Ok(())
+}
+
+/// Compute the base fee of the child of the given parent header (EIP-1559).
+///
+/// The first London block gets the initial base fee, and a pre-London parent
+/// of any other block is treated as having no base fee. A parent whose gas
+/// limit is below the elasticity multiplier has no gas target, and its base
+/// fee is kept.
+#[must_use]
+pub fn calc_base_fee(parent: &Header, config: &ChainConfig) -> U256 {
+	let number = parent.number.saturating_add(U256::one());
+	let parent_base_fee = match parent.base_fee_per_gas {
+		Some(base_fee) if config.london_block != Some(number) => base_fee,
+		_ => return INITIAL_BASE_FEE.into(),
+	};
+
+	let gas_target = parent.gas_limit / ELASTICITY_MULTIPLIER;
+	if gas_target.is_zero() || parent.gas_used == gas_target {
+		return parent_base_fee;
+	}
+
+	let denominator = gas_target.saturating_mul(BASE_FEE_MAX_CHANGE_DENOMINATOR.into());
+	if parent.gas_used > gas_target {
+		let delta = parent_base_fee.saturating_mul(parent.gas_used - gas_target) / denominator;
+		parent_base_fee.saturating_add(delta.max(U256::one()))
+	} else {
+		let delta = parent_base_fee.saturating_mul(gas_target - parent.gas_used) / denominator;
+		parent_base_fee.saturating_sub(delta)
+	}
+}
+
+/// Compute the excess blob gas of a header with the given timestamp, child of
+/// the given parent header (EIP-4844).
+///
+/// Missing blob gas fields of a pre-Cancun parent count as zero.
+#[must_use]
+pub fn calc_excess_blob_gas(parent: &Header, timestamp: u64, config: &ChainConfig) -> u64 {
+	let target = if config.is_prague(timestamp) {
+		TARGET_BLOB_GAS_PER_BLOCK_PRAGUE
+	} else {
+		TARGET_BLOB_GAS_PER_BLOCK_CANCUN
+	};
+
+	parent
+		.excess_blob_gas
+		.unwrap_or_default()
+		.saturating_add(parent.blob_gas_used.unwrap_or_default())
+		.saturating_sub(target)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::header::PartialHeader;
+	use crate::test_utils::partial_header;
+
+	fn parent() -> Header {
+		let partial_header = PartialHeader {
+			number: 20_000_000.into(),
+			gas_used: 15_000_000.into(),
+			timestamp: 1_700_000_000,
+			base_fee_per_gas: Some(10_000_000_000_u64.into()),
+			..partial_header()
+		};
+		Header::new(partial_header, EMPTY_OMMERS_HASH, H256::zero())
+	}
+
+	fn child(parent: &Header) -> Header {
+		let mut header = parent.clone();
+		header.parent_hash = parent.hash();
+		header.number = parent.number + 1;
+		header.timestamp = parent.timestamp + 12;
+		header
+	}
+
+	#[test]
+	fn validate_child_header() {
+		let config = ChainConfig::mainnet();
+		let parent = parent();
+		let header = child(&parent);
+		assert_eq!(validate_header(&header, &parent, &config), Ok(()));
+
+		let mut invalid = header.clone();
+		invalid.number = parent.number;
+		assert!(matches!(
+			validate_header(&invalid, &parent, &config),
+			Err(HeaderValidationError::NumberMismatch { .. })
+		));
+
+		let mut invalid = header.clone();
+		invalid.parent_hash = H256::zero();
+		assert!(matches!(
+			validate_header(&invalid, &parent, &config),
+			Err(HeaderValidationError::ParentHashMismatch { .. })
+		));
+
+		let mut invalid = header.clone();
+		invalid.timestamp = parent.timestamp;
+		assert!(matches!(
+			validate_header(&invalid, &parent, &config),
+			Err(HeaderValidationError::TimestampNotIncreasing { .. })
+		));
+
+		let mut invalid = header.clone();
+		invalid.extra_data = vec![0; 33];
+		assert!(matches!(
+			validate_header(&invalid, &parent, &config),
+			Err(HeaderValidationError::ExtraDataTooLong { .. })
+		));
+
+		let mut invalid = header.clone();
+		invalid.difficulty = U256::one();
+		assert!(matches!(
+			validate_header(&invalid, &parent, &config),
+			Err(HeaderValidationError::NonZeroDifficulty { .. })
+		));
+	}
+
+	#[test]
+	fn validate_gas_limit_bounds() {
+		let config = ChainConfig::mainnet();
+		let parent = parent();
+		let bound = parent.gas_limit / GAS_LIMIT_BOUND_DIVISOR;
+
+		let mut header = child(&parent);
+		header.gas_limit = parent.gas_limit + bound - 1;
+		assert_eq!(validate_header(&header, &parent, &config), Ok(()));
+
+		header.gas_limit = parent.gas_limit + bound;
+		assert!(matches!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::GasLimitOutOfBounds { .. })
+		));
+
+		header.gas_limit = parent.gas_limit - bound;
+		assert!(matches!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::GasLimitOutOfBounds { .. })
+		));
+
+		header.gas_limit = 10_000_000.into();
+		header.gas_used = header.gas_limit + 1;
+		assert!(matches!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::GasUsedExceedsGasLimit { .. })
+		));
+	}
+
+	#[test]
+	fn validate_base_fee() {
+		let config = ChainConfig::mainnet();
+		let mut parent = parent();
+		let base_fee = U256::from(10_000_000_000_u64);
+
+		// Gas used at the target keeps the base fee.
+		assert_eq!(calc_base_fee(&parent, &config), base_fee);
+
+		parent.gas_used = parent.gas_limit;
+		assert_eq!(calc_base_fee(&parent, &config), base_fee * 9 / 8);
+		parent.gas_used = U256::zero();
+		assert_eq!(calc_base_fee(&parent, &config), base_fee * 7 / 8);
+
+		// Smallest increase is one wei.
+		parent.base_fee_per_gas = Some(7.into());
+		parent.gas_used = parent.gas_limit / 2 + 1;
+		assert_eq!(calc_base_fee(&parent, &config), 8.into());
+
+		// A gas limit below the elasticity multiplier leaves no gas target.
+		let mut small = parent.clone();
+		small.gas_limit = U256::one();
+		small.gas_used = U256::one();
+		assert_eq!(calc_base_fee(&small, &config), 7.into());
+
+		let mut header = child(&parent);
+		header.base_fee_per_gas = Some(8.into());
+		assert_eq!(validate_header(&header, &parent, &config), Ok(()));
+
+		header.base_fee_per_gas = Some(7.into());
+		assert_eq!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::BaseFeeMismatch {
+				expected: 8.into(),
+				found: 7.into(),
+			})
+		);
+
+		header.base_fee_per_gas = None;
+		assert_eq!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::MissingBaseFee)
+		);
+
+		// First London block.
+		let config = ChainConfig {
+			london_block: Some(header.number),
+			..ChainConfig::mainnet()
+		};
+		parent.base_fee_per_gas = None;
+		assert_eq!(calc_base_fee(&parent, &config), INITIAL_BASE_FEE.into());
+
+		let config = ChainConfig::default();
+		let mut header = child(&parent);
+		header.base_fee_per_gas = Some(INITIAL_BASE_FEE.into());
+		assert_eq!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::UnexpectedBaseFee {
+				found: INITIAL_BASE_FEE.into(),
+			})
+		);
+	}
+
+	#[test]
+	fn validate_excess_blob_gas() {
+		let config = ChainConfig::mainnet();
+		let cancun = config.cancun_time.unwrap();
+		let mut parent = parent();
+		parent.timestamp = cancun;
+		parent.withdrawals_root = Some(H256::zero());
+		parent.blob_gas_used = Some(6 * 131_072);
+		parent.excess_blob_gas = Some(100);
+
+		let mut header = child(&parent);
+		header.excess_blob_gas = None;
+		assert_eq!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::MissingBlobGasFields)
+		);
+
+		let expected = 100 + 6 * 131_072 - TARGET_BLOB_GAS_PER_BLOCK_CANCUN;
+		header.blob_gas_used = Some(0);
+		header.excess_blob_gas = Some(expected);
+		assert_eq!(validate_header(&header, &parent, &config), Ok(()));
+
+		header.excess_blob_gas = Some(0);
+		assert_eq!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::ExcessBlobGasMismatch { expected, found: 0 })
+		);
+
+		// Prague raises the target, and the excess never goes below zero.
+		let prague = config.prague_time.unwrap();
+		assert_eq!(calc_excess_blob_gas(&parent, prague, &config), 100);
+		let mut small = parent.clone();
+		small.blob_gas_used = Some(131_072);
+		assert_eq!(calc_excess_blob_gas(&small, prague, &config), 0);
+
+		let config = ChainConfig {
+			cancun_time: None,
+			..config
+		};
+		assert_eq!(
+			validate_header(&header, &parent, &config),
+			Err(HeaderValidationError::UnexpectedBlobGasFields)
+		);
+	}
+}
@@ -0,0 +1,527 @@
+//! Decoding with errors locating the failing item.
+//!
+//! Decoding goes through the regular `rlp::Decodable` implementations. Only
+//! when these fail is the input walked again, field by field, to find out
+//! where.
+
+use core::ops::RangeInclusive;
+
+use rlp::{Decodable, DecoderError, Rlp};
+
+use crate::{
+	block::Block,
+	enveloped::{EnvelopedDecodable, EnvelopedDecoderError},
+	header::{Header, HEADER_FIELDS, HEADER_OPTIONAL_FIELDS},
+	transaction::{AccessListItem, TransactionAction, TransactionAny},
+	withdrawal::Withdrawal,
+	Bytes,
+};
+use ethereum_types::{Bloom, H160, H256, H64, U256};
+
+/// Error returned when decoding a block or transaction, along with where it
+/// happened.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodeError {
+	/// Index of the failing transaction in the block.
+	pub transaction_index: Option<usize>,
+	/// Type byte of the failing transaction, `None` for legacy transactions.
+	pub type_id: Option<u8>,
+	/// Name of the failing field, e.g. `EIP1559Transaction.max_fee_per_gas`.
+	pub field: Option<&'static str>,
+	/// Offset in the input of the failing item.
+	pub offset: usize,
+	/// Underlying error.
+	pub error: DecoderError,
+}
+
+impl DecodeError {
+	fn new(offset: usize, error: DecoderError) -> Self {
+		Self {
+			transaction_index: None,
+			type_id: None,
+			field: None,
+			offset,
+			error,
+		}
+	}
+}
+
+/// Decode an RLP encoded block, locating the failing item on error.
+pub fn decode_block(bytes: &[u8]) -> Result<Block<TransactionAny>, DecodeError> {
+	rlp::decode(bytes).map_err(|error| locate_block(bytes).unwrap_or(DecodeError::new(0, error)))
+}
+
+/// Decode an enveloped transaction, locating the failing field on error.
+pub fn decode_transaction(bytes: &[u8]) -> Result<TransactionAny, DecodeError> {
+	TransactionAny::decode(bytes).map_err(|_| locate_transaction(bytes, bytes))
+}
+
+type Check = fn(&Rlp) -> Result<(), DecoderError>;
+
+fn check<T: Decodable>(rlp: &Rlp) -> Result<(), DecoderError> {
+	rlp.as_val::<T>().map(drop)
+}
+
+fn check_list<T: Decodable>(rlp: &Rlp) -> Result<(), DecoderError> {
+	rlp.as_list::<T>().map(drop)
+}
+
+macro_rules! fields {
+	($name:literal { $($field:ident: $check:ident<$ty:ty>),* $(,)? }) => {
+		&[$((concat!($name, ".", stringify!($field)), $check::<$ty> as Check)),*]
+	};
+}
+
+const HEADER: &[(&str, Check)] = fields!("Header" {
+	parent_hash: check<H256>,
+	ommers_hash: check<H256>,
+	beneficiary: check<H160>,
+	state_root: check<H256>,
+	transactions_root: check<H256>,
+	receipts_root: check<H256>,
+	logs_bloom: check<Bloom>,
+	difficulty: check<U256>,
+	number: check<U256>,
+	gas_limit: check<U256>,
+	gas_used: check<U256>,
+	timestamp: check<u64>,
+	extra_data: check<Bytes>,
+	mix_hash: check<H256>,
+	nonce: check<H64>,
+	base_fee_per_gas: check<U256>,
+	withdrawals_root: check<H256>,
+	blob_gas_used: check<u64>,
+	excess_blob_gas: check<u64>,
+	parent_beacon_block_root: check<H256>,
+	requests_hash: check<H256>,
+});
+
+/// Number of items of a header, as accepted by its decoder.
+const HEADER_ITEMS: RangeInclusive<usize> = HEADER_FIELDS..=HEADER_FIELDS + HEADER_OPTIONAL_FIELDS;
+
+/// Number of items of a block, the last one being the optional withdrawals.
+const BLOCK_ITEMS: RangeInclusive<usize> = 3..=4;
+
+const LEGACY_TRANSACTION: &[(&str, Check)] = fields!("LegacyTransaction" {
+	nonce: check<U256>,
+	gas_price: check<U256>,
+	gas_limit: check<U256>,
+	action: check<TransactionAction>,
+	value: check<U256>,
+	input: check<Bytes>,
+	v: check<u64>,
+	r: check<U256>,
+	s: check<U256>,
+});
+
+const EIP2930_TRANSACTION: &[(&str, Check)] = fields!("EIP2930Transaction" {
+	chain_id: check<u64>,
+	nonce: check<U256>,
+	gas_price: check<U256>,
+	gas_limit: check<U256>,
+	action: check<TransactionAction>,
+	value: check<U256>,
+	input: check<Bytes>,
+	access_list: check_list<AccessListItem>,
+	odd_y_parity: check<bool>,
+	r: check<U256>,
+	s: check<U256>,
+});
+
+const EIP1559_TRANSACTION: &[(&str, Check)] = fields!("EIP1559Transaction" {
+	chain_id: check<u64>,
+	nonce: check<U256>,
+	max_priority_fee_per_gas: check<U256>,
+	max_fee_per_gas: check<U256>,
+	gas_limit: check<U256>,
+	action: check<TransactionAction>,
+	value: check<U256>,
+	input: check<Bytes>,
+	access_list: check_list<AccessListItem>,
+	odd_y_parity: check<bool>,
+	r: check<U256>,
+	s: check<U256>,
+});
+
+/// Offset of `item` in `base`, which must contain it.
+fn offset(base: &[u8], item: &[u8]) -> usize {
+	item.as_ptr() as usize - base.as_ptr() as usize
+}
+
+/// Find the first field of a list that fails to decode.
+///
+/// The list must have a number of items in `items`, which are checked against
+/// the leading `fields`.
+fn locate_fields(
+	base: &[u8],
+	rlp: &Rlp,
+	name: &'static str,
+	fields: &[(&'static str, Check)],
+	items: RangeInclusive<usize>,
+) -> Option<DecodeError> {
+	let at = |offset, field, error| DecodeError {
+		field,
+		..DecodeError::new(offset, error)
+	};
+	let list_offset = offset(base, rlp.as_raw());
+
+	let count = match rlp.item_count() {
+		Ok(count) if items.contains(&count) => count,
+		Ok(_) => {
+			return Some(at(
+				list_offset,
+				Some(name),
+				DecoderError::RlpIncorrectListLen,
+			))
+		}
+		Err(error) => return Some(at(list_offset, Some(name), error)),
+	};
+
+	for (index, (field, check)) in fields[..count].iter().enumerate() {
+		let item = match rlp.at(index) {
+			Ok(item) => item,
+			Err(error) => return Some(at(list_offset, Some(field), error)),
+		};
+		if let Err(error) = check(&item) {
+			return Some(at(offset(base, item.as_raw()), Some(field), error));
+		}
+	}
+
+	None
+}
+
+fn locate_transaction(base: &[u8], bytes: &[u8]) -> DecodeError {
+	let (type_id, payload, name, fields) = match bytes.first() {
+		None => return DecodeError::new(offset(base, bytes), DecoderError::RlpIsTooShort),
+		Some(0xc0..) => (None, bytes, "LegacyTransaction", LEGACY_TRANSACTION),
+		Some(0x01) => (
+			Some(1),
+			&bytes[1..],
+			"EIP2930Transaction",
+			EIP2930_TRANSACTION,
+		),
+		Some(0x02) => (
+			Some(2),
+			&bytes[1..],
+			"EIP1559Transaction",
+			EIP1559_TRANSACTION,
+		),
+		Some(type_id) => {
+			return DecodeError {
+				type_id: Some(*type_id),
+				..DecodeError::new(offset(base, bytes), DecoderError::Custom("invalid tx type"))
+			}
+		}
+	};
+
+	// Fields decoding fine means the transaction failed as a whole, e.g. on an
+	// invalid signature.
+	let items = fields.len()..=fields.len();
+	let error = locate_fields(base, &Rlp::new(payload), name, fields, items).unwrap_or_else(|| {
+		let error = TransactionAny::decode(bytes)
+			.err()
+			.and_then(|e| match e {
+				EnvelopedDecoderError::Payload(e) => Some(e),
+				EnvelopedDecoderError::UnknownTypeId => None,
+			})
+			.unwrap_or(DecoderError::Custom("invalid tx type"));
+		DecodeError {
+			field: Some(name),
+			..DecodeError::new(offset(base, bytes), error)
+		}
+	});
+
+	DecodeError { type_id, ..error }
+}
+
+fn locate_block(bytes: &[u8]) -> Option<DecodeError> {
+	let rlp = Rlp::new(bytes);
+	let block = |error| DecodeError {
+		field: Some("Block"),
+		..DecodeError::new(0, error)
+	};
+	let item = |index| rlp.at(index).map_err(block);
+
+	let count = rlp.item_count().map_err(block).ok()?;
+	if !BLOCK_ITEMS.contains(&count) {
+		return Some(block(DecoderError::RlpIncorrectListLen));
+	}
+
+	let header = match item(0) {
+		Ok(header) => header,
+		Err(e) => return Some(e),
+	};
+	if let Some(e) = locate_fields(bytes, &header, "Header", HEADER, HEADER_ITEMS) {
+		return Some(e);
+	}
+
+	let transactions = match item(1) {
+		Ok(transactions) => transactions,
+		Err(e) => return Some(e),
+	};
+	if !transactions.is_list() {
+		return Some(DecodeError {
+			field: Some("Block.transactions"),
+			..DecodeError::new(
+				offset(bytes, transactions.as_raw()),
+				DecoderError::RlpExpectedToBeList,
+			)
+		});
+	}
+	for (index, transaction) in transactions.iter().enumerate() {
+		let enveloped = if transaction.is_list() {
+			Ok(transaction.as_raw())
+		} else {
+			transaction.data()
+		};
+		let error = match enveloped {
+			Ok(enveloped) if TransactionAny::decode(enveloped).is_ok() => continue,
+			Ok(enveloped) => locate_transaction(bytes, enveloped),
+			Err(error) => DecodeError::new(offset(bytes, transaction.as_raw()), error),
+		};
+		return Some(DecodeError {
+			transaction_index: Some(index),
+			..error
+		});
+	}
+
+	let ommers = match item(2) {
+		Ok(ommers) => ommers,
+		Err(e) => return Some(e),
+	};
+	if let Err(error) = ommers.as_list::<Header>() {
+		for ommer in ommers.iter() {
+			if let Some(e) = locate_fields(bytes, &ommer, "Header", HEADER, HEADER_ITEMS) {
+				return Some(e);
+			}
+		}
+		return Some(DecodeError {
+			field: Some("Block.ommers"),
+			..DecodeError::new(offset(bytes, ommers.as_raw()), error)
+		});
+	}
+
+	if count == 4 {
+		let withdrawals = match item(3) {
+			Ok(withdrawals) => withdrawals,
+			Err(e) => return Some(e),
+		};
+		if let Err(error) = withdrawals.as_list::<Withdrawal>() {
+			return Some(DecodeError {
+				field: Some("Block.withdrawals"),
+				..DecodeError::new(offset(bytes, withdrawals.as_raw()), error)
+			});
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::{
+		header::PartialHeader,
+		transaction::{EIP1559Transaction, LegacyTransaction, TransactionSignature},
+	};
+	use rlp::RlpStream;
+
+	fn eip1559() -> EIP1559Transaction {
+		EIP1559Transaction {
+			chain_id: 1,
+			nonce: 1.into(),
+			max_priority_fee_per_gas: 1.into(),
+			max_fee_per_gas: 2.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: 0.into(),
+			input: Vec::new(),
+			access_list: Vec::new(),
+			odd_y_parity: false,
+			r: H256::repeat_byte(1),
+			s: H256::repeat_byte(2),
+		}
+	}
+
+	/// EIP-1559 transaction with a 33 bytes max fee per gas.
+	fn bad_eip1559() -> Vec<u8> {
+		let tx = eip1559();
+		let mut s = RlpStream::new_list(12);
+		s.append(&tx.chain_id);
+		s.append(&tx.nonce);
+		s.append(&tx.max_priority_fee_per_gas);
+		s.append(&[0xff_u8; 33].as_slice());
+		s.append(&tx.gas_limit);
+		s.append(&tx.action);
+		s.append(&tx.value);
+		s.append(&tx.input);
+		s.append_list(&tx.access_list);
+		s.append(&tx.odd_y_parity);
+		s.append(&U256::from_big_endian(&tx.r[..]));
+		s.append(&U256::from_big_endian(&tx.s[..]));
+
+		let mut out = vec![2];
+		out.extend_from_slice(&s.out());
+		out
+	}
+
+	#[test]
+	fn locate_transaction_field() {
+		let bytes = bad_eip1559();
+		let err = decode_transaction(&bytes).unwrap_err();
+		assert_eq!(
+			err,
+			DecodeError {
+				transaction_index: None,
+				type_id: Some(2),
+				field: Some("EIP1559Transaction.max_fee_per_gas"),
+				offset: 6,
+				error: DecoderError::RlpIsTooBig,
+			}
+		);
+
+		let err = decode_transaction(&[0x05, 0xc0]).unwrap_err();
+		assert_eq!(err.type_id, Some(5));
+
+		let mut bytes = bytes;
+		bytes[1] += 1;
+		let err = decode_transaction(&bytes).unwrap_err();
+		assert_eq!(err.field, Some("EIP1559Transaction"));
+		assert_eq!(err.offset, 1);
+	}
+
+	#[test]
+	fn locate_block_transaction() {
+		let header = Header::new(partial_header(), H256::zero(), H256::zero());
+		let legacy = LegacyTransaction {
+			nonce: 0.into(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: 0.into(),
+			input: Vec::new(),
+			signature: TransactionSignature::new(27, H256::repeat_byte(1), H256::repeat_byte(2))
+				.unwrap(),
+		};
+		let bad = bad_eip1559();
+
+		let mut s = RlpStream::new_list(3);
+		s.append(&header);
+		s.begin_list(2);
+		s.append(&legacy);
+		s.append(&bad.as_slice());
+		s.begin_list(0);
+		let bytes = s.out();
+
+		let err = decode_block(&bytes).unwrap_err();
+		assert_eq!(err.transaction_index, Some(1));
+		assert_eq!(err.type_id, Some(2));
+		assert_eq!(err.field, Some("EIP1559Transaction.max_fee_per_gas"));
+		assert_eq!(&bytes[err.offset..err.offset + 2], &[0xa1, 0xff]);
+
+		let mut bytes = bytes.to_vec();
+		let number = offset(
+			&bytes,
+			Rlp::new(&bytes).at(0).unwrap().at(8).unwrap().as_raw(),
+		);
+		assert_eq!(bytes[number], 0x01);
+		bytes[number] = 0x00;
+		let err = decode_block(&bytes).unwrap_err();
+		assert_eq!(err.transaction_index, None);
+		assert_eq!(err.field, Some("Header.number"));
+		assert_eq!(err.error, DecoderError::RlpInvalidIndirection);
+	}
+
+	#[test]
+	fn locate_in_block_with_optional_fields() {
+		let mut header = Header::new(
+			PartialHeader {
+				base_fee_per_gas: Some(1.into()),
+				..partial_header()
+			},
+			H256::zero(),
+			H256::zero(),
+		);
+		header.withdrawals_root = Some(H256::zero());
+		let withdrawals = vec![Withdrawal {
+			index: 1,
+			validator_index: 2,
+			address: H160::repeat_byte(3),
+			amount: 4,
+		}];
+
+		let mut s = RlpStream::new_list(4);
+		s.append(&header);
+		s.begin_list(1);
+		s.append(&bad_eip1559().as_slice());
+		s.begin_list(0);
+		s.append_list(&withdrawals);
+		let bytes = s.out().to_vec();
+
+		let err = decode_block(&bytes).unwrap_err();
+		assert_eq!(err.transaction_index, Some(0));
+		assert_eq!(err.field, Some("EIP1559Transaction.max_fee_per_gas"));
+
+		let item_offset = |bytes: &[u8], item: usize, field: usize| {
+			offset(
+				bytes,
+				Rlp::new(bytes)
+					.at(item)
+					.unwrap()
+					.at(field)
+					.unwrap()
+					.as_raw(),
+			)
+		};
+		let mut s = RlpStream::new_list(4);
+		s.append(&header);
+		s.begin_list(0);
+		s.begin_list(0);
+		s.append_list(&withdrawals);
+		let valid = s.out().to_vec();
+		assert!(decode_block(&valid).is_ok());
+
+		let mut bytes = valid.clone();
+		let base_fee = item_offset(&bytes, 0, 15);
+		assert_eq!(bytes[base_fee], 0x01);
+		bytes[base_fee] = 0x00;
+		let err = decode_block(&bytes).unwrap_err();
+		assert_eq!(err.field, Some("Header.base_fee_per_gas"));
+		assert_eq!(err.offset, base_fee);
+
+		let mut bytes = valid;
+		let withdrawal = item_offset(&bytes, 3, 0);
+		assert_eq!(bytes[withdrawal + 1], 0x01);
+		bytes[withdrawal + 1] = 0x00;
+		let err = decode_block(&bytes).unwrap_err();
+		assert_eq!(err.field, Some("Block.withdrawals"));
+	}
+
+	#[test]
+	fn reject_header_item_count() {
+		let header = rlp::encode(&Header::new(partial_header(), H256::zero(), H256::zero()));
+		let header = Rlp::new(&header);
+
+		for items in [
+			HEADER_FIELDS - 1,
+			HEADER_FIELDS + HEADER_OPTIONAL_FIELDS + 1,
+		] {
+			let mut s = RlpStream::new_list(3);
+			s.begin_list(items);
+			for index in 0..items {
+				match header.at(index) {
+					Ok(field) => s.append_raw(field.as_raw(), 1),
+					Err(_) => s.append_empty_data(),
+				};
+			}
+			s.begin_list(0);
+			s.begin_list(0);
+
+			let err = decode_block(&s.out()).unwrap_err();
+			assert_eq!(err.field, Some("Header"));
+			assert_eq!(err.offset, 3);
+			assert_eq!(err.error, DecoderError::RlpIncorrectListLen);
+		}
+	}
+}
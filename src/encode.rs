@@ -0,0 +1,611 @@
+//! RLP encoding with lengths computed upfront, written to any buffer.
+//!
+//! This mirrors the `rlp::Encodable` implementations of the crate, and is
+//! checked against them in tests.
+
+use alloc::vec::Vec;
+
+use bytes::BufMut;
+use ethereum_types::{Bloom, H160, H256, H64, U256};
+
+use crate::{
+	block::Block,
+	enveloped::EnvelopedEncodable,
+	header::{Header, HEADER_FIELDS, HEADER_OPTIONAL_FIELDS},
+	log::Log,
+	receipt::{EIP658ReceiptData, FrontierReceiptData},
+	transaction::{
+		AccessListItem, EIP1559Transaction, EIP2930Transaction, LegacyTransaction,
+		TransactionAction,
+	},
+	withdrawal::Withdrawal,
+};
+
+/// Value with an RLP encoding of known length.
+pub(crate) trait EncodeTo {
+	/// Length of the RLP encoding.
+	fn encoded_len(&self) -> usize;
+
+	/// Write the RLP encoding to a buffer.
+	fn encode_to(&self, out: &mut dyn BufMut);
+}
+
+/// Value encoded as an RLP list of its fields.
+trait EncodeFields {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R;
+}
+
+impl<T: EncodeFields> EncodeTo for T {
+	fn encoded_len(&self) -> usize {
+		self.with_fields(|fields| item_len(fields.iter().map(|field| field.encoded_len()).sum()))
+	}
+
+	fn encode_to(&self, out: &mut dyn BufMut) {
+		self.with_fields(|fields| {
+			put_header(
+				out,
+				0xc0,
+				fields.iter().map(|field| field.encoded_len()).sum(),
+			);
+			for field in fields {
+				field.encode_to(out);
+			}
+		})
+	}
+}
+
+/// Number of bytes of the big endian encoding of a non-zero value.
+fn be_len(value: u64) -> usize {
+	8 - value.leading_zeros() as usize / 8
+}
+
+/// Length of a string or list with the given payload length.
+fn item_len(payload_len: usize) -> usize {
+	if payload_len < 56 {
+		1 + payload_len
+	} else {
+		1 + be_len(payload_len as u64) + payload_len
+	}
+}
+
+/// Write a string header, with `offset` 0x80, or a list header, with `offset`
+/// 0xc0.
+fn put_header(out: &mut dyn BufMut, offset: u8, payload_len: usize) {
+	if payload_len < 56 {
+		out.put_u8(offset + payload_len as u8);
+	} else {
+		let len = be_len(payload_len as u64);
+		out.put_u8(offset + 55 + len as u8);
+		out.put_slice(&(payload_len as u64).to_be_bytes()[8 - len..]);
+	}
+}
+
+fn bytes_len(bytes: &[u8]) -> usize {
+	match bytes {
+		[byte] if *byte < 0x80 => 1,
+		_ => item_len(bytes.len()),
+	}
+}
+
+fn put_bytes(out: &mut dyn BufMut, bytes: &[u8]) {
+	match bytes {
+		[byte] if *byte < 0x80 => out.put_u8(*byte),
+		_ => {
+			put_header(out, 0x80, bytes.len());
+			out.put_slice(bytes);
+		}
+	}
+}
+
+impl EncodeTo for Vec<u8> {
+	fn encoded_len(&self) -> usize {
+		bytes_len(self)
+	}
+
+	fn encode_to(&self, out: &mut dyn BufMut) {
+		put_bytes(out, self);
+	}
+}
+
+macro_rules! impl_encode_to_uint {
+	($($ty:ty),*) => {
+		$(
+			impl EncodeTo for $ty {
+				fn encoded_len(&self) -> usize {
+					U256::from(*self).encoded_len()
+				}
+
+				fn encode_to(&self, out: &mut dyn BufMut) {
+					U256::from(*self).encode_to(out)
+				}
+			}
+		)*
+	};
+}
+
+impl_encode_to_uint!(u8, u64);
+
+impl EncodeTo for bool {
+	fn encoded_len(&self) -> usize {
+		1
+	}
+
+	fn encode_to(&self, out: &mut dyn BufMut) {
+		out.put_u8(if *self { 0x01 } else { 0x80 });
+	}
+}
+
+impl EncodeTo for U256 {
+	fn encoded_len(&self) -> usize {
+		match self.bits() {
+			0..=7 => 1,
+			bits => 1 + bits.div_ceil(8),
+		}
+	}
+
+	fn encode_to(&self, out: &mut dyn BufMut) {
+		let mut bytes = [0; 32];
+		self.to_big_endian(&mut bytes);
+		put_bytes(out, &bytes[32 - self.bits().div_ceil(8)..]);
+	}
+}
+
+macro_rules! impl_encode_to_hash {
+	($($ty:ty),*) => {
+		$(
+			impl EncodeTo for $ty {
+				fn encoded_len(&self) -> usize {
+					bytes_len(self.as_bytes())
+				}
+
+				fn encode_to(&self, out: &mut dyn BufMut) {
+					put_bytes(out, self.as_bytes())
+				}
+			}
+		)*
+	};
+}
+
+impl_encode_to_hash!(H64, H160, H256, Bloom);
+
+impl EncodeTo for TransactionAction {
+	fn encoded_len(&self) -> usize {
+		match self {
+			Self::Call(address) => address.encoded_len(),
+			Self::Create => 1,
+		}
+	}
+
+	fn encode_to(&self, out: &mut dyn BufMut) {
+		match self {
+			Self::Call(address) => address.encode_to(out),
+			Self::Create => out.put_u8(0x80),
+		}
+	}
+}
+
+/// RLP list of items.
+pub(crate) struct List<'a, T>(pub &'a [T]);
+
+impl<T: EncodeTo> EncodeTo for List<'_, T> {
+	fn encoded_len(&self) -> usize {
+		item_len(self.0.iter().map(EncodeTo::encoded_len).sum())
+	}
+
+	fn encode_to(&self, out: &mut dyn BufMut) {
+		put_header(out, 0xc0, self.0.iter().map(EncodeTo::encoded_len).sum());
+		for item in self.0 {
+			item.encode_to(out);
+		}
+	}
+}
+
+/// RLP list of items in their network form, as appended by
+/// [`crate::append_enveloped_list`].
+pub(crate) struct EnvelopedList<'a, T>(pub &'a [T]);
+
+fn enveloped_network_len<T: EnvelopedEncodable>(item: &T) -> usize {
+	if item.type_id().is_some() {
+		item_len(item.encoded_len())
+	} else {
+		item.payload_len()
+	}
+}
+
+impl<T: EnvelopedEncodable> EncodeTo for EnvelopedList<'_, T> {
+	fn encoded_len(&self) -> usize {
+		item_len(self.0.iter().map(enveloped_network_len).sum())
+	}
+
+	fn encode_to(&self, mut out: &mut dyn BufMut) {
+		put_header(out, 0xc0, self.0.iter().map(enveloped_network_len).sum());
+		for item in self.0 {
+			if item.type_id().is_some() {
+				put_header(out, 0x80, item.encoded_len());
+			}
+			item.encode_to(&mut out);
+		}
+	}
+}
+
+impl EncodeFields for Header {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		let fields: [&dyn EncodeTo; HEADER_FIELDS + HEADER_OPTIONAL_FIELDS] = [
+			&self.parent_hash,
+			&self.ommers_hash,
+			&self.beneficiary,
+			&self.state_root,
+			&self.transactions_root,
+			&self.receipts_root,
+			&self.logs_bloom,
+			&self.difficulty,
+			&self.number,
+			&self.gas_limit,
+			&self.gas_used,
+			&self.timestamp,
+			&self.extra_data,
+			&self.mix_hash,
+			&self.nonce,
+			&self.base_fee_per_gas.unwrap_or_default(),
+			&self.withdrawals_root.unwrap_or_default(),
+			&self.blob_gas_used.unwrap_or_default(),
+			&self.excess_blob_gas.unwrap_or_default(),
+			&self.parent_beacon_block_root.unwrap_or_default(),
+			&self.requests_hash.unwrap_or_default(),
+		];
+		f(&fields[..HEADER_FIELDS + self.optional_fields()])
+	}
+}
+
+impl EncodeFields for Log {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[&self.address, &List(&self.topics), &self.data])
+	}
+}
+
+impl EncodeFields for AccessListItem {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[&self.address, &List(&self.storage_keys)])
+	}
+}
+
+impl EncodeFields for LegacyTransaction {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[
+			&self.nonce,
+			&self.gas_price,
+			&self.gas_limit,
+			&self.action,
+			&self.value,
+			&self.input,
+			&self.signature.v(),
+			&U256::from_big_endian(&self.signature.r()[..]),
+			&U256::from_big_endian(&self.signature.s()[..]),
+		])
+	}
+}
+
+impl EncodeFields for EIP2930Transaction {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[
+			&self.chain_id,
+			&self.nonce,
+			&self.gas_price,
+			&self.gas_limit,
+			&self.action,
+			&self.value,
+			&self.input,
+			&List(&self.access_list),
+			&self.odd_y_parity,
+			&U256::from_big_endian(&self.r[..]),
+			&U256::from_big_endian(&self.s[..]),
+		])
+	}
+}
+
+impl EncodeFields for EIP1559Transaction {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[
+			&self.chain_id,
+			&self.nonce,
+			&self.max_priority_fee_per_gas,
+			&self.max_fee_per_gas,
+			&self.gas_limit,
+			&self.action,
+			&self.value,
+			&self.input,
+			&List(&self.access_list),
+			&self.odd_y_parity,
+			&U256::from_big_endian(&self.r[..]),
+			&U256::from_big_endian(&self.s[..]),
+		])
+	}
+}
+
+impl EncodeFields for FrontierReceiptData {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[
+			&self.state_root,
+			&self.used_gas,
+			&self.logs_bloom,
+			&List(&self.logs),
+		])
+	}
+}
+
+impl EncodeFields for EIP658ReceiptData {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[
+			&self.status_code,
+			&self.used_gas,
+			&self.logs_bloom,
+			&List(&self.logs),
+		])
+	}
+}
+
+impl EncodeFields for Withdrawal {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		f(&[
+			&self.index,
+			&self.validator_index,
+			&self.address,
+			&self.amount,
+		])
+	}
+}
+
+impl<T: EnvelopedEncodable> EncodeFields for Block<T> {
+	fn with_fields<R>(&self, f: impl FnOnce(&[&dyn EncodeTo]) -> R) -> R {
+		let fields: [&dyn EncodeTo; 4] = [
+			&self.header,
+			&EnvelopedList(&self.transactions),
+			&List(&self.ommers),
+			&List(self.withdrawals.as_deref().unwrap_or_default()),
+		];
+		f(&fields[..3 + usize::from(self.withdrawals.is_some())])
+	}
+}
+
+macro_rules! impl_encoded_len {
+	($($ty:ty),*) => {
+		$(
+			impl $ty {
+				/// Length of the RLP encoding, computed without encoding.
+				#[must_use]
+				pub fn encoded_len(&self) -> usize {
+					EncodeTo::encoded_len(self)
+				}
+
+				/// Write the RLP encoding to a buffer.
+				pub fn encode_to(&self, out: &mut impl BufMut) {
+					EncodeTo::encode_to(self, out)
+				}
+			}
+		)*
+	};
+}
+
+impl_encoded_len!(
+	Header,
+	Log,
+	AccessListItem,
+	LegacyTransaction,
+	EIP2930Transaction,
+	EIP1559Transaction,
+	FrontierReceiptData,
+	EIP658ReceiptData
+);
+
+impl<T: EnvelopedEncodable> Block<T> {
+	/// Length of the RLP encoding, computed without encoding.
+	#[must_use]
+	pub fn encoded_len(&self) -> usize {
+		EncodeTo::encoded_len(self)
+	}
+
+	/// Write the RLP encoding to a buffer.
+	pub fn encode_to(&self, out: &mut impl BufMut) {
+		EncodeTo::encode_to(self, out)
+	}
+}
+
+/// Payload of an [`crate::enveloped_enum!`] variant.
+///
+/// The macro calls the methods below through a reference to this wrapper, so
+/// that [`EncodePayloadTo`] is picked for the payload types of the crate and
+/// [`EncodePayloadRlp`], which encodes into a temporary buffer, for any other
+/// RLP encodable type.
+#[doc(hidden)]
+pub struct Payload<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait EncodePayloadTo {
+	fn payload_len(&self) -> usize;
+	fn encode_payload_to(&self, out: &mut dyn BufMut);
+}
+
+impl<T: EncodeTo> EncodePayloadTo for Payload<'_, T> {
+	fn payload_len(&self) -> usize {
+		self.0.encoded_len()
+	}
+
+	fn encode_payload_to(&self, out: &mut dyn BufMut) {
+		self.0.encode_to(out)
+	}
+}
+
+#[doc(hidden)]
+pub trait EncodePayloadRlp {
+	fn payload_len(&self) -> usize;
+	fn encode_payload_to(&self, out: &mut dyn BufMut);
+}
+
+impl<T: rlp::Encodable> EncodePayloadRlp for &Payload<'_, T> {
+	fn payload_len(&self) -> usize {
+		rlp::encode(self.0).len()
+	}
+
+	fn encode_payload_to(&self, out: &mut dyn BufMut) {
+		out.put_slice(&rlp::encode(self.0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::{
+		header::PartialHeader,
+		receipt::{ReceiptAny, ReceiptV3},
+		transaction::{TransactionAny, TransactionSignature, TransactionV1},
+	};
+	use bytes::BytesMut;
+
+	fn check<T: rlp::Encodable>(value: &T, encoded_len: usize, encode_to: impl Fn(&mut BytesMut)) {
+		let expected = rlp::encode(value);
+		let mut out = BytesMut::new();
+		encode_to(&mut out);
+		assert_eq!(out, expected);
+		assert_eq!(encoded_len, expected.len());
+	}
+
+	fn check_enveloped<T: EnvelopedEncodable>(value: &T, expected: &[u8]) {
+		let mut out = BytesMut::new();
+		value.encode_to(&mut out);
+		assert_eq!(&out[..], expected);
+		assert_eq!(value.encoded_len(), expected.len());
+	}
+
+	fn logs() -> Vec<Log> {
+		vec![
+			Log {
+				address: H160::repeat_byte(1),
+				topics: vec![H256::repeat_byte(2); 3],
+				data: vec![0xff; 100],
+			},
+			Log {
+				address: H160::zero(),
+				topics: Vec::new(),
+				data: vec![0x01],
+			},
+		]
+	}
+
+	fn transactions() -> Vec<TransactionAny> {
+		let access_list = vec![AccessListItem {
+			address: H160::repeat_byte(3),
+			storage_keys: vec![H256::zero(), H256::repeat_byte(4)],
+		}];
+		vec![
+			TransactionAny::Legacy(LegacyTransaction {
+				nonce: U256::zero(),
+				gas_price: U256::from(u64::MAX) + 1,
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Call(H160::repeat_byte(5)),
+				value: U256::MAX,
+				input: Vec::new(),
+				signature: TransactionSignature::new(
+					37,
+					H256::from_low_u64_be(1),
+					H256::repeat_byte(6),
+				)
+				.unwrap(),
+			}),
+			TransactionAny::EIP2930(EIP2930Transaction {
+				chain_id: 1,
+				nonce: 0x7f.into(),
+				gas_price: 0x80.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Create,
+				value: U256::zero(),
+				input: vec![0x00],
+				access_list: access_list.clone(),
+				odd_y_parity: false,
+				r: H256::repeat_byte(7),
+				s: H256::repeat_byte(8),
+			}),
+			TransactionAny::EIP1559(EIP1559Transaction {
+				chain_id: u64::MAX,
+				nonce: 1.into(),
+				max_priority_fee_per_gas: 2.into(),
+				max_fee_per_gas: 3.into(),
+				gas_limit: 4.into(),
+				action: TransactionAction::Create,
+				value: 5.into(),
+				input: vec![0xab; 1024],
+				access_list,
+				odd_y_parity: true,
+				r: H256::repeat_byte(7),
+				s: H256::repeat_byte(8),
+			}),
+		]
+	}
+
+	#[test]
+	fn transactions_match_rlp() {
+		for tx in transactions() {
+			check_enveloped(&tx, &EnvelopedEncodable::encode(&tx));
+			match &tx {
+				TransactionAny::Legacy(t) => check(t, t.encoded_len(), |out| t.encode_to(out)),
+				TransactionAny::EIP2930(t) => {
+					check(t, t.encoded_len(), |out| t.encode_to(out));
+					let tx = TransactionV1::EIP2930(t.clone());
+					check_enveloped(&tx, &EnvelopedEncodable::encode(&tx));
+				}
+				TransactionAny::EIP1559(t) => check(t, t.encoded_len(), |out| t.encode_to(out)),
+			}
+		}
+	}
+
+	#[test]
+	fn receipts_match_rlp() {
+		let receipts = vec![
+			ReceiptAny::Frontier(FrontierReceiptData::new(
+				H256::repeat_byte(1),
+				0.into(),
+				logs(),
+			)),
+			ReceiptAny::EIP658(EIP658ReceiptData::new(1, 21_000.into(), Vec::new())),
+			ReceiptAny::EIP2930(EIP658ReceiptData::new(0, 1.into(), logs())),
+			ReceiptAny::EIP1559(EIP658ReceiptData::new(1, U256::MAX, logs())),
+		];
+		for receipt in &receipts {
+			check_enveloped(receipt, &EnvelopedEncodable::encode(receipt));
+			match receipt {
+				ReceiptAny::Frontier(r) => check(r, r.encoded_len(), |out| r.encode_to(out)),
+				ReceiptAny::EIP658(r) | ReceiptAny::EIP2930(r) | ReceiptAny::EIP1559(r) => {
+					check(r, r.encoded_len(), |out| r.encode_to(out));
+				}
+			}
+		}
+
+		let receipt = ReceiptV3::EIP1559(EIP658ReceiptData::new(1, 2.into(), logs()));
+		check_enveloped(&receipt, &EnvelopedEncodable::encode(&receipt));
+	}
+
+	#[test]
+	fn blocks_match_rlp() {
+		let partial_header = PartialHeader {
+			parent_hash: H256::repeat_byte(1),
+			beneficiary: H160::repeat_byte(2),
+			state_root: H256::repeat_byte(3),
+			receipts_root: H256::repeat_byte(4),
+			logs_bloom: Bloom::repeat_byte(5),
+			difficulty: 0x20000.into(),
+			number: 0.into(),
+			timestamp: 0,
+			extra_data: vec![0xee; 32],
+			nonce: H64::repeat_byte(6),
+			..partial_header()
+		};
+		let ommer = Block::<TransactionAny>::new(partial_header.clone(), Vec::new(), Vec::new());
+		check(&ommer.header, ommer.header.encoded_len(), |out| {
+			ommer.header.encode_to(out)
+		});
+		check(&ommer, ommer.encoded_len(), |out| ommer.encode_to(out));
+
+		let block = Block::new(partial_header, transactions(), vec![ommer.header]);
+		check(&block, block.encoded_len(), |out| block.encode_to(out));
+	}
+}
@@ -0,0 +1,950 @@
+//! Engine API types exchanged with consensus clients.
+
+use alloc::{string::String, vec::Vec};
+
+use ethereum_types::{Address, Bloom, H256, H64, U256};
+use rlp::DecoderError;
+use sha2::{Digest, Sha256};
+
+pub use crate::withdrawal::Withdrawal;
+use crate::{
+	block::{transactions_root, Block, EMPTY_OMMERS_HASH},
+	enveloped::{EnvelopedDecodable, EnvelopedDecoderError, EnvelopedEncodable},
+	header::Header,
+	transaction::TransactionAny,
+	withdrawal::withdrawals_root,
+	Bytes,
+};
+
+/// Execution payload of the Paris fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ExecutionPayloadV1 {
+	pub parent_hash: H256,
+	pub fee_recipient: Address,
+	pub state_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bloom,
+	pub prev_randao: H256,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub block_number: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_limit: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_used: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes"))]
+	pub extra_data: Bytes,
+	pub base_fee_per_gas: U256,
+	pub block_hash: H256,
+	/// Enveloped encodings of the transactions.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub transactions: Vec<Bytes>,
+}
+
+/// Execution payload of the Shanghai fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ExecutionPayloadV2 {
+	pub parent_hash: H256,
+	pub fee_recipient: Address,
+	pub state_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bloom,
+	pub prev_randao: H256,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub block_number: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_limit: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_used: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes"))]
+	pub extra_data: Bytes,
+	pub base_fee_per_gas: U256,
+	pub block_hash: H256,
+	/// Enveloped encodings of the transactions.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub transactions: Vec<Bytes>,
+	pub withdrawals: Vec<Withdrawal>,
+}
+
+/// Execution payload of the Cancun fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ExecutionPayloadV3 {
+	pub parent_hash: H256,
+	pub fee_recipient: Address,
+	pub state_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bloom,
+	pub prev_randao: H256,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub block_number: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_limit: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_used: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes"))]
+	pub extra_data: Bytes,
+	pub base_fee_per_gas: U256,
+	pub block_hash: H256,
+	/// Enveloped encodings of the transactions.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub transactions: Vec<Bytes>,
+	pub withdrawals: Vec<Withdrawal>,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub blob_gas_used: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub excess_blob_gas: u64,
+}
+
+/// Execution payload of the Prague fork.
+///
+/// The payload is the Cancun one, together with the execution requests passed
+/// alongside it to `engine_newPayloadV4` (EIP-7685).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ExecutionPayloadV4 {
+	pub parent_hash: H256,
+	pub fee_recipient: Address,
+	pub state_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bloom,
+	pub prev_randao: H256,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub block_number: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_limit: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub gas_used: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes"))]
+	pub extra_data: Bytes,
+	pub base_fee_per_gas: U256,
+	pub block_hash: H256,
+	/// Enveloped encodings of the transactions.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub transactions: Vec<Bytes>,
+	pub withdrawals: Vec<Withdrawal>,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub blob_gas_used: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub excess_blob_gas: u64,
+	/// Execution requests, each being the request type followed by its data.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub execution_requests: Vec<Bytes>,
+}
+
+/// Error returned when an execution payload does not convert to a block, or a
+/// block to an execution payload.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PayloadConversionError {
+	/// Transaction at the given index does not decode.
+	InvalidTransaction {
+		index: usize,
+		error: EnvelopedDecoderError<DecoderError>,
+	},
+	/// Block hash of the payload is not the hash of the block.
+	BlockHashMismatch { expected: H256, computed: H256 },
+	/// Requests hash of the block does not match the execution requests.
+	RequestsHashMismatch { expected: H256, computed: H256 },
+	/// Block lacks a field required by the payload version.
+	MissingField(&'static str),
+	/// Block field does not fit in a payload, or is not a post-merge constant.
+	InvalidField(&'static str),
+}
+
+/// Compute the requests hash of a list of execution requests (EIP-7685).
+///
+/// Requests without data are skipped.
+#[must_use]
+pub fn requests_hash(requests: &[Bytes]) -> H256 {
+	let mut hasher = Sha256::new();
+	for request in requests.iter().filter(|request| request.len() > 1) {
+		hasher.update(Sha256::digest(request));
+	}
+	H256::from_slice(hasher.finalize().as_slice())
+}
+
+/// Header fields of a block that are not part of the Paris payload.
+#[derive(Default)]
+struct PayloadExtension {
+	withdrawals: Option<Vec<Withdrawal>>,
+	blob_gas_used: Option<u64>,
+	excess_blob_gas: Option<u64>,
+	parent_beacon_block_root: Option<H256>,
+	requests_hash: Option<H256>,
+}
+
+fn payload_to_block(
+	payload: ExecutionPayloadV1,
+	extension: PayloadExtension,
+) -> Result<Block<TransactionAny>, PayloadConversionError> {
+	let transactions = payload
+		.transactions
+		.iter()
+		.enumerate()
+		.map(|(index, bytes)| {
+			TransactionAny::decode(bytes)
+				.map_err(|error| PayloadConversionError::InvalidTransaction { index, error })
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let header = Header {
+		parent_hash: payload.parent_hash,
+		ommers_hash: EMPTY_OMMERS_HASH,
+		beneficiary: payload.fee_recipient,
+		state_root: payload.state_root,
+		transactions_root: transactions_root(&transactions),
+		receipts_root: payload.receipts_root,
+		logs_bloom: payload.logs_bloom,
+		difficulty: U256::zero(),
+		number: payload.block_number.into(),
+		gas_limit: payload.gas_limit.into(),
+		gas_used: payload.gas_used.into(),
+		timestamp: payload.timestamp,
+		extra_data: payload.extra_data,
+		mix_hash: payload.prev_randao,
+		nonce: H64::zero(),
+		base_fee_per_gas: Some(payload.base_fee_per_gas),
+		withdrawals_root: extension.withdrawals.as_deref().map(withdrawals_root),
+		blob_gas_used: extension.blob_gas_used,
+		excess_blob_gas: extension.excess_blob_gas,
+		parent_beacon_block_root: extension.parent_beacon_block_root,
+		requests_hash: extension.requests_hash,
+	};
+
+	let computed = header.hash();
+	if computed != payload.block_hash {
+		return Err(PayloadConversionError::BlockHashMismatch {
+			expected: payload.block_hash,
+			computed,
+		});
+	}
+
+	Ok(Block {
+		header,
+		transactions,
+		ommers: Vec::new(),
+		withdrawals: extension.withdrawals,
+	})
+}
+
+fn block_to_payload(
+	block: Block<TransactionAny>,
+) -> Result<(ExecutionPayloadV1, PayloadExtension), PayloadConversionError> {
+	fn to_u64(value: U256, field: &'static str) -> Result<u64, PayloadConversionError> {
+		u64::try_from(value).map_err(|_| PayloadConversionError::InvalidField(field))
+	}
+
+	let block_hash = block.header.hash();
+	let header = block.header;
+	if !block.ommers.is_empty() || header.ommers_hash != EMPTY_OMMERS_HASH {
+		return Err(PayloadConversionError::InvalidField("ommers"));
+	}
+	if !header.difficulty.is_zero() {
+		return Err(PayloadConversionError::InvalidField("difficulty"));
+	}
+	if !header.nonce.is_zero() {
+		return Err(PayloadConversionError::InvalidField("nonce"));
+	}
+
+	let payload = ExecutionPayloadV1 {
+		parent_hash: header.parent_hash,
+		fee_recipient: header.beneficiary,
+		state_root: header.state_root,
+		receipts_root: header.receipts_root,
+		logs_bloom: header.logs_bloom,
+		prev_randao: header.mix_hash,
+		block_number: to_u64(header.number, "number")?,
+		gas_limit: to_u64(header.gas_limit, "gasLimit")?,
+		gas_used: to_u64(header.gas_used, "gasUsed")?,
+		timestamp: header.timestamp,
+		extra_data: header.extra_data,
+		base_fee_per_gas: header
+			.base_fee_per_gas
+			.ok_or(PayloadConversionError::MissingField("baseFeePerGas"))?,
+		block_hash,
+		transactions: block
+			.transactions
+			.iter()
+			.map(|transaction| EnvelopedEncodable::encode(transaction).to_vec())
+			.collect(),
+	};
+	let extension = PayloadExtension {
+		withdrawals: block.withdrawals,
+		blob_gas_used: header.blob_gas_used,
+		excess_blob_gas: header.excess_blob_gas,
+		parent_beacon_block_root: header.parent_beacon_block_root,
+		requests_hash: header.requests_hash,
+	};
+
+	Ok((payload, extension))
+}
+
+impl ExecutionPayloadV1 {
+	/// Convert the payload to a block, checking the block hash.
+	pub fn try_into_block(self) -> Result<Block<TransactionAny>, PayloadConversionError> {
+		payload_to_block(self, PayloadExtension::default())
+	}
+}
+
+impl TryFrom<Block<TransactionAny>> for ExecutionPayloadV1 {
+	type Error = PayloadConversionError;
+
+	fn try_from(block: Block<TransactionAny>) -> Result<Self, Self::Error> {
+		let (payload, extension) = block_to_payload(block)?;
+		if extension.withdrawals.is_some() {
+			return Err(PayloadConversionError::InvalidField("withdrawals"));
+		}
+
+		Ok(payload)
+	}
+}
+
+impl ExecutionPayloadV2 {
+	fn split(self) -> (ExecutionPayloadV1, Vec<Withdrawal>) {
+		let payload = ExecutionPayloadV1 {
+			parent_hash: self.parent_hash,
+			fee_recipient: self.fee_recipient,
+			state_root: self.state_root,
+			receipts_root: self.receipts_root,
+			logs_bloom: self.logs_bloom,
+			prev_randao: self.prev_randao,
+			block_number: self.block_number,
+			gas_limit: self.gas_limit,
+			gas_used: self.gas_used,
+			timestamp: self.timestamp,
+			extra_data: self.extra_data,
+			base_fee_per_gas: self.base_fee_per_gas,
+			block_hash: self.block_hash,
+			transactions: self.transactions,
+		};
+		(payload, self.withdrawals)
+	}
+
+	fn join(payload: ExecutionPayloadV1, withdrawals: Vec<Withdrawal>) -> Self {
+		Self {
+			parent_hash: payload.parent_hash,
+			fee_recipient: payload.fee_recipient,
+			state_root: payload.state_root,
+			receipts_root: payload.receipts_root,
+			logs_bloom: payload.logs_bloom,
+			prev_randao: payload.prev_randao,
+			block_number: payload.block_number,
+			gas_limit: payload.gas_limit,
+			gas_used: payload.gas_used,
+			timestamp: payload.timestamp,
+			extra_data: payload.extra_data,
+			base_fee_per_gas: payload.base_fee_per_gas,
+			block_hash: payload.block_hash,
+			transactions: payload.transactions,
+			withdrawals,
+		}
+	}
+
+	/// Convert the payload to a block, checking the block hash.
+	pub fn try_into_block(self) -> Result<Block<TransactionAny>, PayloadConversionError> {
+		let (payload, withdrawals) = self.split();
+		let extension = PayloadExtension {
+			withdrawals: Some(withdrawals),
+			..Default::default()
+		};
+		payload_to_block(payload, extension)
+	}
+}
+
+impl TryFrom<Block<TransactionAny>> for ExecutionPayloadV2 {
+	type Error = PayloadConversionError;
+
+	fn try_from(block: Block<TransactionAny>) -> Result<Self, Self::Error> {
+		let (payload, extension) = block_to_payload(block)?;
+		let withdrawals = extension
+			.withdrawals
+			.ok_or(PayloadConversionError::MissingField("withdrawals"))?;
+		Ok(Self::join(payload, withdrawals))
+	}
+}
+
+impl ExecutionPayloadV3 {
+	fn split(self) -> (ExecutionPayloadV2, u64, u64) {
+		let payload = ExecutionPayloadV2 {
+			parent_hash: self.parent_hash,
+			fee_recipient: self.fee_recipient,
+			state_root: self.state_root,
+			receipts_root: self.receipts_root,
+			logs_bloom: self.logs_bloom,
+			prev_randao: self.prev_randao,
+			block_number: self.block_number,
+			gas_limit: self.gas_limit,
+			gas_used: self.gas_used,
+			timestamp: self.timestamp,
+			extra_data: self.extra_data,
+			base_fee_per_gas: self.base_fee_per_gas,
+			block_hash: self.block_hash,
+			transactions: self.transactions,
+			withdrawals: self.withdrawals,
+		};
+		(payload, self.blob_gas_used, self.excess_blob_gas)
+	}
+
+	fn join(payload: ExecutionPayloadV2, blob_gas_used: u64, excess_blob_gas: u64) -> Self {
+		Self {
+			parent_hash: payload.parent_hash,
+			fee_recipient: payload.fee_recipient,
+			state_root: payload.state_root,
+			receipts_root: payload.receipts_root,
+			logs_bloom: payload.logs_bloom,
+			prev_randao: payload.prev_randao,
+			block_number: payload.block_number,
+			gas_limit: payload.gas_limit,
+			gas_used: payload.gas_used,
+			timestamp: payload.timestamp,
+			extra_data: payload.extra_data,
+			base_fee_per_gas: payload.base_fee_per_gas,
+			block_hash: payload.block_hash,
+			transactions: payload.transactions,
+			withdrawals: payload.withdrawals,
+			blob_gas_used,
+			excess_blob_gas,
+		}
+	}
+
+	/// Convert the payload to a block, checking the block hash.
+	///
+	/// The parent beacon block root is passed alongside the payload to
+	/// `engine_newPayloadV3`.
+	pub fn try_into_block(
+		self,
+		parent_beacon_block_root: H256,
+	) -> Result<Block<TransactionAny>, PayloadConversionError> {
+		let (payload, blob_gas_used, excess_blob_gas) = self.split();
+		let (payload, withdrawals) = payload.split();
+		let extension = PayloadExtension {
+			withdrawals: Some(withdrawals),
+			blob_gas_used: Some(blob_gas_used),
+			excess_blob_gas: Some(excess_blob_gas),
+			parent_beacon_block_root: Some(parent_beacon_block_root),
+			requests_hash: None,
+		};
+		payload_to_block(payload, extension)
+	}
+}
+
+impl TryFrom<Block<TransactionAny>> for ExecutionPayloadV3 {
+	type Error = PayloadConversionError;
+
+	fn try_from(block: Block<TransactionAny>) -> Result<Self, Self::Error> {
+		let (payload, extension) = block_to_payload(block)?;
+		let withdrawals = extension
+			.withdrawals
+			.ok_or(PayloadConversionError::MissingField("withdrawals"))?;
+		let blob_gas_used = extension
+			.blob_gas_used
+			.ok_or(PayloadConversionError::MissingField("blobGasUsed"))?;
+		let excess_blob_gas = extension
+			.excess_blob_gas
+			.ok_or(PayloadConversionError::MissingField("excessBlobGas"))?;
+		Ok(Self::join(
+			ExecutionPayloadV2::join(payload, withdrawals),
+			blob_gas_used,
+			excess_blob_gas,
+		))
+	}
+}
+
+impl ExecutionPayloadV4 {
+	fn split(self) -> (ExecutionPayloadV3, Vec<Bytes>) {
+		let payload = ExecutionPayloadV3 {
+			parent_hash: self.parent_hash,
+			fee_recipient: self.fee_recipient,
+			state_root: self.state_root,
+			receipts_root: self.receipts_root,
+			logs_bloom: self.logs_bloom,
+			prev_randao: self.prev_randao,
+			block_number: self.block_number,
+			gas_limit: self.gas_limit,
+			gas_used: self.gas_used,
+			timestamp: self.timestamp,
+			extra_data: self.extra_data,
+			base_fee_per_gas: self.base_fee_per_gas,
+			block_hash: self.block_hash,
+			transactions: self.transactions,
+			withdrawals: self.withdrawals,
+			blob_gas_used: self.blob_gas_used,
+			excess_blob_gas: self.excess_blob_gas,
+		};
+		(payload, self.execution_requests)
+	}
+
+	fn join(payload: ExecutionPayloadV3, execution_requests: Vec<Bytes>) -> Self {
+		Self {
+			parent_hash: payload.parent_hash,
+			fee_recipient: payload.fee_recipient,
+			state_root: payload.state_root,
+			receipts_root: payload.receipts_root,
+			logs_bloom: payload.logs_bloom,
+			prev_randao: payload.prev_randao,
+			block_number: payload.block_number,
+			gas_limit: payload.gas_limit,
+			gas_used: payload.gas_used,
+			timestamp: payload.timestamp,
+			extra_data: payload.extra_data,
+			base_fee_per_gas: payload.base_fee_per_gas,
+			block_hash: payload.block_hash,
+			transactions: payload.transactions,
+			withdrawals: payload.withdrawals,
+			blob_gas_used: payload.blob_gas_used,
+			excess_blob_gas: payload.excess_blob_gas,
+			execution_requests,
+		}
+	}
+
+	/// Convert the payload to a block, checking the block hash.
+	///
+	/// The parent beacon block root is passed alongside the payload to
+	/// `engine_newPayloadV4`.
+	pub fn try_into_block(
+		self,
+		parent_beacon_block_root: H256,
+	) -> Result<Block<TransactionAny>, PayloadConversionError> {
+		let (payload, execution_requests) = self.split();
+		let (payload, blob_gas_used, excess_blob_gas) = payload.split();
+		let (payload, withdrawals) = payload.split();
+		let extension = PayloadExtension {
+			withdrawals: Some(withdrawals),
+			blob_gas_used: Some(blob_gas_used),
+			excess_blob_gas: Some(excess_blob_gas),
+			parent_beacon_block_root: Some(parent_beacon_block_root),
+			requests_hash: Some(requests_hash(&execution_requests)),
+		};
+		payload_to_block(payload, extension)
+	}
+
+	/// Convert a block to a payload, together with the execution requests
+	/// committed to by its requests hash.
+	pub fn from_block(
+		block: Block<TransactionAny>,
+		execution_requests: Vec<Bytes>,
+	) -> Result<Self, PayloadConversionError> {
+		let expected = block
+			.header
+			.requests_hash
+			.ok_or(PayloadConversionError::MissingField("requestsHash"))?;
+		let computed = requests_hash(&execution_requests);
+		if computed != expected {
+			return Err(PayloadConversionError::RequestsHashMismatch { expected, computed });
+		}
+
+		Ok(Self::join(
+			ExecutionPayloadV3::try_from(block)?,
+			execution_requests,
+		))
+	}
+}
+
+/// Fork choice state sent with `engine_forkchoiceUpdated`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ForkchoiceStateV1 {
+	pub head_block_hash: H256,
+	pub safe_block_hash: H256,
+	pub finalized_block_hash: H256,
+}
+
+/// Attributes of a payload to build, for the Paris fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct PayloadAttributesV1 {
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	pub prev_randao: H256,
+	pub suggested_fee_recipient: Address,
+}
+
+/// Attributes of a payload to build, for the Shanghai fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct PayloadAttributesV2 {
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	pub prev_randao: H256,
+	pub suggested_fee_recipient: Address,
+	pub withdrawals: Vec<Withdrawal>,
+}
+
+/// Attributes of a payload to build, for the Cancun fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct PayloadAttributesV3 {
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub timestamp: u64,
+	pub prev_randao: H256,
+	pub suggested_fee_recipient: Address,
+	pub withdrawals: Vec<Withdrawal>,
+	pub parent_beacon_block_root: H256,
+}
+
+/// Validation status of a payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "SCREAMING_SNAKE_CASE")
+)]
+pub enum PayloadValidationStatus {
+	Valid,
+	Invalid,
+	Syncing,
+	Accepted,
+	InvalidBlockHash,
+}
+
+/// Result of validating a payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct PayloadStatusV1 {
+	pub status: PayloadValidationStatus,
+	pub latest_valid_hash: Option<H256>,
+	pub validation_error: Option<String>,
+}
+
+/// Response of `engine_forkchoiceUpdated`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ForkchoiceUpdatedResponse {
+	pub payload_status: PayloadStatusV1,
+	pub payload_id: Option<H64>,
+}
+
+/// Block body returned by `engine_getPayloadBodiesByHash` and `engine_getPayloadBodiesByRange`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct ExecutionPayloadBodyV1 {
+	/// Enveloped encodings of the transactions.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub transactions: Vec<Bytes>,
+	/// Withdrawals of the block, `None` before the Shanghai fork.
+	pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+/// Blobs, commitments and proofs of the blob transactions of a payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct BlobsBundleV1 {
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub commitments: Vec<Bytes>,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub proofs: Vec<Bytes>,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::bytes_list"))]
+	pub blobs: Vec<Bytes>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::header::PartialHeader;
+	use crate::test_utils::partial_header;
+	use crate::transaction::{
+		EIP1559Transaction, LegacyTransaction, TransactionAction, TransactionSignature,
+	};
+	use hex_literal::hex;
+
+	fn block() -> Block<TransactionAny> {
+		let transactions = vec![
+			TransactionAny::Legacy(LegacyTransaction {
+				nonce: 1.into(),
+				gas_price: 10.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Call(Address::repeat_byte(1)),
+				value: 1.into(),
+				input: Vec::new(),
+				signature: TransactionSignature::new(
+					37,
+					H256::repeat_byte(2),
+					H256::repeat_byte(3),
+				)
+				.unwrap(),
+			}),
+			TransactionAny::EIP1559(EIP1559Transaction {
+				chain_id: 1,
+				nonce: 2.into(),
+				max_priority_fee_per_gas: 1.into(),
+				max_fee_per_gas: 10.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Create,
+				value: U256::zero(),
+				input: vec![0x60, 0x00],
+				access_list: Vec::new(),
+				odd_y_parity: true,
+				r: H256::repeat_byte(4),
+				s: H256::repeat_byte(5),
+			}),
+		];
+		let withdrawals = vec![Withdrawal {
+			index: 1,
+			validator_index: 2,
+			address: Address::repeat_byte(6),
+			amount: 3,
+		}];
+		let partial_header = PartialHeader {
+			mix_hash: H256::repeat_byte(7),
+			gas_used: 42_000.into(),
+			extra_data: b"payload".to_vec(),
+			base_fee_per_gas: Some(7.into()),
+			blob_gas_used: Some(0),
+			excess_blob_gas: Some(0),
+			parent_beacon_block_root: Some(H256::repeat_byte(8)),
+			requests_hash: Some(requests_hash(&[])),
+			..partial_header()
+		};
+		Block::new_with_withdrawals(partial_header, transactions, Vec::new(), withdrawals)
+	}
+
+	#[test]
+	fn payload_round_trip() {
+		let block = block();
+		let parent_beacon_block_root = H256::repeat_byte(8);
+
+		let payload = ExecutionPayloadV4::from_block(block.clone(), Vec::new()).unwrap();
+		assert_eq!(payload.prev_randao, H256::repeat_byte(7));
+		assert_eq!(payload.block_hash, block.header.hash());
+		assert_eq!(payload.transactions[1][0], 0x02);
+		assert_eq!(
+			payload.clone().try_into_block(parent_beacon_block_root),
+			Ok(block.clone())
+		);
+
+		let mut block = block;
+		block.header.requests_hash = None;
+		let payload = ExecutionPayloadV3::try_from(block.clone()).unwrap();
+		assert_eq!(
+			payload.try_into_block(parent_beacon_block_root),
+			Ok(block.clone())
+		);
+
+		block.header.blob_gas_used = None;
+		block.header.excess_blob_gas = None;
+		block.header.parent_beacon_block_root = None;
+		let payload = ExecutionPayloadV2::try_from(block.clone()).unwrap();
+		assert_eq!(payload.try_into_block(), Ok(block.clone()));
+		assert_eq!(
+			ExecutionPayloadV3::try_from(block.clone()),
+			Err(PayloadConversionError::MissingField("blobGasUsed"))
+		);
+
+		block.withdrawals = None;
+		block.header.withdrawals_root = None;
+		let payload = ExecutionPayloadV1::try_from(block.clone()).unwrap();
+		assert_eq!(payload.try_into_block(), Ok(block));
+	}
+
+	#[test]
+	fn payload_block_hash_mismatch() {
+		let block = block();
+		let mut payload = ExecutionPayloadV4::from_block(block.clone(), Vec::new()).unwrap();
+		let computed = payload.block_hash;
+
+		payload.block_hash = H256::zero();
+		assert_eq!(
+			payload.clone().try_into_block(H256::repeat_byte(8)),
+			Err(PayloadConversionError::BlockHashMismatch {
+				expected: H256::zero(),
+				computed,
+			})
+		);
+
+		// A different parent beacon block root changes the block hash.
+		payload.block_hash = computed;
+		assert!(matches!(
+			payload.clone().try_into_block(H256::zero()),
+			Err(PayloadConversionError::BlockHashMismatch { .. })
+		));
+
+		payload.transactions[0] = vec![0x7f];
+		assert!(matches!(
+			payload.try_into_block(H256::repeat_byte(8)),
+			Err(PayloadConversionError::InvalidTransaction { index: 0, .. })
+		));
+
+		assert!(matches!(
+			ExecutionPayloadV4::from_block(block.clone(), vec![vec![0x00, 0x01]]),
+			Err(PayloadConversionError::RequestsHashMismatch { .. })
+		));
+	}
+
+	#[test]
+	fn pre_merge_block_to_payload() {
+		let mut block = block();
+		block.header.difficulty = 1.into();
+		assert_eq!(
+			ExecutionPayloadV3::try_from(block.clone()),
+			Err(PayloadConversionError::InvalidField("difficulty"))
+		);
+
+		block.header.difficulty = U256::zero();
+		block.header.nonce = H64::repeat_byte(1);
+		assert_eq!(
+			ExecutionPayloadV3::try_from(block),
+			Err(PayloadConversionError::InvalidField("nonce"))
+		);
+	}
+
+	#[test]
+	fn empty_requests_hash() {
+		assert_eq!(
+			requests_hash(&[]),
+			H256::from(hex!(
+				"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+			))
+		);
+		assert_eq!(requests_hash(&[vec![0x00], vec![0x01]]), requests_hash(&[]));
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn forkchoice_updated_response_json() {
+		let json = r#"{"payloadStatus":{"status":"VALID","latestValidHash":"0x3559e851470f6e7bbed1db474980683e8c315bfce99b2a6ef47c057c04de7858","validationError":null},"payloadId":"0xa247243752eb10b4"}"#;
+		let response = ForkchoiceUpdatedResponse {
+			payload_status: PayloadStatusV1 {
+				status: PayloadValidationStatus::Valid,
+				latest_valid_hash: Some(
+					hex!("3559e851470f6e7bbed1db474980683e8c315bfce99b2a6ef47c057c04de7858").into(),
+				),
+				validation_error: None,
+			},
+			payload_id: Some(hex!("a247243752eb10b4").into()),
+		};
+
+		assert_eq!(serde_json::to_string(&response).unwrap(), json);
+		assert_eq!(
+			serde_json::from_str::<ForkchoiceUpdatedResponse>(json).unwrap(),
+			response
+		);
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn payload_attributes_json() {
+		let json = r#"{"timestamp":"0x5","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","withdrawals":[{"index":"0xf0","validatorIndex":"0x0","address":"0x00000000000000000000000000000000000010f0","amount":"0x1"}]}"#;
+		let attributes = PayloadAttributesV2 {
+			timestamp: 5,
+			prev_randao: H256::zero(),
+			suggested_fee_recipient: hex!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b").into(),
+			withdrawals: vec![Withdrawal {
+				index: 0xf0,
+				validator_index: 0,
+				address: hex!("00000000000000000000000000000000000010f0").into(),
+				amount: 1,
+			}],
+		};
+
+		assert_eq!(serde_json::to_string(&attributes).unwrap(), json);
+		assert_eq!(
+			serde_json::from_str::<PayloadAttributesV2>(json).unwrap(),
+			attributes
+		);
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn execution_payload_json() {
+		let payload = ExecutionPayloadV1 {
+			parent_hash: H256::repeat_byte(1),
+			fee_recipient: Address::repeat_byte(2),
+			state_root: H256::repeat_byte(3),
+			receipts_root: H256::repeat_byte(4),
+			logs_bloom: Bloom::zero(),
+			prev_randao: H256::repeat_byte(5),
+			block_number: 1,
+			gas_limit: 0x1c9c380,
+			gas_used: 0,
+			timestamp: 0x5,
+			extra_data: vec![],
+			base_fee_per_gas: 7.into(),
+			block_hash: H256::repeat_byte(6),
+			transactions: vec![hex!("02f8").to_vec()],
+		};
+
+		let json = serde_json::to_value(&payload).unwrap();
+		assert_eq!(json["blockNumber"], "0x1");
+		assert_eq!(json["gasLimit"], "0x1c9c380");
+		assert_eq!(json["extraData"], "0x");
+		assert_eq!(json["baseFeePerGas"], "0x7");
+		assert_eq!(json["transactions"][0], "0x02f8");
+		assert_eq!(
+			serde_json::from_value::<ExecutionPayloadV1>(json).unwrap(),
+			payload
+		);
+	}
+}
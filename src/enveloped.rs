@@ -1,4 +1,7 @@
-use bytes::BytesMut;
+use alloc::vec::Vec;
+
+use bytes::{BufMut, BytesMut};
+use rlp::{DecoderError, Rlp, RlpStream};
 
 /// DecoderError for typed transactions.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -17,16 +20,24 @@ impl<T> From<T> for EnvelopedDecoderError<T> {
 pub trait EnvelopedEncodable {
 	/// Convert self to an owned vector.
 	fn encode(&self) -> BytesMut {
-		let type_id = self.type_id();
+		let mut out = BytesMut::with_capacity(self.encoded_len());
+		self.encode_to(&mut out);
+		out
+	}
 
-		let mut out = BytesMut::new();
-		if let Some(type_id) = type_id {
+	/// Length of the output of `encode`.
+	fn encoded_len(&self) -> usize {
+		usize::from(self.type_id().is_some()) + self.payload_len()
+	}
+
+	/// Write the output of `encode` to a buffer.
+	fn encode_to(&self, out: &mut impl BufMut) {
+		if let Some(type_id) = self.type_id() {
 			assert!(type_id <= 0x7f);
-			out.extend_from_slice(&[type_id]);
+			out.put_u8(type_id);
 		}
 
-		out.extend_from_slice(&self.encode_payload()[..]);
-		out
+		self.encode_payload_to(out);
 	}
 
 	/// Type Id of the transaction.
@@ -34,6 +45,16 @@ pub trait EnvelopedEncodable {
 
 	/// Encode inner payload.
 	fn encode_payload(&self) -> BytesMut;
+
+	/// Length of the inner payload.
+	fn payload_len(&self) -> usize {
+		self.encode_payload().len()
+	}
+
+	/// Write the inner payload to a buffer.
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		out.put_slice(&self.encode_payload());
+	}
 }
 
 /// Decodable typed transactions.
@@ -44,3 +65,403 @@ pub trait EnvelopedDecodable: Sized {
 	/// Decode raw bytes to a Self type.
 	fn decode(bytes: &[u8]) -> Result<Self, EnvelopedDecoderError<Self::PayloadDecoderError>>;
 }
+
+/// Append an item in its network form, as used in blocks and devp2p messages.
+///
+/// Untyped items are appended as the RLP list they encode to, typed items as a
+/// byte string holding the type id followed by the payload.
+pub fn append_enveloped<T: EnvelopedEncodable>(s: &mut RlpStream, item: &T) {
+	if item.type_id().is_some() {
+		s.append(&&item.encode()[..]);
+	} else {
+		s.append_raw(&item.encode_payload(), 1);
+	}
+}
+
+/// Append a list of items in their network form.
+pub fn append_enveloped_list<T: EnvelopedEncodable>(s: &mut RlpStream, items: &[T]) {
+	s.begin_list(items.len());
+	for item in items {
+		append_enveloped(s, item);
+	}
+}
+
+/// Decode an item from its network form.
+///
+/// Untyped items wrapped in a byte string are accepted as well.
+pub fn decode_enveloped<T: EnvelopedDecodable>(rlp: &Rlp) -> Result<T, DecoderError> {
+	let bytes = if rlp.is_list() {
+		rlp.as_raw()
+	} else {
+		rlp.data()?
+	};
+
+	T::decode(bytes).map_err(|_| DecoderError::Custom("decode enveloped item failed"))
+}
+
+/// Decode a list of items from their network form.
+pub fn decode_enveloped_list<T: EnvelopedDecodable>(rlp: &Rlp) -> Result<Vec<T>, DecoderError> {
+	if !rlp.is_list() {
+		return Err(DecoderError::RlpExpectedToBeList);
+	}
+
+	rlp.iter().map(|item| decode_enveloped(&item)).collect()
+}
+
+/// Define an enum of enveloped items from its variants and their EIP-2718
+/// type ids.
+///
+/// Each variant holds an RLP encodable and decodable payload type, and is
+/// given either a type id, or `legacy` for at most one untyped variant, which
+/// must come first. Payload types must be distinct. Type ids must be distinct
+/// and at most `0x7f`, which is checked at compile time.
+///
+/// The enum implements [`EnvelopedEncodable`] and [`EnvelopedDecodable`], a
+/// `hash` method returning the Keccak hash of the enveloped encoding, and
+/// `From` each payload type. Attributes are passed through, so serde or SCALE
+/// support is added with the usual derives.
+///
+/// Payload types of this crate are written by `encode_to` without an
+/// intermediate buffer, other payload types through [`rlp::encode`].
+///
+/// ```
+/// use ethereum::{EIP1559Transaction, LegacyTransaction};
+///
+/// ethereum::enveloped_enum! {
+///     #[derive(Clone, Debug, PartialEq, Eq)]
+///     pub enum ChainTransaction {
+///         Legacy(LegacyTransaction) = legacy,
+///         EIP1559(EIP1559Transaction) = 0x02,
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use ethereum::{EIP1559Transaction, EIP2930Transaction};
+///
+/// ethereum::enveloped_enum! {
+///     pub enum ChainTransaction {
+///         EIP2930(EIP2930Transaction) = 0x02,
+///         EIP1559(EIP1559Transaction) = 0x02,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! enveloped_enum {
+	(
+		$(#[$attr:meta])*
+		$vis:vis enum $name:ident {
+			$(
+				$(#[$variant_attr:meta])*
+				$variant:ident($ty:ty) = $kind:tt
+			),+ $(,)?
+		}
+	) => {
+		$(#[$attr])*
+		$vis enum $name {
+			$(
+				$(#[$variant_attr])*
+				$variant($ty),
+			)+
+		}
+
+		const _: () = {
+			let type_ids: &[Option<u8>] = &[$($crate::__enveloped_type_id!($kind)),+];
+			let mut i = 0;
+			while i < type_ids.len() {
+				match type_ids[i] {
+					Some(type_id) => assert!(type_id <= 0x7f, "type id above 0x7f"),
+					None => assert!(i == 0, "legacy variant is not the first one"),
+				}
+				let mut j = i + 1;
+				while j < type_ids.len() {
+					if let (Some(a), Some(b)) = (type_ids[i], type_ids[j]) {
+						assert!(a != b, "duplicate type id");
+					}
+					j += 1;
+				}
+				i += 1;
+			}
+		};
+
+		impl $name {
+			pub fn hash(&self) -> $crate::__private::ethereum_types::H256 {
+				use $crate::__private::sha3::Digest;
+
+				$crate::__private::ethereum_types::H256::from_slice(
+					$crate::__private::sha3::Keccak256::digest(
+						&$crate::EnvelopedEncodable::encode(self),
+					)
+					.as_slice(),
+				)
+			}
+		}
+
+		impl $crate::EnvelopedEncodable for $name {
+			fn type_id(&self) -> Option<u8> {
+				match self {
+					$(Self::$variant(_) => $crate::__enveloped_type_id!($kind),)+
+				}
+			}
+
+			fn encode_payload(&self) -> $crate::__private::bytes::BytesMut {
+				match self {
+					$(Self::$variant(payload) => $crate::__private::rlp::encode(payload),)+
+				}
+			}
+
+			fn payload_len(&self) -> usize {
+				#[allow(unused_imports)]
+				use $crate::__private::{EncodePayloadRlp as _, EncodePayloadTo as _};
+
+				match self {
+					$(Self::$variant(payload) => (&$crate::__private::Payload(payload)).payload_len(),)+
+				}
+			}
+
+			fn encode_payload_to(&self, out: &mut impl $crate::__private::bytes::BufMut) {
+				#[allow(unused_imports)]
+				use $crate::__private::{EncodePayloadRlp as _, EncodePayloadTo as _};
+
+				match self {
+					$(Self::$variant(payload) => {
+						(&$crate::__private::Payload(payload)).encode_payload_to(out)
+					})+
+				}
+			}
+		}
+
+		impl $crate::EnvelopedDecodable for $name {
+			type PayloadDecoderError = $crate::__private::rlp::DecoderError;
+
+			fn decode(
+				bytes: &[u8],
+			) -> Result<Self, $crate::EnvelopedDecoderError<Self::PayloadDecoderError>> {
+				if bytes.is_empty() {
+					return Err($crate::EnvelopedDecoderError::UnknownTypeId);
+				}
+
+				$($crate::__enveloped_try_decode!($kind, $variant, bytes);)+
+
+				Err($crate::EnvelopedDecoderError::UnknownTypeId)
+			}
+		}
+
+		$(
+			impl From<$ty> for $name {
+				fn from(payload: $ty) -> Self {
+					Self::$variant(payload)
+				}
+			}
+		)+
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __enveloped_type_id {
+	(legacy) => {
+		None
+	};
+	($type_id:literal) => {
+		Some($type_id)
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __enveloped_try_decode {
+	(legacy, $variant:ident, $bytes:ident) => {
+		let rlp = $crate::__private::rlp::Rlp::new($bytes);
+		if rlp.is_list() {
+			return Ok(Self::$variant(rlp.as_val()?));
+		}
+	};
+	($type_id:literal, $variant:ident, $bytes:ident) => {
+		if $bytes[0] == $type_id {
+			return Ok(Self::$variant($crate::__private::rlp::decode(
+				&$bytes[1..],
+			)?));
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::transaction::{
+		EIP1559Transaction, EIP2930Transaction, LegacyTransaction, TransactionAction,
+		TransactionSignature, TransactionV2,
+	};
+	use ethereum_types::{H256, U256};
+
+	crate::enveloped_enum! {
+		#[derive(Clone, Debug, PartialEq, Eq)]
+		#[cfg_attr(
+			feature = "with-scale",
+			derive(scale_codec::Encode, scale_codec::Decode)
+		)]
+		#[cfg_attr(
+			feature = "with-serde",
+			derive(serde::Serialize, serde::Deserialize),
+			serde(untagged)
+		)]
+		enum TestTransaction {
+			Legacy(LegacyTransaction) = legacy,
+			EIP2930(EIP2930Transaction) = 0x01,
+			EIP1559(EIP1559Transaction) = 0x02,
+		}
+	}
+
+	crate::enveloped_enum! {
+		#[derive(Clone, Debug, PartialEq, Eq)]
+		enum TypedOnly {
+			EIP2930(EIP2930Transaction) = 1,
+		}
+	}
+
+	#[derive(Clone, Debug, PartialEq, Eq, rlp::RlpEncodable, rlp::RlpDecodable)]
+	struct CustomPayload {
+		value: U256,
+		data: Vec<u8>,
+	}
+
+	crate::enveloped_enum! {
+		#[derive(Clone, Debug, PartialEq, Eq)]
+		#[allow(clippy::large_enum_variant)]
+		enum WithCustom {
+			EIP1559(EIP1559Transaction) = 0x02,
+			Custom(CustomPayload) = 0x10,
+		}
+	}
+
+	fn eip1559() -> EIP1559Transaction {
+		EIP1559Transaction {
+			chain_id: 1,
+			nonce: 2.into(),
+			max_priority_fee_per_gas: 1.into(),
+			max_fee_per_gas: 2.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: vec![0x60],
+			access_list: Vec::new(),
+			odd_y_parity: true,
+			r: H256::repeat_byte(3),
+			s: H256::repeat_byte(4),
+		}
+	}
+
+	fn eip2930() -> EIP2930Transaction {
+		EIP2930Transaction {
+			chain_id: 1,
+			nonce: 1.into(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: vec![0x60],
+			access_list: Vec::new(),
+			odd_y_parity: false,
+			r: H256::repeat_byte(1),
+			s: H256::repeat_byte(2),
+		}
+	}
+
+	#[test]
+	fn enveloped_enum_matches_transaction_v2() {
+		let legacy = LegacyTransaction {
+			nonce: 1.into(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: Vec::new(),
+			signature: TransactionSignature::new(27, H256::repeat_byte(1), H256::repeat_byte(2))
+				.unwrap(),
+		};
+		let transactions = [
+			(
+				TestTransaction::from(legacy.clone()),
+				TransactionV2::Legacy(legacy),
+			),
+			(eip2930().into(), TransactionV2::EIP2930(eip2930())),
+			(eip1559().into(), TransactionV2::EIP1559(eip1559())),
+		];
+
+		for (tx, expected) in transactions {
+			let encoded = tx.encode();
+			assert_eq!(encoded, EnvelopedEncodable::encode(&expected));
+			assert_eq!(tx.type_id(), expected.type_id());
+			assert_eq!(tx.hash(), expected.hash());
+			assert_eq!(
+				<TestTransaction as EnvelopedDecodable>::decode(&encoded),
+				Ok(tx)
+			);
+		}
+
+		assert_eq!(
+			<TestTransaction as EnvelopedDecodable>::decode(&[0x03, 0xc0]),
+			Err(EnvelopedDecoderError::UnknownTypeId)
+		);
+		assert_eq!(
+			TypedOnly::from(eip2930()).hash(),
+			TransactionV2::EIP2930(eip2930()).hash()
+		);
+		assert_eq!(
+			<TypedOnly as EnvelopedDecodable>::decode(&rlp::encode(&eip2930())),
+			Err(EnvelopedDecoderError::UnknownTypeId)
+		);
+	}
+
+	#[test]
+	fn enveloped_enum_encode_to() {
+		let custom = CustomPayload {
+			value: 1.into(),
+			data: vec![0xab; 60],
+		};
+		assert_eq!(
+			WithCustom::from(eip1559()).hash(),
+			TransactionV2::EIP1559(eip1559()).hash()
+		);
+		for tx in [WithCustom::from(eip1559()), WithCustom::from(custom)] {
+			let encoded = tx.encode();
+			assert_eq!(tx.encoded_len(), encoded.len());
+			assert_eq!(tx.payload_len(), encoded.len() - 1);
+
+			let mut out = Vec::new();
+			tx.encode_to(&mut out);
+			assert_eq!(out, encoded);
+			assert_eq!(
+				<WithCustom as EnvelopedDecodable>::decode(&out),
+				Ok(tx.clone())
+			);
+		}
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn enveloped_enum_serde() {
+		let tx = TestTransaction::from(eip1559());
+		let json = serde_json::to_value(&tx).unwrap();
+		assert_eq!(
+			json,
+			serde_json::to_value(TransactionV2::EIP1559(eip1559())).unwrap()
+		);
+		assert_eq!(serde_json::from_value::<TestTransaction>(json).unwrap(), tx);
+	}
+
+	#[cfg(feature = "with-scale")]
+	#[test]
+	fn enveloped_enum_scale() {
+		use scale_codec::{Decode, Encode};
+
+		let tx = TestTransaction::from(eip1559());
+		let encoded = Encode::encode(&tx);
+		assert_eq!(encoded, Encode::encode(&TransactionV2::EIP1559(eip1559())));
+		assert_eq!(
+			<TestTransaction as Decode>::decode(&mut &encoded[..]),
+			Ok(tx)
+		);
+	}
+}
@@ -0,0 +1,598 @@
+//! Reader and writer for Era1 pre-merge history archives.
+//!
+//! An Era1 file is an e2store file holding up to [`MAX_BLOCKS`] consecutive
+//! blocks. Each block is stored as its snappy compressed header, body and
+//! receipts, followed by its total difficulty. The blocks are followed by the
+//! epoch accumulator root and a block index.
+//!
+//! Era1 files only hold blocks up to the merge, so headers may carry the
+//! London base fee but no later field: blocks with withdrawals or blob gas
+//! fields are rejected.
+
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use ethereum_types::{H256, U256};
+use rlp::{DecoderError, Rlp, RlpStream};
+use sha2::{Digest, Sha256};
+
+use crate::{
+	block::Block,
+	enveloped::{append_enveloped_list, decode_enveloped_list},
+	header::Header,
+	receipt::ReceiptAny,
+	transaction::TransactionAny,
+};
+
+/// Maximum number of blocks in an Era1 file.
+pub const MAX_BLOCKS: usize = 8192;
+
+/// Record type of the version record.
+pub const VERSION: [u8; 2] = [0x65, 0x32];
+/// Record type of a snappy compressed RLP header.
+pub const COMPRESSED_HEADER: [u8; 2] = [0x03, 0x00];
+/// Record type of a snappy compressed RLP body.
+pub const COMPRESSED_BODY: [u8; 2] = [0x04, 0x00];
+/// Record type of snappy compressed RLP receipts.
+pub const COMPRESSED_RECEIPTS: [u8; 2] = [0x05, 0x00];
+/// Record type of a little endian total difficulty.
+pub const TOTAL_DIFFICULTY: [u8; 2] = [0x06, 0x00];
+/// Record type of the epoch accumulator root.
+pub const ACCUMULATOR: [u8; 2] = [0x07, 0x00];
+/// Record type of the block index.
+pub const BLOCK_INDEX: [u8; 2] = [0x66, 0x32];
+
+/// Length of an e2store record header.
+const HEADER_LEN: u64 = 8;
+
+/// Record type and content of an e2store record.
+type Record = ([u8; 2], Vec<u8>);
+
+/// Error returned when reading or writing an Era1 file.
+#[derive(Debug)]
+pub enum Era1Error {
+	/// Underlying reader or writer failed.
+	Io(io::Error),
+	/// Record has an unexpected type.
+	UnexpectedRecord { expected: [u8; 2], found: [u8; 2] },
+	/// Record content is malformed.
+	InvalidRecord(&'static str),
+	/// Compressed RLP content is malformed.
+	Rlp(DecoderError),
+	/// Block number does not follow the previous block.
+	NonSequentialBlock { expected: U256, found: U256 },
+	/// File holds more than [`MAX_BLOCKS`] blocks.
+	TooManyBlocks,
+	/// Accumulator root does not match the blocks of the file.
+	AccumulatorMismatch { expected: H256, computed: H256 },
+}
+
+impl From<io::Error> for Era1Error {
+	fn from(e: io::Error) -> Self {
+		Self::Io(e)
+	}
+}
+
+impl From<DecoderError> for Era1Error {
+	fn from(e: DecoderError) -> Self {
+		Self::Rlp(e)
+	}
+}
+
+/// Block stored in an Era1 file, with its receipts and total difficulty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Era1Block {
+	pub block: Block<TransactionAny>,
+	pub receipts: Vec<ReceiptAny>,
+	pub total_difficulty: U256,
+}
+
+/// Root of the epoch accumulator of the given block hashes and total difficulties.
+///
+/// This is the SSZ hash tree root of a list of header records with a limit of
+/// [`MAX_BLOCKS`] entries.
+#[must_use]
+pub fn accumulator_root(records: &[(H256, U256)]) -> H256 {
+	fn hash(left: &[u8], right: &[u8]) -> [u8; 32] {
+		let mut hasher = Sha256::new();
+		hasher.update(left);
+		hasher.update(right);
+		hasher.finalize().into()
+	}
+
+	let mut layer: Vec<[u8; 32]> = records
+		.iter()
+		.map(|(block_hash, total_difficulty)| {
+			let mut difficulty = [0; 32];
+			total_difficulty.to_little_endian(&mut difficulty);
+			hash(block_hash.as_bytes(), &difficulty)
+		})
+		.collect();
+
+	let mut zero = [0; 32];
+	let mut width = MAX_BLOCKS;
+	while width > 1 {
+		if layer.len() % 2 == 1 {
+			layer.push(zero);
+		}
+		layer = layer
+			.chunks_exact(2)
+			.map(|pair| hash(&pair[0], &pair[1]))
+			.collect();
+		zero = hash(&zero, &zero);
+		width /= 2;
+	}
+	let root = layer.first().copied().unwrap_or(zero);
+
+	let mut length = [0; 32];
+	length[..8].copy_from_slice(&(records.len() as u64).to_le_bytes());
+	H256(hash(&root, &length))
+}
+
+fn check_pre_merge(block: &Block<TransactionAny>) -> Result<(), Era1Error> {
+	// The base fee is the only optional field of pre-merge headers.
+	if block.header.optional_fields() > 1 || block.withdrawals.is_some() {
+		return Err(Era1Error::InvalidRecord("post-merge block"));
+	}
+	Ok(())
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, Era1Error> {
+	let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+	encoder.write_all(data)?;
+	encoder
+		.into_inner()
+		.map_err(|e| Era1Error::Io(e.into_error()))
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, Era1Error> {
+	let mut out = Vec::new();
+	snap::read::FrameDecoder::new(data).read_to_end(&mut out)?;
+	Ok(out)
+}
+
+/// Streaming writer of an Era1 file.
+pub struct Era1Writer<W> {
+	writer: W,
+	position: u64,
+	starting_number: Option<U256>,
+	offsets: Vec<u64>,
+	records: Vec<(H256, U256)>,
+}
+
+impl<W: Write> Era1Writer<W> {
+	/// Start a new file, writing the version record.
+	pub fn new(writer: W) -> Result<Self, Era1Error> {
+		let mut era = Self {
+			writer,
+			position: 0,
+			starting_number: None,
+			offsets: Vec::new(),
+			records: Vec::new(),
+		};
+		era.write_record(VERSION, &[])?;
+		Ok(era)
+	}
+
+	fn write_record(&mut self, kind: [u8; 2], data: &[u8]) -> Result<(), Era1Error> {
+		let len = u32::try_from(data.len()).map_err(|_| Era1Error::InvalidRecord("too large"))?;
+		self.writer.write_all(&kind)?;
+		self.writer.write_all(&len.to_le_bytes())?;
+		self.writer.write_all(&[0, 0])?;
+		self.writer.write_all(data)?;
+		self.position += HEADER_LEN + u64::from(len);
+		Ok(())
+	}
+
+	/// Append the next block of the file.
+	pub fn append(&mut self, block: &Era1Block) -> Result<(), Era1Error> {
+		if self.records.len() == MAX_BLOCKS {
+			return Err(Era1Error::TooManyBlocks);
+		}
+		check_pre_merge(&block.block)?;
+		let number = block.block.header.number;
+		let starting_number = *self.starting_number.get_or_insert(number);
+		let expected = starting_number + self.records.len();
+		if number != expected {
+			return Err(Era1Error::NonSequentialBlock {
+				expected,
+				found: number,
+			});
+		}
+
+		let mut body = RlpStream::new_list(2);
+		append_enveloped_list(&mut body, &block.block.transactions);
+		body.append_list(&block.block.ommers);
+
+		let mut receipts = RlpStream::new();
+		append_enveloped_list(&mut receipts, &block.receipts);
+
+		let mut total_difficulty = [0; 32];
+		block
+			.total_difficulty
+			.to_little_endian(&mut total_difficulty);
+
+		self.offsets.push(self.position);
+		self.write_record(
+			COMPRESSED_HEADER,
+			&compress(&rlp::encode(&block.block.header))?,
+		)?;
+		self.write_record(COMPRESSED_BODY, &compress(&body.out())?)?;
+		self.write_record(COMPRESSED_RECEIPTS, &compress(&receipts.out())?)?;
+		self.write_record(TOTAL_DIFFICULTY, &total_difficulty)?;
+
+		self.records
+			.push((block.block.header.hash(), block.total_difficulty));
+		Ok(())
+	}
+
+	/// Write the accumulator and the block index, returning the writer and
+	/// the accumulator root.
+	pub fn finish(mut self) -> Result<(W, H256), Era1Error> {
+		let root = accumulator_root(&self.records);
+		self.write_record(ACCUMULATOR, root.as_bytes())?;
+
+		let index_position = self.position;
+		let starting_number = self.starting_number.unwrap_or_default();
+		if starting_number > U256::from(u64::MAX) {
+			return Err(Era1Error::InvalidRecord("block number out of range"));
+		}
+
+		let mut index = Vec::with_capacity(16 + 8 * self.offsets.len());
+		index.extend_from_slice(&starting_number.as_u64().to_le_bytes());
+		for offset in &self.offsets {
+			let relative = *offset as i64 - index_position as i64;
+			index.extend_from_slice(&relative.to_le_bytes());
+		}
+		index.extend_from_slice(&(self.offsets.len() as u64).to_le_bytes());
+		self.write_record(BLOCK_INDEX, &index)?;
+
+		self.writer.flush()?;
+		Ok((self.writer, root))
+	}
+}
+
+/// Streaming reader of an Era1 file.
+///
+/// Blocks are yielded as they are read. The accumulator root is checked once
+/// all blocks have been read, so blocks should only be trusted after the
+/// iterator finished without error.
+pub struct Era1Reader<R> {
+	reader: R,
+	records: Vec<(H256, U256)>,
+	accumulator: Option<H256>,
+	done: bool,
+}
+
+impl<R: Read> Era1Reader<R> {
+	/// Open a file, reading the version record.
+	pub fn new(reader: R) -> Result<Self, Era1Error> {
+		let mut era = Self {
+			reader,
+			records: Vec::new(),
+			accumulator: None,
+			done: false,
+		};
+
+		let (kind, _) = era
+			.read_record()?
+			.ok_or(Era1Error::InvalidRecord("empty file"))?;
+		if kind != VERSION {
+			return Err(Era1Error::UnexpectedRecord {
+				expected: VERSION,
+				found: kind,
+			});
+		}
+		Ok(era)
+	}
+
+	/// Verified accumulator root, available once all blocks have been read.
+	#[must_use]
+	pub fn accumulator(&self) -> Option<H256> {
+		self.accumulator
+	}
+
+	fn read_record(&mut self) -> Result<Option<Record>, Era1Error> {
+		let mut header = [0; HEADER_LEN as usize];
+		match self.reader.read_exact(&mut header) {
+			Ok(()) => (),
+			Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+			Err(e) => return Err(e.into()),
+		}
+
+		let kind = [header[0], header[1]];
+		let len = u32::from_le_bytes([header[2], header[3], header[4], header[5]]);
+		if header[6..] != [0, 0] {
+			return Err(Era1Error::InvalidRecord("non-zero reserved bytes"));
+		}
+
+		let mut data = Vec::new();
+		self.reader
+			.by_ref()
+			.take(u64::from(len))
+			.read_to_end(&mut data)?;
+		if data.len() != len as usize {
+			return Err(Era1Error::InvalidRecord("truncated record"));
+		}
+		Ok(Some((kind, data)))
+	}
+
+	fn expect_record(&mut self, expected: [u8; 2]) -> Result<Vec<u8>, Era1Error> {
+		match self.read_record()? {
+			Some((kind, data)) if kind == expected => Ok(data),
+			Some((found, _)) => Err(Era1Error::UnexpectedRecord { expected, found }),
+			None => Err(Era1Error::InvalidRecord("unexpected end of file")),
+		}
+	}
+
+	fn read_block(&mut self, header: &[u8]) -> Result<Era1Block, Era1Error> {
+		if self.records.len() == MAX_BLOCKS {
+			return Err(Era1Error::TooManyBlocks);
+		}
+
+		let header: Header = rlp::decode(&decompress(header)?)?;
+
+		let body = decompress(&self.expect_record(COMPRESSED_BODY)?)?;
+		let body = Rlp::new(&body);
+		if body.item_count()? != 2 {
+			return Err(DecoderError::RlpIncorrectListLen.into());
+		}
+		let transactions = decode_enveloped_list(&body.at(0)?)?;
+		let ommers = body.list_at(1)?;
+
+		let receipts = decompress(&self.expect_record(COMPRESSED_RECEIPTS)?)?;
+		let receipts = decode_enveloped_list(&Rlp::new(&receipts))?;
+
+		let total_difficulty = self.expect_record(TOTAL_DIFFICULTY)?;
+		if total_difficulty.len() != 32 {
+			return Err(Era1Error::InvalidRecord("total difficulty length"));
+		}
+		let total_difficulty = U256::from_little_endian(&total_difficulty);
+
+		let block = Block {
+			header,
+			transactions,
+			ommers,
+			withdrawals: None,
+		};
+		check_pre_merge(&block)?;
+
+		self.records.push((block.header.hash(), total_difficulty));
+		Ok(Era1Block {
+			block,
+			receipts,
+			total_difficulty,
+		})
+	}
+
+	fn read_trailer(&mut self, accumulator: &[u8]) -> Result<(), Era1Error> {
+		if accumulator.len() != 32 {
+			return Err(Era1Error::InvalidRecord("accumulator length"));
+		}
+		let expected = H256::from_slice(accumulator);
+
+		let index = self.expect_record(BLOCK_INDEX)?;
+		if index.len() != 16 + 8 * self.records.len() {
+			return Err(Era1Error::InvalidRecord("block index length"));
+		}
+		let mut count = [0; 8];
+		count.copy_from_slice(&index[index.len() - 8..]);
+		if u64::from_le_bytes(count) != self.records.len() as u64 {
+			return Err(Era1Error::InvalidRecord("block index count"));
+		}
+
+		let computed = accumulator_root(&self.records);
+		if computed != expected {
+			return Err(Era1Error::AccumulatorMismatch { expected, computed });
+		}
+		self.accumulator = Some(computed);
+		Ok(())
+	}
+
+	fn next_block(&mut self) -> Result<Option<Era1Block>, Era1Error> {
+		loop {
+			let Some((kind, data)) = self.read_record()? else {
+				return Err(Era1Error::InvalidRecord("missing accumulator"));
+			};
+
+			match kind {
+				COMPRESSED_HEADER => return self.read_block(&data).map(Some),
+				ACCUMULATOR => {
+					self.read_trailer(&data)?;
+					return Ok(None);
+				}
+				// Other entries are allowed after the blocks and are skipped.
+				_ => continue,
+			}
+		}
+	}
+}
+
+impl<R: Read> Iterator for Era1Reader<R> {
+	type Item = Result<Era1Block, Era1Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		let next = self.next_block().transpose();
+		if !matches!(next, Some(Ok(_))) {
+			self.done = true;
+		}
+		next
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		block::{ommers_hash, transactions_root},
+		header::PartialHeader,
+		receipt::{EIP658ReceiptData, FrontierReceiptData},
+		test_utils::partial_header,
+		transaction::{
+			EIP2930Transaction, LegacyTransaction, TransactionAction, TransactionSignature,
+		},
+	};
+	use ethereum_types::H160;
+
+	fn era_block(number: u64, parent_hash: H256) -> Era1Block {
+		let transactions = vec![
+			TransactionAny::Legacy(LegacyTransaction {
+				nonce: number.into(),
+				gas_price: 1.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Call(H160::repeat_byte(1)),
+				value: 1.into(),
+				input: Vec::new(),
+				signature: TransactionSignature::new(
+					27,
+					H256::repeat_byte(2),
+					H256::repeat_byte(3),
+				)
+				.unwrap(),
+			}),
+			TransactionAny::EIP2930(EIP2930Transaction {
+				chain_id: 1,
+				nonce: number.into(),
+				gas_price: 1.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Create,
+				value: U256::zero(),
+				input: vec![0x60],
+				access_list: Vec::new(),
+				odd_y_parity: false,
+				r: H256::repeat_byte(2),
+				s: H256::repeat_byte(3),
+			}),
+		];
+		let legacy_receipt = if number == 100 {
+			ReceiptAny::Frontier(FrontierReceiptData::new(
+				H256::repeat_byte(4),
+				21_000.into(),
+				Vec::new(),
+			))
+		} else {
+			ReceiptAny::EIP658(EIP658ReceiptData::new(1, 21_000.into(), Vec::new()))
+		};
+		let receipts = vec![
+			legacy_receipt,
+			ReceiptAny::EIP2930(EIP658ReceiptData::new(1, 42_000.into(), Vec::new())),
+		];
+
+		let header = Header::new(
+			PartialHeader {
+				parent_hash,
+				beneficiary: H160::repeat_byte(5),
+				state_root: H256::repeat_byte(6),
+				receipts_root: H256::repeat_byte(7),
+				difficulty: 131_072.into(),
+				number: number.into(),
+				gas_limit: 5_000_000.into(),
+				gas_used: 42_000.into(),
+				timestamp: 1_500_000_000 + number,
+				// Last block is a London one.
+				base_fee_per_gas: (number == 102).then(|| 7.into()),
+				..partial_header()
+			},
+			ommers_hash(&[]),
+			transactions_root(&transactions),
+		);
+		Era1Block {
+			block: Block {
+				header,
+				transactions,
+				ommers: Vec::new(),
+				withdrawals: None,
+			},
+			receipts,
+			total_difficulty: (131_072 * (number + 1)).into(),
+		}
+	}
+
+	fn era_blocks() -> Vec<Era1Block> {
+		let mut blocks = Vec::new();
+		let mut parent_hash = H256::zero();
+		for number in 100..103 {
+			let block = era_block(number, parent_hash);
+			parent_hash = block.block.header.hash();
+			blocks.push(block);
+		}
+		blocks
+	}
+
+	#[test]
+	fn write_and_read() {
+		let blocks = era_blocks();
+		let mut writer = Era1Writer::new(Vec::new()).unwrap();
+		for block in &blocks {
+			writer.append(block).unwrap();
+		}
+		let (file, root) = writer.finish().unwrap();
+		assert_eq!(&file[..8], &[0x65, 0x32, 0, 0, 0, 0, 0, 0]);
+
+		let mut reader = Era1Reader::new(&file[..]).unwrap();
+		let read: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+		assert_eq!(read, blocks);
+		assert_eq!(reader.accumulator(), Some(root));
+
+		let mut writer = Era1Writer::new(Vec::new()).unwrap();
+		writer.append(&blocks[0]).unwrap();
+		assert!(matches!(
+			writer.append(&blocks[2]),
+			Err(Era1Error::NonSequentialBlock { .. })
+		));
+	}
+
+	#[test]
+	fn reject_post_merge_blocks() {
+		let mut block = era_block(100, H256::zero());
+		block.block.header.base_fee_per_gas = Some(7.into());
+		block.block.header.withdrawals_root = Some(H256::zero());
+		let mut writer = Era1Writer::new(Vec::new()).unwrap();
+		assert!(matches!(
+			writer.append(&block),
+			Err(Era1Error::InvalidRecord("post-merge block"))
+		));
+
+		let mut block = era_block(100, H256::zero());
+		block.block.withdrawals = Some(Vec::new());
+		assert!(matches!(
+			writer.append(&block),
+			Err(Era1Error::InvalidRecord("post-merge block"))
+		));
+	}
+
+	#[test]
+	fn detect_accumulator_mismatch() {
+		let mut blocks = era_blocks();
+		let mut writer = Era1Writer::new(Vec::new()).unwrap();
+		for block in &blocks {
+			writer.append(block).unwrap();
+		}
+		let (_, root) = writer.finish().unwrap();
+
+		blocks[1].total_difficulty += U256::one();
+		let mut writer = Era1Writer::new(Vec::new()).unwrap();
+		for block in &blocks {
+			writer.append(block).unwrap();
+		}
+		let (mut file, _) = writer.finish().unwrap();
+
+		// Replace the accumulator root, followed by the index of three blocks,
+		// by the one of the original blocks.
+		let position = file.len() - 48 - 32;
+		assert_eq!(&file[position - 8..position - 6], &ACCUMULATOR);
+		file[position..position + 32].copy_from_slice(root.as_bytes());
+
+		let results: Vec<_> = Era1Reader::new(&file[..]).unwrap().collect();
+		assert_eq!(results.len(), 4);
+		assert!(results[..3].iter().all(Result::is_ok));
+		assert!(matches!(
+			results[3],
+			Err(Era1Error::AccumulatorMismatch { .. })
+		));
+	}
+}
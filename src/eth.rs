@@ -0,0 +1,915 @@
+//! Messages of the devp2p `eth` wire protocol, versions 68 and 69.
+//!
+//! Messages are encoded without the devp2p message id and without snappy
+//! compression. Requests and responses carry the eth/66 request id.
+
+use alloc::vec::Vec;
+
+use ethereum_types::{H256, H32, U256};
+use rlp::{DecoderError, Rlp, RlpStream};
+
+use crate::{
+	block::BlockV2,
+	enveloped::{append_enveloped_list, decode_enveloped_list, EnvelopedEncodable},
+	header::Header,
+	limits::DecodeLimits,
+	receipt::{EIP658ReceiptData, ReceiptV3},
+	transaction::TransactionV2,
+	withdrawal::Withdrawal,
+};
+
+/// Protocol version eth/68.
+pub const ETH_68: u8 = 68;
+/// Protocol version eth/69, with bloomless receipts and block range updates.
+pub const ETH_69: u8 = 69;
+
+/// Message ids of the `eth` protocol, relative to the protocol offset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageId {
+	Status = 0x00,
+	NewBlockHashes = 0x01,
+	Transactions = 0x02,
+	GetBlockHeaders = 0x03,
+	BlockHeaders = 0x04,
+	GetBlockBodies = 0x05,
+	BlockBodies = 0x06,
+	NewBlock = 0x07,
+	NewPooledTransactionHashes = 0x08,
+	GetPooledTransactions = 0x09,
+	PooledTransactions = 0x0a,
+	GetReceipts = 0x0f,
+	Receipts = 0x10,
+	BlockRangeUpdate = 0x11,
+}
+
+impl TryFrom<u8> for MessageId {
+	type Error = DecoderError;
+
+	fn try_from(id: u8) -> Result<Self, Self::Error> {
+		Ok(match id {
+			0x00 => Self::Status,
+			0x01 => Self::NewBlockHashes,
+			0x02 => Self::Transactions,
+			0x03 => Self::GetBlockHeaders,
+			0x04 => Self::BlockHeaders,
+			0x05 => Self::GetBlockBodies,
+			0x06 => Self::BlockBodies,
+			0x07 => Self::NewBlock,
+			0x08 => Self::NewPooledTransactionHashes,
+			0x09 => Self::GetPooledTransactions,
+			0x0a => Self::PooledTransactions,
+			0x0f => Self::GetReceipts,
+			0x10 => Self::Receipts,
+			0x11 => Self::BlockRangeUpdate,
+			_ => return Err(DecoderError::Custom("unknown eth message id")),
+		})
+	}
+}
+
+/// EIP-2124 fork identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct ForkId {
+	/// CRC32 checksum of the genesis hash and the passed fork blocks.
+	pub hash: H32,
+	/// Next upcoming fork block, or zero if none is known.
+	pub next: u64,
+}
+
+/// Handshake message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct Status {
+	pub version: u8,
+	pub network_id: u64,
+	pub total_difficulty: U256,
+	pub best_hash: H256,
+	pub genesis_hash: H256,
+	pub fork_id: ForkId,
+}
+
+/// Announced block hash with its number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct BlockHashNumber {
+	pub hash: H256,
+	pub number: u64,
+}
+
+/// Announcement of new blocks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(rlp::RlpEncodableWrapper, rlp::RlpDecodableWrapper)]
+pub struct NewBlockHashes(pub Vec<BlockHashNumber>);
+
+/// Full transactions propagated to peers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transactions(pub Vec<TransactionV2>);
+
+impl rlp::Encodable for Transactions {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		append_enveloped_list(s, &self.0);
+	}
+}
+
+impl rlp::Decodable for Transactions {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		Ok(Self(decode_enveloped_list(rlp)?))
+	}
+}
+
+/// Origin of a header request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashOrNumber {
+	Hash(H256),
+	Number(u64),
+}
+
+impl rlp::Encodable for HashOrNumber {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		match self {
+			Self::Hash(hash) => hash.rlp_append(s),
+			Self::Number(number) => number.rlp_append(s),
+		}
+	}
+}
+
+impl rlp::Decodable for HashOrNumber {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.size() == H256::len_bytes() {
+			Ok(Self::Hash(rlp.as_val()?))
+		} else {
+			Ok(Self::Number(rlp.as_val()?))
+		}
+	}
+}
+
+/// Request for a range of headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GetBlockHeaders {
+	pub request_id: u64,
+	pub start: HashOrNumber,
+	pub limit: u64,
+	pub skip: u64,
+	pub reverse: bool,
+}
+
+impl rlp::Encodable for GetBlockHeaders {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&self.request_id);
+		s.begin_list(4);
+		s.append(&self.start);
+		s.append(&self.limit);
+		s.append(&self.skip);
+		s.append(&self.reverse);
+	}
+}
+
+impl rlp::Decodable for GetBlockHeaders {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 2 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let request = rlp.at(1)?;
+		if request.item_count()? != 4 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			request_id: rlp.val_at(0)?,
+			start: request.val_at(0)?,
+			limit: request.val_at(1)?,
+			skip: request.val_at(2)?,
+			reverse: request.val_at(3)?,
+		})
+	}
+}
+
+/// Response to [`GetBlockHeaders`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct BlockHeaders {
+	pub request_id: u64,
+	pub headers: Vec<Header>,
+}
+
+/// Request for the bodies of the given blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetBlockBodies {
+	pub request_id: u64,
+	pub hashes: Vec<H256>,
+}
+
+/// Transactions, ommers and withdrawals of a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockBody {
+	pub transactions: Vec<TransactionV2>,
+	pub ommers: Vec<Header>,
+	/// Withdrawals, since Shanghai (EIP-4895).
+	pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl rlp::Encodable for BlockBody {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2 + usize::from(self.withdrawals.is_some()));
+		append_enveloped_list(s, &self.transactions);
+		s.append_list(&self.ommers);
+		if let Some(withdrawals) = &self.withdrawals {
+			s.append_list(withdrawals);
+		}
+	}
+}
+
+impl rlp::Decodable for BlockBody {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		let withdrawals = match rlp.item_count()? {
+			2 => None,
+			3 => Some(rlp.list_at(2)?),
+			_ => return Err(DecoderError::RlpIncorrectListLen),
+		};
+
+		Ok(Self {
+			transactions: decode_enveloped_list(&rlp.at(0)?)?,
+			ommers: rlp.list_at(1)?,
+			withdrawals,
+		})
+	}
+}
+
+impl From<BlockV2> for BlockBody {
+	fn from(block: BlockV2) -> Self {
+		Self {
+			transactions: block.transactions,
+			ommers: block.ommers,
+			withdrawals: block.withdrawals,
+		}
+	}
+}
+
+/// Response to [`GetBlockBodies`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct BlockBodies {
+	pub request_id: u64,
+	pub bodies: Vec<BlockBody>,
+}
+
+/// Propagation of a full block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct NewBlock {
+	pub block: BlockV2,
+	pub total_difficulty: U256,
+}
+
+/// Announcement of transactions available in the pool.
+///
+/// The three lists are parallel: entry `i` of each describes the same transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NewPooledTransactionHashes {
+	/// Transaction types, `0` for legacy transactions.
+	pub types: Vec<u8>,
+	/// Sizes of the transactions in their network form.
+	pub sizes: Vec<u32>,
+	pub hashes: Vec<H256>,
+}
+
+impl rlp::Encodable for NewPooledTransactionHashes {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(3);
+		s.append(&self.types);
+		s.append_list(&self.sizes);
+		s.append_list(&self.hashes);
+	}
+}
+
+impl rlp::Decodable for NewPooledTransactionHashes {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 3 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let announcement = Self {
+			types: rlp.val_at(0)?,
+			sizes: rlp.list_at(1)?,
+			hashes: rlp.list_at(2)?,
+		};
+		if announcement.types.len() != announcement.hashes.len()
+			|| announcement.sizes.len() != announcement.hashes.len()
+		{
+			return Err(DecoderError::Custom("mismatched announcement lengths"));
+		}
+
+		Ok(announcement)
+	}
+}
+
+/// Request for pooled transactions by hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetPooledTransactions {
+	pub request_id: u64,
+	pub hashes: Vec<H256>,
+}
+
+/// Response to [`GetPooledTransactions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PooledTransactions {
+	pub request_id: u64,
+	pub transactions: Vec<TransactionV2>,
+}
+
+impl rlp::Encodable for PooledTransactions {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&self.request_id);
+		append_enveloped_list(s, &self.transactions);
+	}
+}
+
+impl rlp::Decodable for PooledTransactions {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 2 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			request_id: rlp.val_at(0)?,
+			transactions: decode_enveloped_list(&rlp.at(1)?)?,
+		})
+	}
+}
+
+/// Request for the receipts of the given blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetReceipts {
+	pub request_id: u64,
+	pub hashes: Vec<H256>,
+}
+
+/// Response to [`GetReceipts`], holding the receipts of each requested block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Receipts {
+	pub request_id: u64,
+	pub receipts: Vec<Vec<ReceiptV3>>,
+}
+
+impl rlp::Encodable for Receipts {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&self.request_id);
+		s.begin_list(self.receipts.len());
+		for receipts in &self.receipts {
+			append_enveloped_list(s, receipts);
+		}
+	}
+}
+
+impl rlp::Decodable for Receipts {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 2 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			request_id: rlp.val_at(0)?,
+			receipts: rlp
+				.at(1)?
+				.iter()
+				.map(|receipts| decode_enveloped_list(&receipts))
+				.collect::<Result<_, _>>()?,
+		})
+	}
+}
+
+/// Handshake message of eth/69, without the total difficulty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct Status69 {
+	pub version: u8,
+	pub network_id: u64,
+	pub genesis_hash: H256,
+	pub fork_id: ForkId,
+	/// Number of the earliest block the node can serve.
+	pub earliest_block: u64,
+	pub latest_block: u64,
+	pub latest_block_hash: H256,
+}
+
+/// Update of the block range served by a peer, introduced in eth/69.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct BlockRangeUpdate {
+	pub earliest_block: u64,
+	pub latest_block: u64,
+	pub latest_block_hash: H256,
+}
+
+/// Receipt in the eth/69 network form.
+///
+/// The receipt is always a list of the transaction type, status, cumulative
+/// gas used and logs. The logs bloom is not sent and is recomputed on decode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BloomlessReceipt(pub ReceiptV3);
+
+impl rlp::Encodable for BloomlessReceipt {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		let data = match &self.0 {
+			ReceiptV3::Legacy(r) | ReceiptV3::EIP2930(r) | ReceiptV3::EIP1559(r) => r,
+		};
+
+		s.begin_list(4);
+		s.append(&self.0.type_id().unwrap_or(0));
+		s.append(&data.status_code);
+		s.append(&data.used_gas);
+		s.append_list(&data.logs);
+	}
+}
+
+impl rlp::Decodable for BloomlessReceipt {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 4 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let data = EIP658ReceiptData::new(rlp.val_at(1)?, rlp.val_at(2)?, rlp.list_at(3)?);
+		match rlp.val_at::<u8>(0)? {
+			0 => Ok(Self(ReceiptV3::Legacy(data))),
+			1 => Ok(Self(ReceiptV3::EIP2930(data))),
+			2 => Ok(Self(ReceiptV3::EIP1559(data))),
+			_ => Err(DecoderError::Custom("invalid receipt type")),
+		}
+	}
+}
+
+/// Response to [`GetReceipts`] in eth/69, with bloomless receipts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Receipts69 {
+	pub request_id: u64,
+	pub receipts: Vec<Vec<BloomlessReceipt>>,
+}
+
+impl rlp::Encodable for Receipts69 {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&self.request_id);
+		s.begin_list(self.receipts.len());
+		for receipts in &self.receipts {
+			s.append_list(receipts);
+		}
+	}
+}
+
+impl rlp::Decodable for Receipts69 {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 2 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			request_id: rlp.val_at(0)?,
+			receipts: rlp
+				.at(1)?
+				.iter()
+				.map(|receipts| receipts.as_list())
+				.collect::<Result<_, _>>()?,
+		})
+	}
+}
+
+impl From<Receipts> for Receipts69 {
+	fn from(receipts: Receipts) -> Self {
+		Self {
+			request_id: receipts.request_id,
+			receipts: receipts
+				.receipts
+				.into_iter()
+				.map(|receipts| receipts.into_iter().map(BloomlessReceipt).collect())
+				.collect(),
+		}
+	}
+}
+
+impl From<Receipts69> for Receipts {
+	fn from(receipts: Receipts69) -> Self {
+		Self {
+			request_id: receipts.request_id,
+			receipts: receipts
+				.receipts
+				.into_iter()
+				.map(|receipts| receipts.into_iter().map(|r| r.0).collect())
+				.collect(),
+		}
+	}
+}
+
+/// Any message of the `eth` protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EthMessage {
+	Status(Status),
+	NewBlockHashes(NewBlockHashes),
+	Transactions(Transactions),
+	GetBlockHeaders(GetBlockHeaders),
+	BlockHeaders(BlockHeaders),
+	GetBlockBodies(GetBlockBodies),
+	BlockBodies(BlockBodies),
+	NewBlock(alloc::boxed::Box<NewBlock>),
+	NewPooledTransactionHashes(NewPooledTransactionHashes),
+	GetPooledTransactions(GetPooledTransactions),
+	PooledTransactions(PooledTransactions),
+	GetReceipts(GetReceipts),
+	Receipts(Receipts),
+	Status69(Status69),
+	Receipts69(Receipts69),
+	BlockRangeUpdate(BlockRangeUpdate),
+}
+
+impl EthMessage {
+	/// Id of the message.
+	#[must_use]
+	pub fn id(&self) -> MessageId {
+		match self {
+			Self::Status(_) | Self::Status69(_) => MessageId::Status,
+			Self::NewBlockHashes(_) => MessageId::NewBlockHashes,
+			Self::Transactions(_) => MessageId::Transactions,
+			Self::GetBlockHeaders(_) => MessageId::GetBlockHeaders,
+			Self::BlockHeaders(_) => MessageId::BlockHeaders,
+			Self::GetBlockBodies(_) => MessageId::GetBlockBodies,
+			Self::BlockBodies(_) => MessageId::BlockBodies,
+			Self::NewBlock(_) => MessageId::NewBlock,
+			Self::NewPooledTransactionHashes(_) => MessageId::NewPooledTransactionHashes,
+			Self::GetPooledTransactions(_) => MessageId::GetPooledTransactions,
+			Self::PooledTransactions(_) => MessageId::PooledTransactions,
+			Self::GetReceipts(_) => MessageId::GetReceipts,
+			Self::Receipts(_) | Self::Receipts69(_) => MessageId::Receipts,
+			Self::BlockRangeUpdate(_) => MessageId::BlockRangeUpdate,
+		}
+	}
+
+	/// RLP encoded message data, without the message id.
+	#[must_use]
+	pub fn encode(&self) -> Vec<u8> {
+		match self {
+			Self::Status(m) => rlp::encode(m),
+			Self::NewBlockHashes(m) => rlp::encode(m),
+			Self::Transactions(m) => rlp::encode(m),
+			Self::GetBlockHeaders(m) => rlp::encode(m),
+			Self::BlockHeaders(m) => rlp::encode(m),
+			Self::GetBlockBodies(m) => rlp::encode(m),
+			Self::BlockBodies(m) => rlp::encode(m),
+			Self::NewBlock(m) => rlp::encode(m.as_ref()),
+			Self::NewPooledTransactionHashes(m) => rlp::encode(m),
+			Self::GetPooledTransactions(m) => rlp::encode(m),
+			Self::PooledTransactions(m) => rlp::encode(m),
+			Self::GetReceipts(m) => rlp::encode(m),
+			Self::Receipts(m) => rlp::encode(m),
+			Self::Status69(m) => rlp::encode(m),
+			Self::Receipts69(m) => rlp::encode(m),
+			Self::BlockRangeUpdate(m) => rlp::encode(m),
+		}
+		.to_vec()
+	}
+
+	/// Decode the message data of a message with the given id.
+	///
+	/// `version` selects the eth/68 or eth/69 form of the messages that differ.
+	/// Messages are checked against [`DecodeLimits::PEER`].
+	pub fn decode(version: u8, id: MessageId, data: &[u8]) -> Result<Self, DecoderError> {
+		Self::decode_with_limits(version, id, data, &DecodeLimits::PEER)
+	}
+
+	/// Decode the message data of a message with the given id, checking the
+	/// blocks and transactions it holds against `limits` first.
+	pub fn decode_with_limits(
+		version: u8,
+		id: MessageId,
+		data: &[u8],
+		limits: &DecodeLimits,
+	) -> Result<Self, DecoderError> {
+		limits.check_bytes(data)?;
+		let rlp = Rlp::new(data);
+		match id {
+			MessageId::Transactions => limits.check_transactions(&rlp)?,
+			MessageId::PooledTransactions => limits.check_transactions(&rlp.at(1)?)?,
+			MessageId::BlockBodies => {
+				for body in &rlp.at(1)? {
+					limits.check_body(&body.at(0)?, &body.at(1)?)?;
+				}
+			}
+			MessageId::NewBlock => {
+				let block = rlp.at(0)?;
+				limits.check_body(&block.at(1)?, &block.at(2)?)?;
+			}
+			_ => (),
+		}
+
+		Ok(match id {
+			MessageId::Status if version >= ETH_69 => Self::Status69(rlp::decode(data)?),
+			MessageId::Status => Self::Status(rlp::decode(data)?),
+			MessageId::NewBlockHashes => Self::NewBlockHashes(rlp::decode(data)?),
+			MessageId::Transactions => Self::Transactions(rlp::decode(data)?),
+			MessageId::GetBlockHeaders => Self::GetBlockHeaders(rlp::decode(data)?),
+			MessageId::BlockHeaders => Self::BlockHeaders(rlp::decode(data)?),
+			MessageId::GetBlockBodies => Self::GetBlockBodies(rlp::decode(data)?),
+			MessageId::BlockBodies => Self::BlockBodies(rlp::decode(data)?),
+			MessageId::NewBlock => Self::NewBlock(alloc::boxed::Box::new(rlp::decode(data)?)),
+			MessageId::NewPooledTransactionHashes => {
+				Self::NewPooledTransactionHashes(rlp::decode(data)?)
+			}
+			MessageId::GetPooledTransactions => Self::GetPooledTransactions(rlp::decode(data)?),
+			MessageId::PooledTransactions => Self::PooledTransactions(rlp::decode(data)?),
+			MessageId::GetReceipts => Self::GetReceipts(rlp::decode(data)?),
+			MessageId::Receipts if version >= ETH_69 => Self::Receipts69(rlp::decode(data)?),
+			MessageId::Receipts => Self::Receipts(rlp::decode(data)?),
+			MessageId::BlockRangeUpdate if version >= ETH_69 => {
+				Self::BlockRangeUpdate(rlp::decode(data)?)
+			}
+			MessageId::BlockRangeUpdate => {
+				return Err(DecoderError::Custom(
+					"message not supported by protocol version",
+				))
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		log::Log,
+		test_utils::partial_header,
+		transaction::{
+			EIP1559Transaction, LegacyTransaction, TransactionAction, TransactionSignature,
+		},
+	};
+	use ethereum_types::H160;
+	use hex_literal::hex;
+
+	fn transactions() -> Vec<TransactionV2> {
+		let legacy = LegacyTransaction {
+			nonce: U256::zero(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Call(H160::repeat_byte(1)),
+			value: 1.into(),
+			input: Vec::new(),
+			signature: TransactionSignature::new(38, H256::repeat_byte(2), H256::repeat_byte(3))
+				.unwrap(),
+		};
+		let typed = EIP1559Transaction {
+			chain_id: 1,
+			nonce: U256::zero(),
+			max_priority_fee_per_gas: 1.into(),
+			max_fee_per_gas: 2.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: vec![0x60],
+			access_list: Vec::new(),
+			odd_y_parity: true,
+			r: H256::repeat_byte(2),
+			s: H256::repeat_byte(3),
+		};
+		vec![legacy.into(), TransactionV2::EIP1559(typed)]
+	}
+
+	fn round_trip(message: EthMessage) {
+		let version = match message {
+			EthMessage::Status69(_)
+			| EthMessage::Receipts69(_)
+			| EthMessage::BlockRangeUpdate(_) => ETH_69,
+			_ => ETH_68,
+		};
+		let data = message.encode();
+		assert_eq!(
+			EthMessage::decode(version, message.id(), &data),
+			Ok(message)
+		);
+	}
+
+	#[test]
+	fn typed_transactions_are_strings() {
+		let transactions = transactions();
+		let encoded = rlp::encode(&Transactions(transactions.clone()));
+		let rlp = Rlp::new(&encoded);
+
+		assert!(rlp.at(0).unwrap().is_list());
+		assert_eq!(
+			rlp.at(0).unwrap().as_raw(),
+			&EnvelopedEncodable::encode(&transactions[0])[..]
+		);
+		assert!(rlp.at(1).unwrap().is_data());
+		assert_eq!(rlp.at(1).unwrap().data().unwrap()[0], 0x02);
+
+		round_trip(EthMessage::Transactions(Transactions(transactions)));
+	}
+
+	#[test]
+	fn block_body_items() {
+		let body = BlockBody {
+			transactions: transactions(),
+			ommers: Vec::new(),
+			withdrawals: Some(Vec::new()),
+		};
+		let encoded = rlp::encode(&body);
+		assert_eq!(Rlp::new(&encoded).item_count(), Ok(3));
+		assert_eq!(rlp::decode::<BlockBody>(&encoded), Ok(body));
+
+		let mut s = RlpStream::new_list(4);
+		for _ in 0..4 {
+			s.begin_list(0);
+		}
+		assert_eq!(
+			rlp::decode::<BlockBody>(&s.out()),
+			Err(DecoderError::RlpIncorrectListLen)
+		);
+	}
+
+	#[test]
+	fn new_block_uses_network_form() {
+		// Blocks are encoded as on the network, with typed transactions as
+		// byte strings, so that `NewBlock` matches other clients.
+		let block = BlockV2::new(partial_header(), transactions(), Vec::new());
+		let message = NewBlock {
+			block: block.clone(),
+			total_difficulty: 1.into(),
+		};
+		let encoded = rlp::encode(&message);
+		let transactions = Rlp::new(&encoded).at(0).unwrap().at(1).unwrap();
+		assert!(transactions.at(0).unwrap().is_list());
+		assert!(transactions.at(1).unwrap().is_data());
+		assert_eq!(
+			rlp::encode(&block),
+			Rlp::new(&encoded).at(0).unwrap().as_raw()
+		);
+
+		round_trip(EthMessage::NewBlock(Box::new(message)));
+	}
+
+	#[test]
+	fn get_block_headers() {
+		// Example from the devp2p specification.
+		let message = GetBlockHeaders {
+			request_id: 1111,
+			start: HashOrNumber::Hash(H256::from(hex!(
+				"00000000000000000000000000000000000000000000000000000000deadc0de"
+			))),
+			limit: 5,
+			skip: 5,
+			reverse: false,
+		};
+		let encoded = hex!(
+			"e8820457e4a000000000000000000000000000000000000000000000000000000000deadc0de050580"
+		);
+		assert_eq!(rlp::encode(&message)[..], encoded[..]);
+		assert_eq!(rlp::decode::<GetBlockHeaders>(&encoded), Ok(message));
+
+		round_trip(EthMessage::GetBlockHeaders(GetBlockHeaders {
+			start: HashOrNumber::Number(9_999),
+			..message
+		}));
+	}
+
+	#[test]
+	fn message_round_trips() {
+		round_trip(EthMessage::Status(Status {
+			version: ETH_68,
+			network_id: 1,
+			total_difficulty: 17_179_869_184_u64.into(),
+			best_hash: H256::repeat_byte(1),
+			genesis_hash: H256::repeat_byte(2),
+			fork_id: ForkId {
+				hash: H32::from(hex!("fc64ec04")),
+				next: 1_150_000,
+			},
+		}));
+		round_trip(EthMessage::NewPooledTransactionHashes(
+			NewPooledTransactionHashes {
+				types: vec![0, 2],
+				sizes: vec![110, 120],
+				hashes: transactions().iter().map(TransactionV2::hash).collect(),
+			},
+		));
+		round_trip(EthMessage::PooledTransactions(PooledTransactions {
+			request_id: 7,
+			transactions: transactions(),
+		}));
+		round_trip(EthMessage::BlockBodies(BlockBodies {
+			request_id: 8,
+			bodies: vec![
+				BlockBody {
+					transactions: transactions(),
+					ommers: Vec::new(),
+					withdrawals: None,
+				},
+				BlockBody {
+					transactions: Vec::new(),
+					ommers: Vec::new(),
+					withdrawals: Some(vec![Withdrawal {
+						index: 1,
+						validator_index: 2,
+						address: H160::repeat_byte(3),
+						amount: 4,
+					}]),
+				},
+				BlockBody::default(),
+			],
+		}));
+
+		let receipt = EIP658ReceiptData::new(1, 21_000.into(), Vec::new());
+		round_trip(EthMessage::Receipts(Receipts {
+			request_id: 9,
+			receipts: vec![
+				vec![
+					ReceiptV3::Legacy(receipt.clone()),
+					ReceiptV3::EIP1559(receipt),
+				],
+				Vec::new(),
+			],
+		}));
+	}
+
+	#[test]
+	fn message_limits() {
+		let message = EthMessage::BlockBodies(BlockBodies {
+			request_id: 1,
+			bodies: vec![BlockBody {
+				transactions: transactions(),
+				ommers: Vec::new(),
+				withdrawals: None,
+			}],
+		});
+		let data = message.encode();
+		let limits = DecodeLimits {
+			max_transactions: 1,
+			..DecodeLimits::PEER
+		};
+		assert_eq!(
+			EthMessage::decode_with_limits(ETH_68, MessageId::BlockBodies, &data, &limits),
+			Err(DecoderError::Custom("transactions limit exceeded"))
+		);
+
+		let data = vec![0; DecodeLimits::PEER.max_bytes + 1];
+		assert_eq!(
+			EthMessage::decode(ETH_68, MessageId::Transactions, &data),
+			Err(DecoderError::Custom("input size limit exceeded"))
+		);
+	}
+
+	#[test]
+	fn bloomless_receipts() {
+		let receipt = ReceiptV3::EIP1559(EIP658ReceiptData::new(
+			1,
+			42_000.into(),
+			vec![Log {
+				address: H160::repeat_byte(1),
+				topics: vec![H256::repeat_byte(2)],
+				data: vec![3],
+			}],
+		));
+		let receipts = Receipts {
+			request_id: 10,
+			receipts: vec![vec![receipt.clone()], Vec::new()],
+		};
+
+		let bloomless = Receipts69::from(receipts.clone());
+		let encoded = rlp::encode(&bloomless);
+		assert!(encoded.len() + 256 < rlp::encode(&receipts).len());
+
+		let rlp = Rlp::new(&encoded);
+		let item = rlp.at(1).unwrap().at(0).unwrap().at(0).unwrap();
+		assert_eq!(item.item_count(), Ok(4));
+		assert_eq!(item.val_at::<u8>(0), Ok(2));
+
+		let decoded: Receipts69 = rlp::decode(&encoded).unwrap();
+		assert_eq!(Receipts::from(decoded.clone()), receipts);
+		round_trip(EthMessage::Receipts69(decoded));
+
+		round_trip(EthMessage::Status69(Status69 {
+			version: ETH_69,
+			network_id: 1,
+			genesis_hash: H256::repeat_byte(1),
+			fork_id: ForkId {
+				hash: H32::from(hex!("fc64ec04")),
+				next: 0,
+			},
+			earliest_block: 0,
+			latest_block: 100,
+			latest_block_hash: H256::repeat_byte(2),
+		}));
+		let update = BlockRangeUpdate {
+			earliest_block: 10,
+			latest_block: 100,
+			latest_block_hash: H256::repeat_byte(2),
+		};
+		round_trip(EthMessage::BlockRangeUpdate(update));
+		assert!(
+			EthMessage::decode(ETH_68, MessageId::BlockRangeUpdate, &rlp::encode(&update)).is_err()
+		);
+	}
+}
@@ -1,11 +1,20 @@
+use alloc::vec::Vec;
+use core::ops::Deref;
+
 use ethereum_types::{Bloom, H160, H256, H64, U256};
+use rlp::{Decodable, DecoderError, Rlp, RlpStream};
 use sha3::{Digest, Keccak256};
 
 use crate::Bytes;
 
 /// Ethereum header definition.
+///
+/// Fields introduced after the Frontier header are optional, and encoded only
+/// when set. They must be set in order: a field set after an unset one would
+/// be encoded with the unset one as its zero value, as done by geth, and would
+/// not decode back to the same header. Encoding such a header panics in debug
+/// builds.
 #[derive(Clone, Debug, PartialEq, Eq)]
-#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
 #[cfg_attr(
 	feature = "with-scale",
 	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
@@ -27,8 +36,49 @@ pub struct Header {
 	pub extra_data: Bytes,
 	pub mix_hash: H256,
 	pub nonce: H64,
+	/// Base fee per gas, since London (EIP-1559).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub base_fee_per_gas: Option<U256>,
+	/// Root of the withdrawals trie, since Shanghai (EIP-4895).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub withdrawals_root: Option<H256>,
+	/// Blob gas used by the block, since Cancun (EIP-4844).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub blob_gas_used: Option<u64>,
+	/// Excess blob gas of the block, since Cancun (EIP-4844).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub excess_blob_gas: Option<u64>,
+	/// Root of the parent beacon block, since Cancun (EIP-4788).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub parent_beacon_block_root: Option<H256>,
+	/// Hash of the execution layer requests, since Prague (EIP-7685).
+	#[cfg_attr(
+		feature = "with-serde",
+		serde(default, skip_serializing_if = "Option::is_none")
+	)]
+	pub requests_hash: Option<H256>,
 }
 
+/// Number of fields of a Frontier header.
+pub(crate) const HEADER_FIELDS: usize = 15;
+/// Number of optional fields added to the header since London.
+pub(crate) const HEADER_OPTIONAL_FIELDS: usize = 6;
+
 impl Header {
 	#[must_use]
 	pub fn new(partial_header: PartialHeader, ommers_hash: H256, transactions_root: H256) -> Self {
@@ -48,16 +98,241 @@ impl Header {
 			extra_data: partial_header.extra_data,
 			mix_hash: partial_header.mix_hash,
 			nonce: partial_header.nonce,
+			base_fee_per_gas: partial_header.base_fee_per_gas,
+			withdrawals_root: None,
+			blob_gas_used: partial_header.blob_gas_used,
+			excess_blob_gas: partial_header.excess_blob_gas,
+			parent_beacon_block_root: partial_header.parent_beacon_block_root,
+			requests_hash: partial_header.requests_hash,
 		}
 	}
 
 	#[must_use]
 	pub fn hash(&self) -> H256 {
-		H256::from_slice(Keccak256::digest(&rlp::encode(self)).as_slice())
+		let mut out = Vec::with_capacity(self.encoded_len());
+		self.encode_to(&mut out);
+		H256::from_slice(Keccak256::digest(&out).as_slice())
+	}
+
+	/// Number of optional fields in the encoding, up to the last one set.
+	pub(crate) fn optional_fields(&self) -> usize {
+		let set = [
+			self.base_fee_per_gas.is_some(),
+			self.withdrawals_root.is_some(),
+			self.blob_gas_used.is_some(),
+			self.excess_blob_gas.is_some(),
+			self.parent_beacon_block_root.is_some(),
+			self.requests_hash.is_some(),
+		];
+		let count = set.iter().rposition(|set| *set).map_or(0, |last| last + 1);
+		debug_assert!(
+			set[..count].iter().all(|set| *set),
+			"unset optional header field before a set one"
+		);
+		count
+	}
+
+	/// Append the first `count` optional fields to an RLP list.
+	pub(crate) fn append_optional_fields(&self, s: &mut RlpStream, count: usize) {
+		if count > 0 {
+			s.append(&self.base_fee_per_gas.unwrap_or_default());
+		}
+		if count > 1 {
+			s.append(&self.withdrawals_root.unwrap_or_default());
+		}
+		if count > 2 {
+			s.append(&self.blob_gas_used.unwrap_or_default());
+		}
+		if count > 3 {
+			s.append(&self.excess_blob_gas.unwrap_or_default());
+		}
+		if count > 4 {
+			s.append(&self.parent_beacon_block_root.unwrap_or_default());
+		}
+		if count > 5 {
+			s.append(&self.requests_hash.unwrap_or_default());
+		}
+	}
+}
+
+impl rlp::Encodable for Header {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		let optional = self.optional_fields();
+		s.begin_list(HEADER_FIELDS + optional);
+		s.append(&self.parent_hash);
+		s.append(&self.ommers_hash);
+		s.append(&self.beneficiary);
+		s.append(&self.state_root);
+		s.append(&self.transactions_root);
+		s.append(&self.receipts_root);
+		s.append(&self.logs_bloom);
+		s.append(&self.difficulty);
+		s.append(&self.number);
+		s.append(&self.gas_limit);
+		s.append(&self.gas_used);
+		s.append(&self.timestamp);
+		s.append(&self.extra_data);
+		s.append(&self.mix_hash);
+		s.append(&self.nonce);
+		self.append_optional_fields(s, optional);
+	}
+}
+
+/// Decode the item at `index` of a list of `count` items, if present.
+fn optional_at<T: Decodable>(
+	rlp: &Rlp,
+	count: usize,
+	index: usize,
+) -> Result<Option<T>, DecoderError> {
+	if index < count {
+		rlp.val_at(index).map(Some)
+	} else {
+		Ok(None)
+	}
+}
+
+impl rlp::Decodable for Header {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		let count = rlp.item_count()?;
+		if !(HEADER_FIELDS..=HEADER_FIELDS + HEADER_OPTIONAL_FIELDS).contains(&count) {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			parent_hash: rlp.val_at(0)?,
+			ommers_hash: rlp.val_at(1)?,
+			beneficiary: rlp.val_at(2)?,
+			state_root: rlp.val_at(3)?,
+			transactions_root: rlp.val_at(4)?,
+			receipts_root: rlp.val_at(5)?,
+			logs_bloom: rlp.val_at(6)?,
+			difficulty: rlp.val_at(7)?,
+			number: rlp.val_at(8)?,
+			gas_limit: rlp.val_at(9)?,
+			gas_used: rlp.val_at(10)?,
+			timestamp: rlp.val_at(11)?,
+			extra_data: rlp.val_at(12)?,
+			mix_hash: rlp.val_at(13)?,
+			nonce: rlp.val_at(14)?,
+			base_fee_per_gas: optional_at(rlp, count, 15)?,
+			withdrawals_root: optional_at(rlp, count, 16)?,
+			blob_gas_used: optional_at(rlp, count, 17)?,
+			excess_blob_gas: optional_at(rlp, count, 18)?,
+			parent_beacon_block_root: optional_at(rlp, count, 19)?,
+			requests_hash: optional_at(rlp, count, 20)?,
+		})
 	}
 }
 
-/// Partial header definition without ommers hash and transactions root.
+/// Header together with its precomputed hash.
+///
+/// Deserializing and SCALE decoding check the hash against the header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize))]
+pub struct SealedHeader {
+	header: Header,
+	hash: H256,
+}
+
+/// Error returned when a given hash does not match the hash of a header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SealMismatch {
+	pub expected: H256,
+	pub computed: H256,
+}
+
+impl SealedHeader {
+	/// Seal the header by computing its hash.
+	#[must_use]
+	pub fn seal(header: Header) -> Self {
+		let hash = header.hash();
+		Self { header, hash }
+	}
+
+	/// Seal the header with the given hash, checking that it is the header hash.
+	pub fn new(header: Header, hash: H256) -> Result<Self, SealMismatch> {
+		let computed = header.hash();
+		if computed != hash {
+			return Err(SealMismatch {
+				expected: hash,
+				computed,
+			});
+		}
+
+		Ok(Self { header, hash })
+	}
+
+	/// Seal the header with the given hash, without checking it.
+	#[must_use]
+	pub fn new_unchecked(header: Header, hash: H256) -> Self {
+		Self { header, hash }
+	}
+
+	#[must_use]
+	pub fn header(&self) -> &Header {
+		&self.header
+	}
+
+	#[must_use]
+	pub fn hash(&self) -> H256 {
+		self.hash
+	}
+
+	#[must_use]
+	pub fn into_inner(self) -> Header {
+		self.header
+	}
+}
+
+#[cfg(feature = "with-scale")]
+impl scale_codec::Decode for SealedHeader {
+	fn decode<I: scale_codec::Input>(value: &mut I) -> Result<Self, scale_codec::Error> {
+		let (header, hash) = scale_codec::Decode::decode(value)?;
+		Self::new(header, hash).map_err(|_| scale_codec::Error::from("Header hash mismatch"))
+	}
+}
+
+#[cfg(feature = "with-serde")]
+impl<'de> serde::Deserialize<'de> for SealedHeader {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		#[derive(serde::Deserialize)]
+		struct Unchecked {
+			header: Header,
+			hash: H256,
+		}
+
+		let unchecked = Unchecked::deserialize(deserializer)?;
+		Self::new(unchecked.header, unchecked.hash)
+			.map_err(|_| serde::de::Error::custom("header hash mismatch"))
+	}
+}
+
+impl Deref for SealedHeader {
+	type Target = Header;
+
+	fn deref(&self) -> &Header {
+		&self.header
+	}
+}
+
+impl From<Header> for SealedHeader {
+	fn from(header: Header) -> Self {
+		Self::seal(header)
+	}
+}
+
+impl From<SealedHeader> for Header {
+	fn from(sealed: SealedHeader) -> Self {
+		sealed.header
+	}
+}
+
+/// Partial header definition without ommers hash, transactions root and
+/// withdrawals root.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PartialHeader {
 	pub parent_hash: H256,
@@ -73,6 +348,11 @@ pub struct PartialHeader {
 	pub extra_data: Bytes,
 	pub mix_hash: H256,
 	pub nonce: H64,
+	pub base_fee_per_gas: Option<U256>,
+	pub blob_gas_used: Option<u64>,
+	pub excess_blob_gas: Option<u64>,
+	pub parent_beacon_block_root: Option<H256>,
+	pub requests_hash: Option<H256>,
 }
 
 impl From<Header> for PartialHeader {
@@ -91,6 +371,95 @@ impl From<Header> for PartialHeader {
 			extra_data: header.extra_data,
 			mix_hash: header.mix_hash,
 			nonce: header.nonce,
+			base_fee_per_gas: header.base_fee_per_gas,
+			blob_gas_used: header.blob_gas_used,
+			excess_blob_gas: header.excess_blob_gas,
+			parent_beacon_block_root: header.parent_beacon_block_root,
+			requests_hash: header.requests_hash,
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::mainnet_block_one;
+	use hex_literal::hex;
+
+	#[test]
+	fn seal_header() {
+		let header = mainnet_block_one();
+		let hash = H256::from(hex!(
+			"88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6"
+		));
+		assert_eq!(header.hash(), hash);
+		assert_eq!(SealedHeader::seal(header.clone()).hash(), hash);
+		assert_eq!(
+			SealedHeader::new(header.clone(), H256::zero()),
+			Err(SealMismatch {
+				expected: H256::zero(),
+				computed: hash,
+			})
+		);
+	}
+
+	#[test]
+	fn optional_fields() {
+		let mut header = mainnet_block_one();
+		header.base_fee_per_gas = Some(7.into());
+		header.withdrawals_root = Some(H256::repeat_byte(1));
+		let bytes = rlp::encode(&header);
+		assert_eq!(Rlp::new(&bytes).item_count(), Ok(HEADER_FIELDS + 2));
+		assert_eq!(rlp::decode::<Header>(&bytes), Ok(header.clone()));
+
+		for count in [
+			HEADER_FIELDS - 1,
+			HEADER_FIELDS + HEADER_OPTIONAL_FIELDS + 1,
+		] {
+			let mut s = RlpStream::new_list(count);
+			for _ in 0..count {
+				s.append_empty_data();
+			}
+			assert_eq!(
+				rlp::decode::<Header>(&s.out()),
+				Err(DecoderError::RlpIncorrectListLen)
+			);
+		}
+	}
+
+	#[cfg(debug_assertions)]
+	#[test]
+	#[should_panic(expected = "unset optional header field before a set one")]
+	fn optional_field_gap() {
+		let mut header = mainnet_block_one();
+		header.parent_beacon_block_root = Some(H256::repeat_byte(2));
+		let _ = rlp::encode(&header);
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn deserialize_checks_hash() {
+		let sealed = SealedHeader::seal(mainnet_block_one());
+		let mut json = serde_json::to_value(&sealed).unwrap();
+		assert_eq!(
+			serde_json::from_value::<SealedHeader>(json.clone()).unwrap(),
+			sealed
+		);
+
+		json["hash"] = serde_json::to_value(H256::zero()).unwrap();
+		assert!(serde_json::from_value::<SealedHeader>(json).is_err());
+	}
+
+	#[cfg(feature = "with-scale")]
+	#[test]
+	fn scale_decode_checks_hash() {
+		use scale_codec::{Decode, Encode};
+
+		let sealed = SealedHeader::seal(mainnet_block_one());
+		let bytes = sealed.encode();
+		assert_eq!(SealedHeader::decode(&mut &bytes[..]), Ok(sealed.clone()));
+
+		let bytes = SealedHeader::new_unchecked(sealed.into_inner(), H256::zero()).encode();
+		assert!(SealedHeader::decode(&mut &bytes[..]).is_err());
+	}
+}
@@ -0,0 +1,90 @@
+//! Serde helpers for the `0x`-prefixed hex encodings of the JSON APIs.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+fn encode(bytes: &[u8]) -> String {
+	let mut out = String::with_capacity(2 + bytes.len() * 2);
+	out.push_str("0x");
+	for byte in bytes {
+		write!(out, "{:02x}", byte).expect("writing to a string cannot fail; qed");
+	}
+	out
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, &'static str> {
+	let value = value.strip_prefix("0x").ok_or("missing 0x prefix")?;
+	if value.len() % 2 != 0 {
+		return Err("odd number of hex digits");
+	}
+
+	value
+		.as_bytes()
+		.chunks_exact(2)
+		.map(|pair| {
+			let pair = core::str::from_utf8(pair).map_err(|_| "invalid hex digit")?;
+			u8::from_str_radix(pair, 16).map_err(|_| "invalid hex digit")
+		})
+		.collect()
+}
+
+/// Unformatted binary data, encoded as `0x`-prefixed hex.
+pub mod bytes {
+	use super::*;
+
+	pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&encode(value))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		decode(&value).map_err(D::Error::custom)
+	}
+}
+
+/// List of unformatted binary data.
+pub mod bytes_list {
+	use super::*;
+	use serde::ser::SerializeSeq;
+
+	pub fn serialize<S: Serializer>(value: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+		let mut seq = serializer.serialize_seq(Some(value.len()))?;
+		for item in value {
+			seq.serialize_element(&encode(item))?;
+		}
+		seq.end()
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Vec<Vec<u8>>, D::Error> {
+		Vec::<String>::deserialize(deserializer)?
+			.iter()
+			.map(|item| decode(item).map_err(D::Error::custom))
+			.collect()
+	}
+}
+
+/// Integer quantity, encoded as minimal `0x`-prefixed hex.
+pub mod quantity {
+	use super::*;
+
+	pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut out = String::new();
+		write!(out, "{:#x}", value).expect("writing to a string cannot fail; qed");
+		serializer.serialize_str(&out)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		let digits = value
+			.strip_prefix("0x")
+			.ok_or_else(|| D::Error::custom("missing 0x prefix"))?;
+		if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+			return Err(D::Error::custom("invalid quantity"));
+		}
+		u64::from_str_radix(digits, 16).map_err(D::Error::custom)
+	}
+}
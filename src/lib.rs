@@ -4,20 +4,61 @@ extern crate alloc;
 
 mod account;
 mod block;
+#[cfg(feature = "std")]
+pub mod chain;
+pub mod consensus;
+mod decode;
+mod encode;
+pub mod engine;
 mod enveloped;
+#[cfg(feature = "era1")]
+pub mod era1;
+pub mod eth;
 mod header;
+#[cfg(feature = "with-serde")]
+mod hex;
+mod limits;
 mod log;
+#[cfg(feature = "optimism")]
+pub mod optimism;
 mod receipt;
+#[cfg(feature = "with-serde")]
+pub mod rpc;
+pub mod snap;
+mod strict;
+#[cfg(feature = "with-serde")]
+mod tagged;
+#[cfg(test)]
+mod test_utils;
 mod transaction;
+pub mod trie;
 pub mod util;
+mod view;
+mod withdrawal;
 
 // Alias for `Vec<u8>`. This type alias is necessary for rlp-derive to work correctly.
 type Bytes = alloc::vec::Vec<u8>;
 
-pub use crate::account::Account;
+#[doc(hidden)]
+pub mod __private {
+	pub use crate::encode::{EncodePayloadRlp, EncodePayloadTo, Payload};
+	pub use bytes;
+	pub use ethereum_types;
+	pub use rlp;
+	pub use sha3;
+}
+
+pub use crate::account::{Account, EMPTY_CODE_HASH};
 pub use crate::block::*;
+pub use crate::decode::{decode_block, decode_transaction, DecodeError};
 pub use crate::enveloped::*;
-pub use crate::header::{Header, PartialHeader};
-pub use crate::log::Log;
+pub use crate::header::{Header, PartialHeader, SealMismatch, SealedHeader};
+pub use crate::limits::DecodeLimits;
+pub use crate::log::{logs_bloom, BloomExt, Log};
 pub use crate::receipt::*;
+pub use crate::strict::{decode_enveloped_strict, decode_strict};
+#[cfg(feature = "with-serde")]
+pub use crate::tagged::Tagged;
 pub use crate::transaction::*;
+pub use crate::view::{BlockRef, TransactionRef, TransactionRefs};
+pub use crate::withdrawal::{withdrawals_root, Withdrawal};
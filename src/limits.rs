@@ -0,0 +1,252 @@
+use rlp::{DecoderError, Rlp};
+
+use crate::{block::Block, enveloped::EnvelopedDecodable, transaction::TransactionAny};
+
+/// Limits on the size of decoded blocks and transactions.
+///
+/// Limits are checked by walking the RLP structure of the input, without
+/// allocating, so that decoding fails before any oversized item is built.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DecodeLimits {
+	/// Maximum length of the input.
+	pub max_bytes: usize,
+	/// Maximum number of transactions in a block or list of transactions.
+	pub max_transactions: usize,
+	/// Maximum number of entries in the access list of a transaction.
+	pub max_access_list_items: usize,
+	/// Maximum number of storage keys in an access list entry.
+	pub max_storage_keys: usize,
+	/// Maximum length of the input data of a transaction.
+	pub max_input_len: usize,
+	/// Maximum number of ommers of a block.
+	pub max_ommers: usize,
+}
+
+impl DecodeLimits {
+	/// No limits, for trusted input.
+	pub const UNLIMITED: Self = Self {
+		max_bytes: usize::MAX,
+		max_transactions: usize::MAX,
+		max_access_list_items: usize::MAX,
+		max_storage_keys: usize::MAX,
+		max_input_len: usize::MAX,
+		max_ommers: usize::MAX,
+	};
+
+	/// Limits for messages received from devp2p peers.
+	///
+	/// The size limit is the devp2p maximum message size. The other limits are
+	/// well above what blocks can hold at current gas limits.
+	pub const PEER: Self = Self {
+		max_bytes: 10 * 1024 * 1024,
+		max_transactions: 16_384,
+		max_access_list_items: 32_768,
+		max_storage_keys: 32_768,
+		max_input_len: crate::block::MAX_RLP_BLOCK_SIZE,
+		max_ommers: 2,
+	};
+
+	/// Decode an RLP encoded block within the limits.
+	pub fn decode_block(&self, bytes: &[u8]) -> Result<Block<TransactionAny>, DecoderError> {
+		self.check_bytes(bytes)?;
+		let rlp = Rlp::new(bytes);
+		self.check_body(&rlp.at(1)?, &rlp.at(2)?)?;
+		rlp.as_val()
+	}
+
+	/// Decode an enveloped transaction within the limits.
+	pub fn decode_transaction(&self, bytes: &[u8]) -> Result<TransactionAny, DecoderError> {
+		self.check_bytes(bytes)?;
+		self.check_transaction(bytes)?;
+		TransactionAny::decode(bytes)
+			.map_err(|_| DecoderError::Custom("decode enveloped item failed"))
+	}
+
+	pub(crate) fn check_bytes(&self, bytes: &[u8]) -> Result<(), DecoderError> {
+		if bytes.len() > self.max_bytes {
+			return Err(DecoderError::Custom("input size limit exceeded"));
+		}
+		Ok(())
+	}
+
+	/// Check the transactions and ommers of a block.
+	pub(crate) fn check_body(&self, transactions: &Rlp, ommers: &Rlp) -> Result<(), DecoderError> {
+		self.check_transactions(transactions)?;
+		if ommers.item_count()? > self.max_ommers {
+			return Err(DecoderError::Custom("ommers limit exceeded"));
+		}
+		Ok(())
+	}
+
+	/// Check a list of transactions in their network form.
+	pub(crate) fn check_transactions(&self, transactions: &Rlp) -> Result<(), DecoderError> {
+		if transactions.item_count()? > self.max_transactions {
+			return Err(DecoderError::Custom("transactions limit exceeded"));
+		}
+		for transaction in transactions {
+			let bytes = if transaction.is_list() {
+				transaction.as_raw()
+			} else {
+				transaction.data()?
+			};
+			self.check_transaction(bytes)?;
+		}
+		Ok(())
+	}
+
+	/// Check an enveloped transaction. Unknown types are left to the decoder.
+	fn check_transaction(&self, bytes: &[u8]) -> Result<(), DecoderError> {
+		let (payload, input, access_list) = match bytes.first() {
+			Some(0xc0..) => (bytes, 5, None),
+			Some(0x01) => (&bytes[1..], 6, Some(7)),
+			Some(0x02) => (&bytes[1..], 7, Some(8)),
+			_ => return Ok(()),
+		};
+		let payload = Rlp::new(payload);
+
+		if payload.at(input)?.payload_info()?.value_len > self.max_input_len {
+			return Err(DecoderError::Custom("input data limit exceeded"));
+		}
+
+		if let Some(access_list) = access_list {
+			let access_list = payload.at(access_list)?;
+			if access_list.item_count()? > self.max_access_list_items {
+				return Err(DecoderError::Custom("access list limit exceeded"));
+			}
+			for item in &access_list {
+				if item.at(1)?.item_count()? > self.max_storage_keys {
+					return Err(DecoderError::Custom("storage keys limit exceeded"));
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Default for DecodeLimits {
+	fn default() -> Self {
+		Self::PEER
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::{
+		enveloped::EnvelopedEncodable,
+		transaction::{
+			AccessListItem, EIP2930Transaction, LegacyTransaction, TransactionAction,
+			TransactionSignature,
+		},
+	};
+	use ethereum_types::{H160, H256};
+
+	fn eip2930(input: usize, items: usize, keys: usize) -> TransactionAny {
+		TransactionAny::EIP2930(EIP2930Transaction {
+			chain_id: 1,
+			nonce: 0.into(),
+			gas_price: 1.into(),
+			gas_limit: 1_000_000.into(),
+			action: TransactionAction::Create,
+			value: 0.into(),
+			input: vec![0xff; input],
+			access_list: vec![
+				AccessListItem {
+					address: H160::repeat_byte(1),
+					storage_keys: vec![H256::zero(); keys],
+				};
+				items
+			],
+			odd_y_parity: false,
+			r: H256::repeat_byte(1),
+			s: H256::repeat_byte(2),
+		})
+	}
+
+	fn block(transactions: usize) -> Block<TransactionAny> {
+		let legacy = TransactionAny::Legacy(LegacyTransaction {
+			nonce: 0.into(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: 0.into(),
+			input: vec![0xff; 100],
+			signature: TransactionSignature::new(27, H256::repeat_byte(1), H256::repeat_byte(2))
+				.unwrap(),
+		});
+		Block::new(partial_header(), vec![legacy; transactions], Vec::new())
+	}
+
+	#[test]
+	fn transaction_limits() {
+		let limits = DecodeLimits {
+			max_input_len: 10,
+			max_access_list_items: 2,
+			max_storage_keys: 3,
+			..DecodeLimits::PEER
+		};
+		let decode = |tx: TransactionAny| limits.decode_transaction(&tx.encode());
+
+		assert!(decode(eip2930(10, 2, 3)).is_ok());
+		assert_eq!(
+			decode(eip2930(11, 2, 3)),
+			Err(DecoderError::Custom("input data limit exceeded"))
+		);
+		assert_eq!(
+			decode(eip2930(10, 3, 3)),
+			Err(DecoderError::Custom("access list limit exceeded"))
+		);
+		assert_eq!(
+			decode(eip2930(10, 2, 4)),
+			Err(DecoderError::Custom("storage keys limit exceeded"))
+		);
+	}
+
+	#[test]
+	fn block_limits() {
+		let bytes = rlp::encode(&block(3));
+		let limits = DecodeLimits {
+			max_transactions: 3,
+			max_input_len: 100,
+			..DecodeLimits::PEER
+		};
+		assert_eq!(limits.decode_block(&bytes), Ok(block(3)));
+		assert_eq!(DecodeLimits::UNLIMITED.decode_block(&bytes), Ok(block(3)));
+
+		let too_many = DecodeLimits {
+			max_transactions: 2,
+			..limits
+		};
+		assert_eq!(
+			too_many.decode_block(&bytes),
+			Err(DecoderError::Custom("transactions limit exceeded"))
+		);
+
+		let too_large = DecodeLimits {
+			max_bytes: bytes.len() - 1,
+			..limits
+		};
+		assert_eq!(
+			too_large.decode_block(&bytes),
+			Err(DecoderError::Custom("input size limit exceeded"))
+		);
+
+		let input_too_long = DecodeLimits {
+			max_input_len: 99,
+			..limits
+		};
+		assert_eq!(
+			input_too_long.decode_block(&bytes),
+			Err(DecoderError::Custom("input data limit exceeded"))
+		);
+
+		let mut with_ommers = block(0);
+		with_ommers.ommers = vec![with_ommers.header.clone(); 3];
+		assert_eq!(
+			DecodeLimits::PEER.decode_block(&rlp::encode(&with_ommers)),
+			Err(DecoderError::Custom("ommers limit exceeded"))
+		);
+	}
+}
@@ -1,6 +1,6 @@
 use alloc::vec::Vec;
 
-use ethereum_types::{H160, H256};
+use ethereum_types::{Bloom, BloomInput, H160, H256};
 
 use crate::Bytes;
 
@@ -16,3 +16,72 @@ pub struct Log {
 	pub topics: Vec<H256>,
 	pub data: Bytes,
 }
+
+impl Log {
+	/// Accrue the address and all topics of this log into the bloom.
+	pub fn accrue_bloom(&self, bloom: &mut Bloom) {
+		bloom.accrue(BloomInput::Raw(&self.address[..]));
+		for topic in &self.topics {
+			bloom.accrue(BloomInput::Raw(&topic[..]));
+		}
+	}
+}
+
+/// Compute the bloom filter of a list of logs.
+pub fn logs_bloom<'a, I>(logs: I) -> Bloom
+where
+	I: IntoIterator<Item = &'a Log>,
+{
+	let mut bloom = Bloom::zero();
+	for log in logs {
+		log.accrue_bloom(&mut bloom);
+	}
+	bloom
+}
+
+/// Log-related queries on a bloom filter.
+pub trait BloomExt {
+	/// Whether the bloom may contain a log emitted by the address.
+	fn contains_address(&self, address: &H160) -> bool;
+
+	/// Whether the bloom may contain a log with the topic.
+	fn contains_topic(&self, topic: &H256) -> bool;
+}
+
+impl BloomExt for Bloom {
+	fn contains_address(&self, address: &H160) -> bool {
+		self.contains_input(BloomInput::Raw(&address[..]))
+	}
+
+	fn contains_topic(&self, topic: &H256) -> bool {
+		self.contains_input(BloomInput::Raw(&topic[..]))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	#[test]
+	fn accrue_and_query_bloom() {
+		let log = Log {
+			address: hex!("22341ae42d6dd7384bc8584e50419ea3ac75b83f").into(),
+			topics: vec![
+				hex!("04491edcd115127caedbd478e2e7895ed80c7847e903431f94f9cfa579cad47f").into(),
+			],
+			data: vec![],
+		};
+
+		let bloom = logs_bloom(core::iter::once(&log));
+		assert!(bloom.contains_address(&log.address));
+		assert!(bloom.contains_topic(&log.topics[0]));
+		assert!(!bloom.contains_address(&H160::repeat_byte(0x11)));
+		assert!(!bloom.contains_topic(&H256::repeat_byte(0x11)));
+
+		let mut expected = Bloom::zero();
+		expected.accrue(BloomInput::Raw(&log.address[..]));
+		expected.accrue(BloomInput::Raw(&log.topics[0][..]));
+		assert_eq!(bloom, expected);
+	}
+}
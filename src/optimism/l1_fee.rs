@@ -0,0 +1,313 @@
+use alloc::vec;
+
+use ethereum_types::U256;
+
+use super::DEPOSIT_TX_TYPE;
+use crate::enveloped::EnvelopedEncodable;
+
+/// Divisor of the fee scalars.
+const SCALAR_DIVISOR: u64 = 1_000_000;
+/// Fjord estimated size intercept, scaled by 1e6.
+const FJORD_INTERCEPT: u64 = 42_585_600;
+/// Fjord FastLZ size coefficient, scaled by 1e6.
+const FJORD_FASTLZ_COEF: u64 = 836_500;
+/// Fjord minimum estimated transaction size, scaled by 1e6.
+const FJORD_MIN_SIZE: u64 = 100 * SCALAR_DIVISOR;
+/// Calldata gas of the signature padding added before Regolith.
+const BEDROCK_SIGNATURE_GAS: u64 = 68 * 16;
+
+/// Fork determining how the L1 data fee is computed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum L1FeeFork {
+	/// Calldata gas plus a fixed overhead, times a scalar, with 68 bytes of
+	/// signature padding added to the calldata.
+	Bedrock,
+	/// Bedrock rules without the signature padding.
+	Regolith,
+	/// Calldata gas priced with both the L1 base fee and the blob base fee.
+	Ecotone,
+	/// FastLZ compressed size estimate priced as in Ecotone.
+	Fjord,
+}
+
+/// L1 fee parameters, as set in the L1 block info of the L2 block.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct L1FeeParams {
+	/// Base fee of the L1 origin block.
+	pub l1_base_fee: U256,
+	/// Scalar applied to the L1 base fee, scaled by 1e6. This is the `scalar`
+	/// of Bedrock.
+	pub base_fee_scalar: U256,
+	/// Blob base fee of the L1 origin block. Unused before Ecotone.
+	pub blob_base_fee: U256,
+	/// Scalar applied to the blob base fee, scaled by 1e6. Unused before
+	/// Ecotone.
+	pub blob_base_fee_scalar: U256,
+	/// Fixed gas overhead per transaction. Only used before Ecotone.
+	pub overhead: U256,
+}
+
+/// Size figures of a signed enveloped transaction entering the L1 data fee.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RollupCostData {
+	/// Number of zero bytes.
+	pub zeroes: u64,
+	/// Number of non-zero bytes.
+	pub ones: u64,
+	/// FastLZ compressed length.
+	pub fastlz_size: u64,
+}
+
+impl RollupCostData {
+	/// Cost data of a signed transaction in its enveloped form.
+	#[must_use]
+	pub fn new(bytes: &[u8]) -> Self {
+		let zeroes = bytes.iter().filter(|b| **b == 0).count() as u64;
+		Self {
+			zeroes,
+			ones: bytes.len() as u64 - zeroes,
+			fastlz_size: u64::from(flz_compress_len(bytes)),
+		}
+	}
+
+	/// Cost data of a signed transaction.
+	#[must_use]
+	pub fn from_transaction<T: EnvelopedEncodable>(transaction: &T) -> Self {
+		Self::new(&transaction.encode())
+	}
+
+	/// Calldata gas of the transaction bytes.
+	#[must_use]
+	pub fn calldata_gas(&self) -> u64 {
+		self.zeroes * 4 + self.ones * 16
+	}
+
+	/// Fjord estimate of the size of the transaction once compressed in a
+	/// batch, scaled by 1e6.
+	#[must_use]
+	pub fn estimated_size_scaled(&self) -> u64 {
+		(self.fastlz_size * FJORD_FASTLZ_COEF)
+			.saturating_sub(FJORD_INTERCEPT)
+			.max(FJORD_MIN_SIZE)
+	}
+}
+
+impl L1FeeParams {
+	/// L1 data fee of a transaction, in wei.
+	#[must_use]
+	pub fn l1_fee(&self, fork: L1FeeFork, cost: &RollupCostData) -> U256 {
+		match fork {
+			L1FeeFork::Bedrock | L1FeeFork::Regolith => {
+				let padding = if fork == L1FeeFork::Bedrock {
+					BEDROCK_SIGNATURE_GAS
+				} else {
+					0
+				};
+				(U256::from(cost.calldata_gas() + padding) + self.overhead)
+					.saturating_mul(self.l1_base_fee)
+					.saturating_mul(self.base_fee_scalar)
+					/ SCALAR_DIVISOR
+			}
+			L1FeeFork::Ecotone => {
+				self.fee_per_byte_scaled()
+					.saturating_mul(cost.calldata_gas().into())
+					/ (16 * SCALAR_DIVISOR)
+			}
+			L1FeeFork::Fjord => {
+				self.fee_per_byte_scaled()
+					.saturating_mul(cost.estimated_size_scaled().into())
+					/ (SCALAR_DIVISOR * SCALAR_DIVISOR)
+			}
+		}
+	}
+
+	/// L1 data fee of a signed transaction, in wei. Deposits pay no L1 fee.
+	#[must_use]
+	pub fn estimate<T: EnvelopedEncodable>(&self, fork: L1FeeFork, transaction: &T) -> U256 {
+		if transaction.type_id() == Some(DEPOSIT_TX_TYPE) {
+			return U256::zero();
+		}
+
+		self.l1_fee(fork, &RollupCostData::from_transaction(transaction))
+	}
+
+	fn fee_per_byte_scaled(&self) -> U256 {
+		self.l1_base_fee
+			.saturating_mul(self.base_fee_scalar)
+			.saturating_mul(16.into())
+			.saturating_add(self.blob_base_fee.saturating_mul(self.blob_base_fee_scalar))
+	}
+}
+
+/// Length of the FastLZ (level 1) compression of `input`, as computed by the
+/// OP Stack for Fjord L1 fees.
+#[must_use]
+pub fn flz_compress_len(input: &[u8]) -> u32 {
+	let len = input.len() as u32;
+	let u24 = |i: u32| {
+		let i = i as usize;
+		u32::from(input[i]) | (u32::from(input[i + 1]) << 8) | (u32::from(input[i + 2]) << 16)
+	};
+	let hash = |v: u32| (2_654_435_769u32.wrapping_mul(v) >> 19) & 0x1fff;
+	let literals = |n: u32| {
+		0x21 * (n / 0x20)
+			+ match n % 0x20 {
+				0 => 0,
+				r => r + 1,
+			}
+	};
+	let matched = |l: u32| {
+		let l = l - 1;
+		3 * (l / 262) + if l % 262 >= 6 { 3 } else { 2 }
+	};
+	// Matches the reference: a mismatch ends the loop one byte later.
+	let compare = |p: u32, q: u32, end: u32| {
+		let mut end = end - q;
+		let mut l = 0;
+		while l < end {
+			if input[(p + l) as usize] != input[(q + l) as usize] {
+				end = 0;
+			}
+			l += 1;
+		}
+		l
+	};
+
+	let mut table = vec![0u32; 8192];
+	let mut n = 0;
+	let mut anchor = 0;
+	let limit = len.saturating_sub(13);
+	let mut ip = 2;
+	while ip < limit {
+		let mut reference;
+		loop {
+			let seq = u24(ip);
+			let h = hash(seq) as usize;
+			reference = table[h];
+			table[h] = ip;
+			let distance = ip - reference;
+			if ip >= limit {
+				break;
+			}
+			ip += 1;
+			if distance <= 0x1fff && seq == u24(reference) {
+				break;
+			}
+		}
+		if ip >= limit {
+			break;
+		}
+		ip -= 1;
+		if ip > anchor {
+			n += literals(ip - anchor);
+		}
+		let l = compare(reference + 3, ip + 3, limit + 9);
+		n += matched(l);
+		ip += l;
+		for _ in 0..2 {
+			table[hash(u24(ip)) as usize] = ip;
+			ip += 1;
+		}
+		anchor = ip;
+	}
+
+	n + literals(len - anchor)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		optimism::{DepositTransaction, OpTransaction},
+		transaction::TransactionAction,
+	};
+	use ethereum_types::{H160, H256};
+
+	fn params() -> L1FeeParams {
+		L1FeeParams {
+			l1_base_fee: 1_000_000_000.into(),
+			base_fee_scalar: 2.into(),
+			blob_base_fee: 10_000_000.into(),
+			blob_base_fee_scalar: 3.into(),
+			overhead: 50.into(),
+		}
+	}
+
+	#[test]
+	fn l1_fees() {
+		let cost = RollupCostData {
+			zeroes: 5,
+			ones: 30,
+			fastlz_size: 100,
+		};
+		assert_eq!(cost.calldata_gas(), 500);
+
+		// (500 + 68 * 16 + 50) * 1e9 * 2 / 1e6
+		assert_eq!(params().l1_fee(L1FeeFork::Bedrock, &cost), 3_276_000.into());
+		// (500 + 50) * 1e9 * 2 / 1e6
+		assert_eq!(
+			params().l1_fee(L1FeeFork::Regolith, &cost),
+			1_100_000.into()
+		);
+		// 500 * (16 * 1e9 * 2 + 1e7 * 3) / 16e6
+		assert_eq!(params().l1_fee(L1FeeFork::Ecotone, &cost), 1_000_937.into());
+		// Below the minimum size: 100 * (16 * 1e9 * 2 + 1e7 * 3) / 1e6
+		assert_eq!(params().l1_fee(L1FeeFork::Fjord, &cost), 3_203_000.into());
+
+		let large = RollupCostData {
+			fastlz_size: 1_000,
+			..cost
+		};
+		assert_eq!(large.estimated_size_scaled(), 793_914_400);
+		assert_eq!(
+			params().l1_fee(L1FeeFork::Fjord, &large),
+			(U256::from(793_914_400u64) * 32_030_000_000u64) / 1_000_000_000_000u64
+		);
+	}
+
+	#[test]
+	fn deposits_are_free() {
+		let deposit = OpTransaction::from(DepositTransaction {
+			source_hash: H256::repeat_byte(1),
+			from: H160::repeat_byte(2),
+			action: TransactionAction::Create,
+			mint: 0.into(),
+			value: 0.into(),
+			gas_limit: 1_000_000.into(),
+			is_system_tx: false,
+			input: vec![0xff; 100],
+		});
+		for fork in [
+			L1FeeFork::Bedrock,
+			L1FeeFork::Regolith,
+			L1FeeFork::Ecotone,
+			L1FeeFork::Fjord,
+		] {
+			assert_eq!(params().estimate(fork, &deposit), U256::zero());
+			assert!(!params()
+				.l1_fee(fork, &RollupCostData::from_transaction(&deposit))
+				.is_zero());
+		}
+	}
+
+	#[test]
+	fn fastlz() {
+		assert_eq!(flz_compress_len(&[]), 0);
+		assert_eq!(flz_compress_len(&[1]), 2);
+		// Two literals, a match of 23 bytes and five trailing literals.
+		assert_eq!(flz_compress_len(&[1; 32]), 12);
+
+		// Incompressible data is stored as runs of up to 32 literals.
+		let random: alloc::vec::Vec<u8> = (0..256u32)
+			.map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+			.collect();
+		assert!(flz_compress_len(&random) >= 256);
+
+		// Repetitive data compresses well.
+		let zeroes = [0; 1024];
+		assert!(flz_compress_len(&zeroes) < 32);
+
+		let cost = RollupCostData::new(&zeroes);
+		assert_eq!((cost.zeroes, cost.ones), (1024, 0));
+	}
+}
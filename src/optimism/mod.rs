@@ -0,0 +1,441 @@
+//! OP Stack deposit transactions, receipts and L1 data fees.
+
+mod l1_fee;
+
+use alloc::vec::Vec;
+
+use bytes::BytesMut;
+use ethereum_types::{Bloom, H160, H256, U256};
+use rlp::{Decodable, DecoderError, Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+pub use self::l1_fee::{flz_compress_len, L1FeeFork, L1FeeParams, RollupCostData};
+use crate::{
+	enveloped::{EnvelopedDecodable, EnvelopedDecoderError, EnvelopedEncodable},
+	log::{logs_bloom, Log},
+	receipt::{EIP1559ReceiptData, EIP2930ReceiptData, EIP658ReceiptData, Receipt},
+	transaction::{EIP1559Transaction, EIP2930Transaction, LegacyTransaction, TransactionAction},
+	Bytes,
+};
+
+/// EIP-2718 type id of deposit transactions and receipts.
+pub const DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+/// Deposit transaction, derived from L1 and included by the sequencer.
+///
+/// Deposits are not signed: the sender is given by `from`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepositTransaction {
+	/// Hash uniquely identifying the origin of the deposit.
+	pub source_hash: H256,
+	pub from: H160,
+	pub action: TransactionAction,
+	/// ETH minted on L2 to `from`.
+	pub mint: U256,
+	pub value: U256,
+	pub gas_limit: U256,
+	/// Whether the transaction is exempt from the L2 gas limit.
+	pub is_system_tx: bool,
+	pub input: Bytes,
+}
+
+impl DepositTransaction {
+	pub fn hash(&self) -> H256 {
+		let encoded = rlp::encode(self);
+		let mut out = alloc::vec![0; 1 + encoded.len()];
+		out[0] = DEPOSIT_TX_TYPE;
+		out[1..].copy_from_slice(&encoded);
+		H256::from_slice(Keccak256::digest(&out).as_slice())
+	}
+}
+
+crate::enveloped_enum! {
+	/// Transaction of an OP Stack chain.
+	#[derive(Clone, Debug, PartialEq, Eq)]
+	#[cfg_attr(
+		feature = "with-scale",
+		derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+	)]
+	#[cfg_attr(
+		feature = "with-serde",
+		derive(serde::Serialize, serde::Deserialize),
+		serde(untagged)
+	)]
+	pub enum OpTransaction {
+		Legacy(LegacyTransaction) = legacy,
+		EIP2930(EIP2930Transaction) = 0x01,
+		EIP1559(EIP1559Transaction) = 0x02,
+		Deposit(DepositTransaction) = 0x7e,
+	}
+}
+
+/// Receipt of a deposit transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepositReceiptData {
+	pub status_code: u8,
+	pub used_gas: U256,
+	pub logs_bloom: Bloom,
+	pub logs: Vec<Log>,
+	/// Nonce of the sender before the deposit, set since Regolith.
+	///
+	/// The nonce is only encoded together with the receipt version, as in the
+	/// receipts root computed by op-geth, so a Regolith receipt decodes without
+	/// it. Encodings carrying a nonce but no version are still decoded.
+	pub deposit_nonce: Option<u64>,
+	/// Receipt version, set to `1` since Canyon. Requires the deposit nonce to
+	/// be set.
+	pub deposit_receipt_version: Option<u64>,
+}
+
+impl DepositReceiptData {
+	/// Create a new receipt, computing the logs bloom from the logs.
+	///
+	/// # Panics
+	///
+	/// If a receipt version is given without a deposit nonce.
+	#[must_use]
+	pub fn new(
+		status_code: u8,
+		used_gas: U256,
+		logs: Vec<Log>,
+		deposit_nonce: Option<u64>,
+		deposit_receipt_version: Option<u64>,
+	) -> Self {
+		assert!(deposit_nonce.is_some() || deposit_receipt_version.is_none());
+		Self {
+			status_code,
+			used_gas,
+			logs_bloom: logs_bloom(&logs),
+			logs,
+			deposit_nonce,
+			deposit_receipt_version,
+		}
+	}
+}
+
+impl rlp::Encodable for DepositReceiptData {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		assert!(
+			self.deposit_nonce.is_some() || self.deposit_receipt_version.is_none(),
+			"deposit receipt version without a deposit nonce"
+		);
+		let nonce_and_version = self.deposit_nonce.zip(self.deposit_receipt_version);
+		s.begin_list(if nonce_and_version.is_some() { 6 } else { 4 });
+		s.append(&self.status_code);
+		s.append(&self.used_gas);
+		s.append(&self.logs_bloom);
+		s.append_list(&self.logs);
+		if let Some((nonce, version)) = nonce_and_version {
+			s.append(&nonce);
+			s.append(&version);
+		}
+	}
+}
+
+impl rlp::Decodable for DepositReceiptData {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		let count = rlp.item_count()?;
+		if !(4..=6).contains(&count) {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			status_code: rlp.val_at(0)?,
+			used_gas: rlp.val_at(1)?,
+			logs_bloom: rlp.val_at(2)?,
+			logs: rlp.list_at(3)?,
+			deposit_nonce: if count > 4 {
+				Some(rlp.val_at(4)?)
+			} else {
+				None
+			},
+			deposit_receipt_version: if count > 5 {
+				Some(rlp.val_at(5)?)
+			} else {
+				None
+			},
+		})
+	}
+}
+
+impl Receipt for DepositReceiptData {
+	fn used_gas(&self) -> U256 {
+		self.used_gas
+	}
+
+	fn logs(&self) -> &[Log] {
+		&self.logs
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		&self.logs_bloom
+	}
+}
+
+/// Receipt of an OP Stack chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(untagged)
+)]
+pub enum OpReceipt {
+	/// Legacy receipt type
+	Legacy(EIP658ReceiptData),
+	/// EIP-2930 receipt type
+	EIP2930(EIP2930ReceiptData),
+	/// EIP-1559 receipt type
+	EIP1559(EIP1559ReceiptData),
+	/// Deposit receipt type
+	Deposit(DepositReceiptData),
+}
+
+impl Receipt for OpReceipt {
+	fn used_gas(&self) -> U256 {
+		match self {
+			Self::Legacy(r) | Self::EIP2930(r) | Self::EIP1559(r) => r.used_gas(),
+			Self::Deposit(r) => r.used_gas(),
+		}
+	}
+
+	fn logs(&self) -> &[Log] {
+		match self {
+			Self::Legacy(r) | Self::EIP2930(r) | Self::EIP1559(r) => r.logs(),
+			Self::Deposit(r) => r.logs(),
+		}
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		match self {
+			Self::Legacy(r) | Self::EIP2930(r) | Self::EIP1559(r) => r.logs_bloom(),
+			Self::Deposit(r) => r.logs_bloom(),
+		}
+	}
+}
+
+impl EnvelopedEncodable for OpReceipt {
+	fn type_id(&self) -> Option<u8> {
+		match self {
+			Self::Legacy(_) => None,
+			Self::EIP2930(_) => Some(1),
+			Self::EIP1559(_) => Some(2),
+			Self::Deposit(_) => Some(DEPOSIT_TX_TYPE),
+		}
+	}
+
+	fn encode_payload(&self) -> BytesMut {
+		match self {
+			Self::Legacy(r) | Self::EIP2930(r) | Self::EIP1559(r) => rlp::encode(r),
+			Self::Deposit(r) => rlp::encode(r),
+		}
+	}
+}
+
+impl EnvelopedDecodable for OpReceipt {
+	type PayloadDecoderError = DecoderError;
+
+	fn decode(bytes: &[u8]) -> Result<Self, EnvelopedDecoderError<Self::PayloadDecoderError>> {
+		if bytes.is_empty() {
+			return Err(EnvelopedDecoderError::UnknownTypeId);
+		}
+
+		let first = bytes[0];
+
+		let rlp = Rlp::new(bytes);
+		if rlp.is_list() {
+			return Ok(Self::Legacy(Decodable::decode(&rlp)?));
+		}
+
+		let s = &bytes[1..];
+
+		match first {
+			0x01 => Ok(Self::EIP2930(rlp::decode(s)?)),
+			0x02 => Ok(Self::EIP1559(rlp::decode(s)?)),
+			DEPOSIT_TX_TYPE => Ok(Self::Deposit(rlp::decode(s)?)),
+			_ => Err(DecoderError::Custom("invalid receipt type").into()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{block::transactions_root, receipt::receipts_root};
+	use hex_literal::hex;
+
+	fn deposit() -> DepositTransaction {
+		DepositTransaction {
+			source_hash: H256::repeat_byte(1),
+			from: H160::repeat_byte(2),
+			action: TransactionAction::Call(H160::repeat_byte(3)),
+			mint: 0.into(),
+			value: 1_000.into(),
+			gas_limit: 1_000_000.into(),
+			is_system_tx: false,
+			input: hex!("440a5e20").to_vec(),
+		}
+	}
+
+	#[test]
+	fn deposit_transaction() {
+		let tx = OpTransaction::from(deposit());
+		let encoded = tx.encode();
+		assert_eq!(encoded[0], DEPOSIT_TX_TYPE);
+		assert_eq!(&encoded[1..], &rlp::encode(&deposit())[..]);
+		assert_eq!(tx.hash(), deposit().hash());
+		assert_eq!(
+			tx.hash(),
+			H256::from_slice(Keccak256::digest(&encoded).as_slice())
+		);
+		assert_eq!(
+			<OpTransaction as EnvelopedDecodable>::decode(&encoded),
+			Ok(tx.clone())
+		);
+
+		let block =
+			crate::block::Block::new(crate::test_utils::partial_header(), vec![tx], Vec::new());
+		assert_eq!(
+			block.header.transactions_root,
+			transactions_root(&block.transactions)
+		);
+		assert_eq!(rlp::decode(&rlp::encode(&block)), Ok(block));
+	}
+
+	#[test]
+	fn deposit_receipts() {
+		let log = Log {
+			address: H160::repeat_byte(4),
+			topics: vec![H256::repeat_byte(5)],
+			data: Vec::new(),
+		};
+		let receipts = [
+			DepositReceiptData::new(1, 21_000.into(), vec![log.clone()], None, None),
+			DepositReceiptData::new(1, 21_000.into(), vec![log.clone()], Some(7), None),
+			DepositReceiptData::new(1, 21_000.into(), vec![log], Some(7), Some(1)),
+		];
+
+		for (receipt, count) in receipts.iter().zip([4, 4, 6]) {
+			let encoded = rlp::encode(receipt);
+			assert_eq!(Rlp::new(&encoded).item_count(), Ok(count));
+
+			// The Regolith nonce is not part of the encoding.
+			let expected = if receipt.deposit_receipt_version.is_some() {
+				receipt.clone()
+			} else {
+				DepositReceiptData {
+					deposit_nonce: None,
+					..receipt.clone()
+				}
+			};
+			assert_eq!(rlp::decode(&encoded), Ok(expected.clone()));
+
+			let receipt = OpReceipt::Deposit(receipt.clone());
+			let encoded = EnvelopedEncodable::encode(&receipt);
+			assert_eq!(encoded[0], DEPOSIT_TX_TYPE);
+			assert_eq!(
+				<OpReceipt as EnvelopedDecodable>::decode(&encoded),
+				Ok(OpReceipt::Deposit(expected))
+			);
+		}
+
+		// A stored Regolith receipt carries its nonce as a fifth item.
+		let mut s = RlpStream::new_list(5);
+		s.append(&1u8);
+		s.append(&U256::from(21_000));
+		s.append(&receipts[1].logs_bloom);
+		s.append_list(&receipts[1].logs);
+		s.append(&7u64);
+		assert_eq!(rlp::decode(&s.out()), Ok(receipts[1].clone()));
+
+		// Only Canyon receipts include the deposit nonce and receipt version in
+		// the receipts root.
+		let with_nonce = [OpReceipt::Deposit(receipts[1].clone())];
+		let with_version = [OpReceipt::Deposit(receipts[2].clone())];
+		assert_ne!(receipts_root(&with_nonce), receipts_root(&with_version));
+	}
+
+	#[test]
+	fn regolith_receipts_root() {
+		let logs = vec![Log {
+			address: H160::repeat_byte(4),
+			topics: vec![H256::repeat_byte(5)],
+			data: vec![6],
+		}];
+		let regolith = DepositReceiptData::new(1, 21_000.into(), logs.clone(), Some(7), None);
+		let bedrock = DepositReceiptData::new(1, 21_000.into(), logs.clone(), None, None);
+
+		// A Regolith receipt is hashed as a typed EIP-658 receipt.
+		let expected = crate::util::ordered_trie_root([[
+			&[DEPOSIT_TX_TYPE][..],
+			&rlp::encode(&EIP658ReceiptData::new(1, 21_000.into(), logs)),
+		]
+		.concat()]);
+		assert_eq!(receipts_root(&[OpReceipt::Deposit(regolith)]), expected);
+		assert_eq!(receipts_root(&[OpReceipt::Deposit(bedrock)]), expected);
+	}
+
+	#[test]
+	#[should_panic(expected = "deposit receipt version without a deposit nonce")]
+	fn reject_version_without_nonce() {
+		let receipt = DepositReceiptData {
+			deposit_receipt_version: Some(1),
+			..DepositReceiptData::new(1, 21_000.into(), Vec::new(), None, None)
+		};
+		rlp::encode(&receipt);
+	}
+
+	#[cfg(feature = "with-serde")]
+	#[test]
+	fn tagged_receipts() {
+		use crate::Tagged;
+
+		let data = EIP658ReceiptData::new(1, 21_000.into(), Vec::new());
+		let receipts = [
+			OpReceipt::Legacy(data.clone()),
+			OpReceipt::EIP2930(data.clone()),
+			OpReceipt::EIP1559(data),
+			OpReceipt::Deposit(DepositReceiptData::new(
+				1,
+				21_000.into(),
+				Vec::new(),
+				Some(7),
+				Some(1),
+			)),
+		];
+
+		for receipt in receipts {
+			let json = serde_json::to_value(Tagged(receipt.clone())).unwrap();
+			assert_eq!(
+				serde_json::from_value::<Tagged<OpReceipt>>(json)
+					.unwrap()
+					.into_inner(),
+				receipt
+			);
+		}
+
+		let transaction = OpTransaction::from(deposit());
+		let json = serde_json::to_value(Tagged(transaction.clone())).unwrap();
+		assert_eq!(json["type"], "Deposit");
+		assert_eq!(
+			serde_json::from_value::<Tagged<OpTransaction>>(json)
+				.unwrap()
+				.into_inner(),
+			transaction
+		);
+	}
+}
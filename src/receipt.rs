@@ -1,12 +1,14 @@
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 use ethereum_types::{Bloom, H256, U256};
 use rlp::{Decodable, DecoderError, Rlp};
 
 use crate::{
 	enveloped::{EnvelopedDecodable, EnvelopedDecoderError, EnvelopedEncodable},
-	log::Log,
+	header::Header,
+	log::{logs_bloom, Log},
+	util::ordered_trie_root,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -23,6 +25,19 @@ pub struct FrontierReceiptData {
 	pub logs: Vec<Log>,
 }
 
+impl FrontierReceiptData {
+	/// Create a new receipt, computing the logs bloom from the logs.
+	#[must_use]
+	pub fn new(state_root: H256, used_gas: U256, logs: Vec<Log>) -> Self {
+		Self {
+			state_root,
+			used_gas,
+			logs_bloom: logs_bloom(&logs),
+			logs,
+		}
+	}
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
 #[cfg_attr(
@@ -37,6 +52,148 @@ pub struct EIP658ReceiptData {
 	pub logs: Vec<Log>,
 }
 
+impl EIP658ReceiptData {
+	/// Create a new receipt, computing the logs bloom from the logs.
+	#[must_use]
+	pub fn new(status_code: u8, used_gas: U256, logs: Vec<Log>) -> Self {
+		Self {
+			status_code,
+			used_gas,
+			logs_bloom: logs_bloom(&logs),
+			logs,
+		}
+	}
+}
+
+/// Common accessors of all receipt types.
+pub trait Receipt {
+	/// Cumulative gas used in the block after the transaction.
+	fn used_gas(&self) -> U256;
+
+	/// Logs emitted by the transaction.
+	fn logs(&self) -> &[Log];
+
+	/// Logs bloom stored in the receipt.
+	fn logs_bloom(&self) -> &Bloom;
+
+	/// Compute the logs bloom from the logs of the receipt.
+	fn compute_logs_bloom(&self) -> Bloom {
+		logs_bloom(self.logs())
+	}
+}
+
+impl Receipt for FrontierReceiptData {
+	fn used_gas(&self) -> U256 {
+		self.used_gas
+	}
+
+	fn logs(&self) -> &[Log] {
+		&self.logs
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		&self.logs_bloom
+	}
+}
+
+impl Receipt for EIP658ReceiptData {
+	fn used_gas(&self) -> U256 {
+		self.used_gas
+	}
+
+	fn logs(&self) -> &[Log] {
+		&self.logs
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		&self.logs_bloom
+	}
+}
+
+/// OR the logs blooms of all receipts of a block into the block logs bloom.
+pub fn block_logs_bloom<'a, R, I>(receipts: I) -> Bloom
+where
+	R: Receipt + 'a,
+	I: IntoIterator<Item = &'a R>,
+{
+	let mut bloom = Bloom::zero();
+	for receipt in receipts {
+		bloom.accrue_bloom(receipt.logs_bloom());
+	}
+	bloom
+}
+
+/// Compute the receipts root of a list of receipts.
+pub fn receipts_root<R: EnvelopedEncodable>(receipts: &[R]) -> H256 {
+	ordered_trie_root(
+		receipts
+			.iter()
+			.map(|r| EnvelopedEncodable::encode(r).freeze()),
+	)
+}
+
+/// Error returned when the receipts of a block do not match its header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReceiptsValidationError {
+	/// Receipts root in the header does not match the receipts.
+	ReceiptsRootMismatch { expected: H256, computed: H256 },
+	/// Gas used in the header does not match the last cumulative gas used.
+	GasUsedMismatch { expected: U256, computed: U256 },
+	/// Logs bloom in the header does not match the receipt blooms.
+	LogsBloomMismatch {
+		expected: Box<Bloom>,
+		computed: Box<Bloom>,
+	},
+	/// Cumulative gas used decreased at the given receipt index.
+	CumulativeGasDecreased {
+		index: usize,
+		previous: U256,
+		current: U256,
+	},
+}
+
+/// Check that the receipts produced by executing a block are consistent with its header.
+pub fn validate_receipts<R>(header: &Header, receipts: &[R]) -> Result<(), ReceiptsValidationError>
+where
+	R: Receipt + EnvelopedEncodable,
+{
+	let mut used_gas = U256::zero();
+	for (index, receipt) in receipts.iter().enumerate() {
+		if receipt.used_gas() < used_gas {
+			return Err(ReceiptsValidationError::CumulativeGasDecreased {
+				index,
+				previous: used_gas,
+				current: receipt.used_gas(),
+			});
+		}
+		used_gas = receipt.used_gas();
+	}
+	if used_gas != header.gas_used {
+		return Err(ReceiptsValidationError::GasUsedMismatch {
+			expected: header.gas_used,
+			computed: used_gas,
+		});
+	}
+
+	let computed = block_logs_bloom(receipts);
+	if computed != header.logs_bloom {
+		return Err(ReceiptsValidationError::LogsBloomMismatch {
+			expected: Box::new(header.logs_bloom),
+			computed: Box::new(computed),
+		});
+	}
+
+	let computed = receipts_root(receipts);
+	if computed != header.receipts_root {
+		return Err(ReceiptsValidationError::ReceiptsRootMismatch {
+			expected: header.receipts_root,
+			computed,
+		});
+	}
+
+	Ok(())
+}
+
 pub type EIP2930ReceiptData = EIP658ReceiptData;
 
 pub type EIP1559ReceiptData = EIP658ReceiptData;
@@ -50,6 +207,12 @@ impl EnvelopedEncodable for ReceiptV0 {
 	fn encode_payload(&self) -> BytesMut {
 		rlp::encode(self)
 	}
+	fn payload_len(&self) -> usize {
+		self.encoded_len()
+	}
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		self.encode_to(out);
+	}
 }
 
 impl EnvelopedDecodable for ReceiptV0 {
@@ -69,6 +232,12 @@ impl EnvelopedEncodable for ReceiptV1 {
 	fn encode_payload(&self) -> BytesMut {
 		rlp::encode(self)
 	}
+	fn payload_len(&self) -> usize {
+		self.encoded_len()
+	}
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		self.encode_to(out);
+	}
 }
 
 impl EnvelopedDecodable for ReceiptV1 {
@@ -96,6 +265,29 @@ pub enum ReceiptV2 {
 	EIP2930(EIP2930ReceiptData),
 }
 
+impl Receipt for ReceiptV2 {
+	fn used_gas(&self) -> U256 {
+		match self {
+			Self::Legacy(r) => r.used_gas(),
+			Self::EIP2930(r) => r.used_gas(),
+		}
+	}
+
+	fn logs(&self) -> &[Log] {
+		match self {
+			Self::Legacy(r) => r.logs(),
+			Self::EIP2930(r) => r.logs(),
+		}
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		match self {
+			Self::Legacy(r) => r.logs_bloom(),
+			Self::EIP2930(r) => r.logs_bloom(),
+		}
+	}
+}
+
 impl EnvelopedEncodable for ReceiptV2 {
 	fn type_id(&self) -> Option<u8> {
 		match self {
@@ -110,6 +302,20 @@ impl EnvelopedEncodable for ReceiptV2 {
 			Self::EIP2930(r) => rlp::encode(r),
 		}
 	}
+
+	fn payload_len(&self) -> usize {
+		match self {
+			Self::Legacy(r) => r.encoded_len(),
+			Self::EIP2930(r) => r.encoded_len(),
+		}
+	}
+
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		match self {
+			Self::Legacy(r) => r.encode_to(out),
+			Self::EIP2930(r) => r.encode_to(out),
+		}
+	}
 }
 
 impl EnvelopedDecodable for ReceiptV2 {
@@ -165,6 +371,32 @@ pub enum ReceiptV3 {
 	EIP1559(EIP1559ReceiptData),
 }
 
+impl Receipt for ReceiptV3 {
+	fn used_gas(&self) -> U256 {
+		match self {
+			Self::Legacy(r) => r.used_gas(),
+			Self::EIP2930(r) => r.used_gas(),
+			Self::EIP1559(r) => r.used_gas(),
+		}
+	}
+
+	fn logs(&self) -> &[Log] {
+		match self {
+			Self::Legacy(r) => r.logs(),
+			Self::EIP2930(r) => r.logs(),
+			Self::EIP1559(r) => r.logs(),
+		}
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		match self {
+			Self::Legacy(r) => r.logs_bloom(),
+			Self::EIP2930(r) => r.logs_bloom(),
+			Self::EIP1559(r) => r.logs_bloom(),
+		}
+	}
+}
+
 impl EnvelopedEncodable for ReceiptV3 {
 	fn type_id(&self) -> Option<u8> {
 		match self {
@@ -181,6 +413,22 @@ impl EnvelopedEncodable for ReceiptV3 {
 			Self::EIP1559(r) => rlp::encode(r),
 		}
 	}
+
+	fn payload_len(&self) -> usize {
+		match self {
+			Self::Legacy(r) => r.encoded_len(),
+			Self::EIP2930(r) => r.encoded_len(),
+			Self::EIP1559(r) => r.encoded_len(),
+		}
+	}
+
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		match self {
+			Self::Legacy(r) => r.encode_to(out),
+			Self::EIP2930(r) => r.encode_to(out),
+			Self::EIP1559(r) => r.encode_to(out),
+		}
+	}
 }
 
 impl EnvelopedDecodable for ReceiptV3 {
@@ -212,6 +460,16 @@ impl EnvelopedDecodable for ReceiptV3 {
 	}
 }
 
+impl From<ReceiptV3> for ReceiptAny {
+	fn from(v3: ReceiptV3) -> Self {
+		match v3 {
+			ReceiptV3::Legacy(r) => ReceiptAny::EIP658(r),
+			ReceiptV3::EIP2930(r) => ReceiptAny::EIP2930(r),
+			ReceiptV3::EIP1559(r) => ReceiptAny::EIP1559(r),
+		}
+	}
+}
+
 impl From<ReceiptV3> for EIP658ReceiptData {
 	fn from(v3: ReceiptV3) -> Self {
 		match v3 {
@@ -243,6 +501,35 @@ pub enum ReceiptAny {
 	EIP1559(EIP1559ReceiptData),
 }
 
+impl Receipt for ReceiptAny {
+	fn used_gas(&self) -> U256 {
+		match self {
+			Self::Frontier(r) => r.used_gas(),
+			Self::EIP658(r) => r.used_gas(),
+			Self::EIP2930(r) => r.used_gas(),
+			Self::EIP1559(r) => r.used_gas(),
+		}
+	}
+
+	fn logs(&self) -> &[Log] {
+		match self {
+			Self::Frontier(r) => r.logs(),
+			Self::EIP658(r) => r.logs(),
+			Self::EIP2930(r) => r.logs(),
+			Self::EIP1559(r) => r.logs(),
+		}
+	}
+
+	fn logs_bloom(&self) -> &Bloom {
+		match self {
+			Self::Frontier(r) => r.logs_bloom(),
+			Self::EIP658(r) => r.logs_bloom(),
+			Self::EIP2930(r) => r.logs_bloom(),
+			Self::EIP1559(r) => r.logs_bloom(),
+		}
+	}
+}
+
 impl EnvelopedEncodable for ReceiptAny {
 	fn type_id(&self) -> Option<u8> {
 		match self {
@@ -261,6 +548,24 @@ impl EnvelopedEncodable for ReceiptAny {
 			Self::EIP1559(r) => rlp::encode(r),
 		}
 	}
+
+	fn payload_len(&self) -> usize {
+		match self {
+			Self::Frontier(r) => r.encoded_len(),
+			Self::EIP658(r) => r.encoded_len(),
+			Self::EIP2930(r) => r.encoded_len(),
+			Self::EIP1559(r) => r.encoded_len(),
+		}
+	}
+
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		match self {
+			Self::Frontier(r) => r.encode_to(out),
+			Self::EIP658(r) => r.encode_to(out),
+			Self::EIP2930(r) => r.encode_to(out),
+			Self::EIP1559(r) => r.encode_to(out),
+		}
+	}
 }
 
 impl EnvelopedDecodable for ReceiptAny {
@@ -278,9 +583,9 @@ impl EnvelopedDecodable for ReceiptAny {
 			if rlp.item_count()? == 4 {
 				let first = rlp.at(0)?;
 				if first.is_data() && first.data()?.len() <= 1 {
-					return Ok(Self::Frontier(Decodable::decode(&rlp)?));
-				} else {
 					return Ok(Self::EIP658(Decodable::decode(&rlp)?));
+				} else {
+					return Ok(Self::Frontier(Decodable::decode(&rlp)?));
 				}
 			}
 
@@ -300,3 +605,106 @@ impl EnvelopedDecodable for ReceiptAny {
 		Err(DecoderError::Custom("invalid receipt type").into())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::header::PartialHeader;
+	use crate::test_utils::partial_header;
+
+	use hex_literal::hex;
+
+	fn header_for(receipts: &[ReceiptV3]) -> Header {
+		let partial_header = PartialHeader {
+			receipts_root: receipts_root(receipts),
+			logs_bloom: block_logs_bloom(receipts),
+			gas_used: receipts.last().map(|r| r.used_gas()).unwrap_or_default(),
+			..partial_header()
+		};
+		Header::new(partial_header, H256::zero(), H256::zero())
+	}
+
+	#[test]
+	fn validate_empty_receipts() {
+		let header = header_for(&[]);
+		assert_eq!(
+			header.receipts_root,
+			H256::from(hex!(
+				"56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+			))
+		);
+		assert_eq!(validate_receipts::<ReceiptV3>(&header, &[]), Ok(()));
+	}
+
+	#[test]
+	fn validate_receipts_against_header() {
+		let log = Log {
+			address: hex!("22341ae42d6dd7384bc8584e50419ea3ac75b83f").into(),
+			topics: vec![
+				hex!("04491edcd115127caedbd478e2e7895ed80c7847e903431f94f9cfa579cad47f").into(),
+			],
+			data: vec![],
+		};
+		let receipts = vec![
+			ReceiptV3::Legacy(EIP658ReceiptData::new(1, 21000.into(), vec![])),
+			ReceiptV3::EIP1559(EIP658ReceiptData::new(1, 50000.into(), vec![log])),
+		];
+		let header = header_for(&receipts);
+		assert_eq!(validate_receipts(&header, &receipts), Ok(()));
+
+		let mut invalid = header.clone();
+		invalid.gas_used = 21000.into();
+		assert_eq!(
+			validate_receipts(&invalid, &receipts),
+			Err(ReceiptsValidationError::GasUsedMismatch {
+				expected: 21000.into(),
+				computed: 50000.into(),
+			})
+		);
+
+		let mut invalid = header.clone();
+		invalid.logs_bloom = Bloom::zero();
+		assert!(matches!(
+			validate_receipts(&invalid, &receipts),
+			Err(ReceiptsValidationError::LogsBloomMismatch { .. })
+		));
+
+		let mut invalid = header;
+		invalid.receipts_root = H256::zero();
+		assert!(matches!(
+			validate_receipts(&invalid, &receipts),
+			Err(ReceiptsValidationError::ReceiptsRootMismatch { .. })
+		));
+
+		let reversed = receipts.into_iter().rev().collect::<Vec<_>>();
+		assert!(matches!(
+			validate_receipts(&header_for(&reversed), &reversed),
+			Err(ReceiptsValidationError::CumulativeGasDecreased { index: 1, .. })
+		));
+	}
+
+	#[test]
+	fn decode_legacy_receipts() {
+		// Status receipts have a single byte first field, Frontier receipts a
+		// 32 bytes state root.
+		for status in [0, 1] {
+			let receipt = ReceiptAny::EIP658(EIP658ReceiptData::new(status, 21000.into(), vec![]));
+			let bytes = EnvelopedEncodable::encode(&receipt);
+			assert_eq!(
+				<ReceiptAny as EnvelopedDecodable>::decode(&bytes),
+				Ok(receipt)
+			);
+		}
+
+		let receipt = ReceiptAny::Frontier(FrontierReceiptData::new(
+			H256::repeat_byte(1),
+			21000.into(),
+			vec![],
+		));
+		let bytes = EnvelopedEncodable::encode(&receipt);
+		assert_eq!(
+			<ReceiptAny as EnvelopedDecodable>::decode(&bytes),
+			Ok(receipt)
+		);
+	}
+}
@@ -0,0 +1,975 @@
+//! JSON-RPC representations of blocks, transactions and receipts.
+//!
+//! These types follow the `eth_` namespace wire format: camelCase names,
+//! `0x`-prefixed hex quantities, a `type` discriminator and the derived
+//! fields returned by execution clients.
+
+use alloc::vec::Vec;
+
+use ethereum_types::{BigEndianHash, Bloom, H160, H256, H64, U256, U64};
+
+use crate::{
+	block::{Block, BlockV2},
+	enveloped::EnvelopedEncodable,
+	header::{Header, SealedHeader},
+	log::Log,
+	receipt::{EIP658ReceiptData, FrontierReceiptData, ReceiptAny, ReceiptV3},
+	transaction::{
+		AccessListItem, EIP1559Transaction, EIP2930Transaction, LegacyTransaction,
+		TransactionAction, TransactionSignature, TransactionV2,
+	},
+	withdrawal::Withdrawal,
+	Bytes,
+};
+
+/// Error returned when an RPC representation does not convert to a consensus type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RpcConversionError {
+	/// Transaction or receipt type is not supported.
+	UnknownType(U64),
+	/// Field required by the type is missing.
+	MissingField(&'static str),
+	/// Field value is out of range.
+	InvalidField(&'static str),
+	/// Signature values are invalid.
+	InvalidSignature,
+	/// Hash field does not match the hash of the converted value.
+	HashMismatch { expected: H256, computed: H256 },
+	/// Ommer headers do not match the uncle hashes of the block.
+	OmmersMismatch,
+	/// Number of senders does not match the number of transactions.
+	SendersMismatch { transactions: usize, senders: usize },
+}
+
+/// Header as returned by `eth_getBlockByNumber`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHeader {
+	pub hash: H256,
+	pub parent_hash: H256,
+	#[serde(rename = "sha3Uncles")]
+	pub ommers_hash: H256,
+	#[serde(rename = "miner")]
+	pub beneficiary: H160,
+	pub state_root: H256,
+	pub transactions_root: H256,
+	pub receipts_root: H256,
+	pub logs_bloom: Bloom,
+	pub difficulty: U256,
+	pub number: U256,
+	pub gas_limit: U256,
+	pub gas_used: U256,
+	#[serde(with = "crate::hex::quantity")]
+	pub timestamp: u64,
+	#[serde(with = "crate::hex::bytes")]
+	pub extra_data: Bytes,
+	pub mix_hash: H256,
+	pub nonce: H64,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub base_fee_per_gas: Option<U256>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub withdrawals_root: Option<H256>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub blob_gas_used: Option<U64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub excess_blob_gas: Option<U64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub parent_beacon_block_root: Option<H256>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub requests_hash: Option<H256>,
+}
+
+impl From<&SealedHeader> for RpcHeader {
+	fn from(header: &SealedHeader) -> Self {
+		Self {
+			hash: header.hash(),
+			parent_hash: header.parent_hash,
+			ommers_hash: header.ommers_hash,
+			beneficiary: header.beneficiary,
+			state_root: header.state_root,
+			transactions_root: header.transactions_root,
+			receipts_root: header.receipts_root,
+			logs_bloom: header.logs_bloom,
+			difficulty: header.difficulty,
+			number: header.number,
+			gas_limit: header.gas_limit,
+			gas_used: header.gas_used,
+			timestamp: header.timestamp,
+			extra_data: header.extra_data.clone(),
+			mix_hash: header.mix_hash,
+			nonce: header.nonce,
+			base_fee_per_gas: header.base_fee_per_gas,
+			withdrawals_root: header.withdrawals_root,
+			blob_gas_used: header.blob_gas_used.map(Into::into),
+			excess_blob_gas: header.excess_blob_gas.map(Into::into),
+			parent_beacon_block_root: header.parent_beacon_block_root,
+			requests_hash: header.requests_hash,
+		}
+	}
+}
+
+impl From<&Header> for RpcHeader {
+	fn from(header: &Header) -> Self {
+		(&SealedHeader::seal(header.clone())).into()
+	}
+}
+
+impl TryFrom<RpcHeader> for SealedHeader {
+	type Error = RpcConversionError;
+
+	fn try_from(header: RpcHeader) -> Result<Self, Self::Error> {
+		let hash = header.hash;
+		SealedHeader::new(
+			Header {
+				parent_hash: header.parent_hash,
+				ommers_hash: header.ommers_hash,
+				beneficiary: header.beneficiary,
+				state_root: header.state_root,
+				transactions_root: header.transactions_root,
+				receipts_root: header.receipts_root,
+				logs_bloom: header.logs_bloom,
+				difficulty: header.difficulty,
+				number: header.number,
+				gas_limit: header.gas_limit,
+				gas_used: header.gas_used,
+				timestamp: header.timestamp,
+				extra_data: header.extra_data,
+				mix_hash: header.mix_hash,
+				nonce: header.nonce,
+				base_fee_per_gas: header.base_fee_per_gas,
+				withdrawals_root: header.withdrawals_root,
+				blob_gas_used: header.blob_gas_used.map(|gas| gas.as_u64()),
+				excess_blob_gas: header.excess_blob_gas.map(|gas| gas.as_u64()),
+				parent_beacon_block_root: header.parent_beacon_block_root,
+				requests_hash: header.requests_hash,
+			},
+			hash,
+		)
+		.map_err(|e| RpcConversionError::HashMismatch {
+			expected: e.expected,
+			computed: e.computed,
+		})
+	}
+}
+
+/// Access list entry with RPC field names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccessListItem {
+	pub address: H160,
+	pub storage_keys: Vec<H256>,
+}
+
+impl From<&AccessListItem> for RpcAccessListItem {
+	fn from(item: &AccessListItem) -> Self {
+		Self {
+			address: item.address,
+			storage_keys: item.storage_keys.clone(),
+		}
+	}
+}
+
+impl From<RpcAccessListItem> for AccessListItem {
+	fn from(item: RpcAccessListItem) -> Self {
+		Self {
+			address: item.address,
+			storage_keys: item.storage_keys,
+		}
+	}
+}
+
+/// Inclusion context of a transaction, not part of the transaction itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionInfo {
+	pub block_hash: Option<H256>,
+	pub block_number: Option<U256>,
+	pub transaction_index: Option<u64>,
+	/// Base fee of the including block, used to compute the effective gas price.
+	pub base_fee_per_gas: Option<U256>,
+}
+
+/// Transaction as returned by `eth_getTransactionByHash`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransaction {
+	pub block_hash: Option<H256>,
+	pub block_number: Option<U256>,
+	pub from: H160,
+	pub gas: U256,
+	pub gas_price: U256,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_fee_per_gas: Option<U256>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_priority_fee_per_gas: Option<U256>,
+	pub hash: H256,
+	#[serde(with = "crate::hex::bytes")]
+	pub input: Bytes,
+	pub nonce: U256,
+	pub to: Option<H160>,
+	pub transaction_index: Option<U64>,
+	pub value: U256,
+	#[serde(rename = "type")]
+	pub transaction_type: U64,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub access_list: Option<Vec<RpcAccessListItem>>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub chain_id: Option<U256>,
+	pub v: U256,
+	pub r: U256,
+	pub s: U256,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub y_parity: Option<U64>,
+}
+
+fn action_to(action: &TransactionAction) -> Option<H160> {
+	match action {
+		TransactionAction::Call(address) => Some(*address),
+		TransactionAction::Create => None,
+	}
+}
+
+fn to_action(to: Option<H160>) -> TransactionAction {
+	to.map_or(TransactionAction::Create, TransactionAction::Call)
+}
+
+fn access_list(access_list: &[AccessListItem]) -> Option<Vec<RpcAccessListItem>> {
+	Some(access_list.iter().map(Into::into).collect())
+}
+
+impl RpcTransaction {
+	/// Build the RPC representation of a transaction sent by `from`.
+	#[must_use]
+	pub fn new(transaction: &TransactionV2, from: H160, info: &TransactionInfo) -> Self {
+		let mut rpc = Self {
+			block_hash: info.block_hash,
+			block_number: info.block_number,
+			from,
+			gas: U256::zero(),
+			gas_price: U256::zero(),
+			max_fee_per_gas: None,
+			max_priority_fee_per_gas: None,
+			hash: transaction.hash(),
+			input: Vec::new(),
+			nonce: U256::zero(),
+			to: None,
+			transaction_index: info.transaction_index.map(Into::into),
+			value: U256::zero(),
+			transaction_type: transaction.type_id().unwrap_or(0).into(),
+			access_list: None,
+			chain_id: None,
+			v: U256::zero(),
+			r: U256::zero(),
+			s: U256::zero(),
+			y_parity: None,
+		};
+
+		match transaction {
+			TransactionV2::Legacy(tx) => {
+				rpc.gas = tx.gas_limit;
+				rpc.gas_price = tx.gas_price;
+				rpc.input = tx.input.clone();
+				rpc.nonce = tx.nonce;
+				rpc.to = action_to(&tx.action);
+				rpc.value = tx.value;
+				rpc.chain_id = tx.signature.chain_id().map(Into::into);
+				rpc.v = tx.signature.v().into();
+				rpc.r = tx.signature.r().into_uint();
+				rpc.s = tx.signature.s().into_uint();
+			}
+			TransactionV2::EIP2930(tx) => {
+				rpc.gas = tx.gas_limit;
+				rpc.gas_price = tx.gas_price;
+				rpc.input = tx.input.clone();
+				rpc.nonce = tx.nonce;
+				rpc.to = action_to(&tx.action);
+				rpc.value = tx.value;
+				rpc.access_list = access_list(&tx.access_list);
+				rpc.chain_id = Some(tx.chain_id.into());
+				rpc.v = u8::from(tx.odd_y_parity).into();
+				rpc.r = tx.r.into_uint();
+				rpc.s = tx.s.into_uint();
+				rpc.y_parity = Some(u8::from(tx.odd_y_parity).into());
+			}
+			TransactionV2::EIP1559(tx) => {
+				rpc.gas = tx.gas_limit;
+				rpc.gas_price = match info.base_fee_per_gas {
+					Some(base_fee) => tx
+						.max_fee_per_gas
+						.min(base_fee.saturating_add(tx.max_priority_fee_per_gas)),
+					None => tx.max_fee_per_gas,
+				};
+				rpc.max_fee_per_gas = Some(tx.max_fee_per_gas);
+				rpc.max_priority_fee_per_gas = Some(tx.max_priority_fee_per_gas);
+				rpc.input = tx.input.clone();
+				rpc.nonce = tx.nonce;
+				rpc.to = action_to(&tx.action);
+				rpc.value = tx.value;
+				rpc.access_list = access_list(&tx.access_list);
+				rpc.chain_id = Some(tx.chain_id.into());
+				rpc.v = u8::from(tx.odd_y_parity).into();
+				rpc.r = tx.r.into_uint();
+				rpc.s = tx.s.into_uint();
+				rpc.y_parity = Some(u8::from(tx.odd_y_parity).into());
+			}
+		}
+
+		rpc
+	}
+
+	fn chain_id(&self) -> Result<u64, RpcConversionError> {
+		let chain_id = self
+			.chain_id
+			.ok_or(RpcConversionError::MissingField("chainId"))?;
+		if chain_id > U256::from(u64::MAX) {
+			return Err(RpcConversionError::InvalidField("chainId"));
+		}
+		Ok(chain_id.as_u64())
+	}
+
+	fn odd_y_parity(&self) -> Result<bool, RpcConversionError> {
+		match self.y_parity.map(|y| y.as_u64()).or_else(|| {
+			if self.v <= U256::one() {
+				Some(self.v.as_u64())
+			} else {
+				None
+			}
+		}) {
+			Some(0) => Ok(false),
+			Some(1) => Ok(true),
+			_ => Err(RpcConversionError::InvalidSignature),
+		}
+	}
+
+	fn access_list(&self) -> Vec<AccessListItem> {
+		self.access_list
+			.iter()
+			.flatten()
+			.cloned()
+			.map(Into::into)
+			.collect()
+	}
+}
+
+impl TryFrom<RpcTransaction> for TransactionV2 {
+	type Error = RpcConversionError;
+
+	fn try_from(rpc: RpcTransaction) -> Result<Self, Self::Error> {
+		let transaction = match rpc.transaction_type.as_u64() {
+			0 => {
+				if rpc.v > U256::from(u64::MAX) {
+					return Err(RpcConversionError::InvalidSignature);
+				}
+				let signature = TransactionSignature::new(
+					rpc.v.as_u64(),
+					H256::from_uint(&rpc.r),
+					H256::from_uint(&rpc.s),
+				)
+				.ok_or(RpcConversionError::InvalidSignature)?;
+
+				TransactionV2::Legacy(LegacyTransaction {
+					nonce: rpc.nonce,
+					gas_price: rpc.gas_price,
+					gas_limit: rpc.gas,
+					action: to_action(rpc.to),
+					value: rpc.value,
+					input: rpc.input.clone(),
+					signature,
+				})
+			}
+			1 => TransactionV2::EIP2930(EIP2930Transaction {
+				chain_id: rpc.chain_id()?,
+				nonce: rpc.nonce,
+				gas_price: rpc.gas_price,
+				gas_limit: rpc.gas,
+				action: to_action(rpc.to),
+				value: rpc.value,
+				input: rpc.input.clone(),
+				access_list: rpc.access_list(),
+				odd_y_parity: rpc.odd_y_parity()?,
+				r: H256::from_uint(&rpc.r),
+				s: H256::from_uint(&rpc.s),
+			}),
+			2 => TransactionV2::EIP1559(EIP1559Transaction {
+				chain_id: rpc.chain_id()?,
+				nonce: rpc.nonce,
+				max_priority_fee_per_gas: rpc
+					.max_priority_fee_per_gas
+					.ok_or(RpcConversionError::MissingField("maxPriorityFeePerGas"))?,
+				max_fee_per_gas: rpc
+					.max_fee_per_gas
+					.ok_or(RpcConversionError::MissingField("maxFeePerGas"))?,
+				gas_limit: rpc.gas,
+				action: to_action(rpc.to),
+				value: rpc.value,
+				input: rpc.input.clone(),
+				access_list: rpc.access_list(),
+				odd_y_parity: rpc.odd_y_parity()?,
+				r: H256::from_uint(&rpc.r),
+				s: H256::from_uint(&rpc.s),
+			}),
+			_ => return Err(RpcConversionError::UnknownType(rpc.transaction_type)),
+		};
+
+		let computed = transaction.hash();
+		if computed != rpc.hash {
+			return Err(RpcConversionError::HashMismatch {
+				expected: rpc.hash,
+				computed,
+			});
+		}
+
+		Ok(transaction)
+	}
+}
+
+/// Transactions of a block, either as hashes or as full objects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum BlockTransactions {
+	Hashes(Vec<H256>),
+	Full(Vec<RpcTransaction>),
+}
+
+/// Block as returned by `eth_getBlockByNumber`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlock {
+	#[serde(flatten)]
+	pub header: RpcHeader,
+	pub size: U256,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total_difficulty: Option<U256>,
+	pub transactions: BlockTransactions,
+	pub uncles: Vec<H256>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl RpcBlock {
+	/// Build the RPC representation of a block, listing transaction hashes only.
+	#[must_use]
+	pub fn with_hashes<T: EnvelopedEncodable>(block: &Block<T>) -> Self {
+		let hashes = block
+			.transactions
+			.iter()
+			.map(|tx| {
+				use sha3::{Digest, Keccak256};
+				H256::from_slice(Keccak256::digest(EnvelopedEncodable::encode(tx)).as_slice())
+			})
+			.collect();
+
+		Self {
+			header: (&block.header).into(),
+			size: rlp::encode(block).len().into(),
+			total_difficulty: None,
+			transactions: BlockTransactions::Hashes(hashes),
+			uncles: block.ommers.iter().map(Header::hash).collect(),
+			withdrawals: block.withdrawals.clone(),
+		}
+	}
+
+	/// Build the RPC representation of a block with full transactions.
+	///
+	/// `senders` holds the sender of each transaction, in order.
+	pub fn with_transactions(
+		block: &BlockV2,
+		senders: &[H160],
+	) -> Result<Self, RpcConversionError> {
+		if block.transactions.len() != senders.len() {
+			return Err(RpcConversionError::SendersMismatch {
+				transactions: block.transactions.len(),
+				senders: senders.len(),
+			});
+		}
+
+		let mut rpc = Self::with_hashes(block);
+		rpc.transactions = BlockTransactions::Full(
+			block
+				.transactions
+				.iter()
+				.zip(senders)
+				.enumerate()
+				.map(|(index, (tx, from))| {
+					let info = TransactionInfo {
+						block_hash: Some(rpc.header.hash),
+						block_number: Some(block.header.number),
+						transaction_index: Some(index as u64),
+						base_fee_per_gas: block.header.base_fee_per_gas,
+					};
+					RpcTransaction::new(tx, *from, &info)
+				})
+				.collect(),
+		);
+		Ok(rpc)
+	}
+
+	/// Convert into a block, given the full ommer headers of the uncle hashes.
+	pub fn into_block(self, ommers: Vec<Header>) -> Result<BlockV2, RpcConversionError> {
+		if ommers.len() != self.uncles.len()
+			|| ommers
+				.iter()
+				.zip(&self.uncles)
+				.any(|(ommer, hash)| ommer.hash() != *hash)
+		{
+			return Err(RpcConversionError::OmmersMismatch);
+		}
+
+		let transactions = match self.transactions {
+			BlockTransactions::Full(transactions) => transactions
+				.into_iter()
+				.map(TryInto::try_into)
+				.collect::<Result<Vec<_>, _>>()?,
+			BlockTransactions::Hashes(hashes) if hashes.is_empty() => Vec::new(),
+			BlockTransactions::Hashes(_) => {
+				return Err(RpcConversionError::MissingField("transactions"))
+			}
+		};
+
+		Ok(Block {
+			header: SealedHeader::try_from(self.header)?.into_inner(),
+			transactions,
+			ommers,
+			withdrawals: self.withdrawals,
+		})
+	}
+}
+
+/// Log as returned by `eth_getTransactionReceipt` and `eth_getLogs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcLog {
+	pub address: H160,
+	pub topics: Vec<H256>,
+	#[serde(with = "crate::hex::bytes")]
+	pub data: Bytes,
+	pub block_number: Option<U256>,
+	pub transaction_hash: Option<H256>,
+	pub transaction_index: Option<U64>,
+	pub block_hash: Option<H256>,
+	pub log_index: Option<U64>,
+	pub removed: bool,
+}
+
+impl From<RpcLog> for Log {
+	fn from(log: RpcLog) -> Self {
+		Self {
+			address: log.address,
+			topics: log.topics,
+			data: log.data,
+		}
+	}
+}
+
+/// Inclusion context of a receipt, not part of the consensus receipt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptInfo {
+	pub transaction_hash: H256,
+	pub transaction_index: u64,
+	pub block_hash: H256,
+	pub block_number: U256,
+	pub from: H160,
+	pub to: Option<H160>,
+	pub contract_address: Option<H160>,
+	/// Gas used by this transaction alone.
+	pub gas_used: U256,
+	pub effective_gas_price: U256,
+	/// Index in the block of the first log of the receipt.
+	pub first_log_index: u64,
+}
+
+/// Receipt as returned by `eth_getTransactionReceipt`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+	pub block_hash: H256,
+	pub block_number: U256,
+	pub contract_address: Option<H160>,
+	pub cumulative_gas_used: U256,
+	pub effective_gas_price: U256,
+	pub from: H160,
+	pub gas_used: U256,
+	pub logs: Vec<RpcLog>,
+	pub logs_bloom: Bloom,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub root: Option<H256>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub status: Option<U64>,
+	pub to: Option<H160>,
+	pub transaction_hash: H256,
+	pub transaction_index: U64,
+	#[serde(rename = "type")]
+	pub transaction_type: U64,
+}
+
+impl RpcReceipt {
+	/// Build the RPC representation of a receipt.
+	#[must_use]
+	pub fn new(receipt: &ReceiptAny, info: &ReceiptInfo) -> Self {
+		let (root, status, data) = match receipt {
+			ReceiptAny::Frontier(r) => (
+				Some(r.state_root),
+				None,
+				(r.used_gas, r.logs_bloom, &r.logs),
+			),
+			ReceiptAny::EIP658(r) | ReceiptAny::EIP2930(r) | ReceiptAny::EIP1559(r) => (
+				None,
+				Some(r.status_code.into()),
+				(r.used_gas, r.logs_bloom, &r.logs),
+			),
+		};
+		let (cumulative_gas_used, logs_bloom, logs) = data;
+
+		Self {
+			block_hash: info.block_hash,
+			block_number: info.block_number,
+			contract_address: info.contract_address,
+			cumulative_gas_used,
+			effective_gas_price: info.effective_gas_price,
+			from: info.from,
+			gas_used: info.gas_used,
+			logs: logs
+				.iter()
+				.zip(info.first_log_index..)
+				.map(|(log, index)| RpcLog {
+					address: log.address,
+					topics: log.topics.clone(),
+					data: log.data.clone(),
+					block_number: Some(info.block_number),
+					transaction_hash: Some(info.transaction_hash),
+					transaction_index: Some(info.transaction_index.into()),
+					block_hash: Some(info.block_hash),
+					log_index: Some(index.into()),
+					removed: false,
+				})
+				.collect(),
+			logs_bloom,
+			root,
+			status,
+			to: info.to,
+			transaction_hash: info.transaction_hash,
+			transaction_index: info.transaction_index.into(),
+			transaction_type: receipt.type_id().unwrap_or(0).into(),
+		}
+	}
+}
+
+impl TryFrom<RpcReceipt> for ReceiptAny {
+	type Error = RpcConversionError;
+
+	fn try_from(rpc: RpcReceipt) -> Result<Self, Self::Error> {
+		let logs = rpc.logs.into_iter().map(Into::into).collect();
+
+		if let Some(state_root) = rpc.root {
+			if !rpc.transaction_type.is_zero() {
+				return Err(RpcConversionError::InvalidField("root"));
+			}
+			return Ok(ReceiptAny::Frontier(FrontierReceiptData {
+				state_root,
+				used_gas: rpc.cumulative_gas_used,
+				logs_bloom: rpc.logs_bloom,
+				logs,
+			}));
+		}
+
+		let status = rpc
+			.status
+			.ok_or(RpcConversionError::MissingField("status"))?;
+		if status > U64::one() {
+			return Err(RpcConversionError::InvalidField("status"));
+		}
+		let data = EIP658ReceiptData {
+			status_code: status.as_u64() as u8,
+			used_gas: rpc.cumulative_gas_used,
+			logs_bloom: rpc.logs_bloom,
+			logs,
+		};
+
+		match rpc.transaction_type.as_u64() {
+			0 => Ok(ReceiptAny::EIP658(data)),
+			1 => Ok(ReceiptAny::EIP2930(data)),
+			2 => Ok(ReceiptAny::EIP1559(data)),
+			_ => Err(RpcConversionError::UnknownType(rpc.transaction_type)),
+		}
+	}
+}
+
+impl TryFrom<RpcReceipt> for ReceiptV3 {
+	type Error = RpcConversionError;
+
+	fn try_from(rpc: RpcReceipt) -> Result<Self, Self::Error> {
+		match ReceiptAny::try_from(rpc)? {
+			ReceiptAny::Frontier(_) => Err(RpcConversionError::MissingField("status")),
+			ReceiptAny::EIP658(r) => Ok(ReceiptV3::Legacy(r)),
+			ReceiptAny::EIP2930(r) => Ok(ReceiptV3::EIP2930(r)),
+			ReceiptAny::EIP1559(r) => Ok(ReceiptV3::EIP1559(r)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::mainnet_block_one;
+	use crate::withdrawal::withdrawals_root;
+	use serde_json::json;
+
+	#[test]
+	fn block_json() {
+		let block = Block {
+			header: mainnet_block_one(),
+			transactions: Vec::<TransactionV2>::new(),
+			ommers: Vec::new(),
+			withdrawals: None,
+		};
+		let expected = json!({
+			"difficulty": "0x3ff800000",
+			"extraData": "0x476574682f76312e302e302f6c696e75782f676f312e342e32",
+			"gasLimit": "0x1388",
+			"gasUsed": "0x0",
+			"hash": "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6",
+			"logsBloom": format!("0x{}", "0".repeat(512)),
+			"miner": "0x05a56e2d52c817161883f50c441c3228cfe54d9f",
+			"mixHash": "0x969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59",
+			"nonce": "0x539bd4979fef1ec4",
+			"number": "0x1",
+			"parentHash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+			"receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+			"sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+			"size": "0x219",
+			"stateRoot": "0xd67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3",
+			"timestamp": "0x55ba4224",
+			"totalDifficulty": "0x7ff800000",
+			"transactions": [],
+			"transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+			"uncles": []
+		});
+
+		let mut rpc = RpcBlock::with_hashes(&block);
+		rpc.total_difficulty = Some(34_351_349_760_u64.into());
+		assert_eq!(serde_json::to_value(&rpc).unwrap(), expected);
+
+		let decoded: RpcBlock = serde_json::from_value(expected).unwrap();
+		assert_eq!(decoded, rpc);
+		assert_eq!(decoded.into_block(Vec::new()), Ok(block));
+	}
+
+	#[test]
+	fn legacy_transaction_json() {
+		let expected = json!({
+			"blockHash": "0x4e3a3754410177e6937ef1f84bba68ea139e8d1a2258c5f85db9f1cd715a1bdd",
+			"blockNumber": "0xb443",
+			"from": "0xa1e4380a3b1f749673e270229993ee55f35663b4",
+			"gas": "0x5208",
+			"gasPrice": "0x2d79883d2000",
+			"hash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+			"input": "0x",
+			"nonce": "0x0",
+			"to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+			"transactionIndex": "0x0",
+			"value": "0x7a69",
+			"type": "0x0",
+			"v": "0x1c",
+			"r": "0x88ff6cf0fefd94db46111149ae4bfc179e9b94721fffd821d38d16464b3f71d0",
+			"s": "0x45e0aff800961cfce805daef7016b9b675c137a6a41a548f7b60a3484c06a33a"
+		});
+
+		let rpc: RpcTransaction = serde_json::from_value(expected.clone()).unwrap();
+		let transaction = TransactionV2::try_from(rpc.clone()).unwrap();
+		assert_eq!(transaction.hash(), rpc.hash);
+
+		let info = TransactionInfo {
+			block_hash: rpc.block_hash,
+			block_number: rpc.block_number,
+			transaction_index: Some(0),
+			base_fee_per_gas: None,
+		};
+		let encoded = RpcTransaction::new(&transaction, rpc.from, &info);
+		assert_eq!(serde_json::to_value(&encoded).unwrap(), expected);
+
+		let mut tampered = rpc;
+		tampered.value = 1.into();
+		assert!(matches!(
+			TransactionV2::try_from(tampered),
+			Err(RpcConversionError::HashMismatch { .. })
+		));
+	}
+
+	#[test]
+	fn receipt_json() {
+		let receipt = ReceiptAny::EIP1559(EIP658ReceiptData::new(
+			1,
+			50_000.into(),
+			vec![Log {
+				address: H160::repeat_byte(0x11),
+				topics: vec![H256::repeat_byte(0x22)],
+				data: vec![0xab],
+			}],
+		));
+		let info = ReceiptInfo {
+			transaction_hash: H256::repeat_byte(0x33),
+			transaction_index: 1,
+			block_hash: H256::repeat_byte(0x44),
+			block_number: 100.into(),
+			from: H160::repeat_byte(0x55),
+			to: Some(H160::repeat_byte(0x11)),
+			contract_address: None,
+			gas_used: 29_000.into(),
+			effective_gas_price: 7.into(),
+			first_log_index: 3,
+		};
+
+		let rpc = RpcReceipt::new(&receipt, &info);
+		let value = serde_json::to_value(&rpc).unwrap();
+		assert_eq!(value["type"], "0x2");
+		assert_eq!(value["status"], "0x1");
+		assert_eq!(value["cumulativeGasUsed"], "0xc350");
+		assert_eq!(value["logs"][0]["logIndex"], "0x3");
+		assert!(value.get("root").is_none());
+
+		let decoded: RpcReceipt = serde_json::from_value(value).unwrap();
+		assert_eq!(ReceiptAny::try_from(decoded), Ok(receipt));
+	}
+
+	// The typed transactions below are signed with the secret key 0x4242...42,
+	// whose address is the `from` field. They are not captured node responses.
+	fn typed_transaction_json(expected: serde_json::Value, base_fee_per_gas: Option<U256>) {
+		let rpc: RpcTransaction = serde_json::from_value(expected.clone()).unwrap();
+		let transaction = TransactionV2::try_from(rpc.clone()).unwrap();
+
+		let info = TransactionInfo {
+			block_hash: rpc.block_hash,
+			block_number: rpc.block_number,
+			transaction_index: Some(rpc.transaction_index.unwrap().as_u64()),
+			base_fee_per_gas,
+		};
+		let encoded = RpcTransaction::new(&transaction, rpc.from, &info);
+		assert_eq!(serde_json::to_value(&encoded).unwrap(), expected);
+	}
+
+	#[test]
+	fn eip2930_transaction_json() {
+		typed_transaction_json(
+			json!({
+				"blockHash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+				"blockNumber": "0xbc8af0",
+				"from": "0x17c5185167401ed00cf5f5b2fc97d9bbfdb7d025",
+				"gas": "0x7530",
+				"gasPrice": "0x3b9aca00",
+				"hash": "0xfe417732054bebe0d6e908356acf96ced80ecefe71a0f76d01a727ff974c0e82",
+				"input": "0x",
+				"nonce": "0x5",
+				"to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+				"transactionIndex": "0x3",
+				"value": "0xde0b6b3a7640000",
+				"type": "0x1",
+				"accessList": [{
+					"address": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+					"storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000001"]
+				}],
+				"chainId": "0x1",
+				"v": "0x1",
+				"r": "0xcb4bb12cea2c6f03f41981df13ab0d766091e218396c4520067b15bc5a8270b7",
+				"s": "0x3df1839dfb0b93594bd6b14fc40c8a55ada203dd5089a58cfbff4473816d9eb2",
+				"yParity": "0x1"
+			}),
+			None,
+		);
+	}
+
+	#[test]
+	fn eip1559_transaction_json() {
+		typed_transaction_json(
+			json!({
+				"blockHash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+				"blockNumber": "0xc5d488",
+				"from": "0x17c5185167401ed00cf5f5b2fc97d9bbfdb7d025",
+				"gas": "0x5208",
+				"gasPrice": "0x59682f00",
+				"maxFeePerGas": "0x77359400",
+				"maxPriorityFeePerGas": "0x3b9aca00",
+				"hash": "0xd7ca7b4f7a9d476ae1d6153eff0ef99780da47c92f2fca7c4bb99c0c28d29f69",
+				"input": "0x",
+				"nonce": "0x6",
+				"to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+				"transactionIndex": "0x0",
+				"value": "0x1",
+				"type": "0x2",
+				"accessList": [],
+				"chainId": "0x1",
+				"v": "0x0",
+				"r": "0x61a384ff27f14c8e30ba0fb053ad52ef34a33d19f155aea93dbfb4a7c032ca28",
+				"s": "0x6528c19cef3331271970db31f3f8ef68b61011d355558d43051e38141b40ae14",
+				"yParity": "0x0"
+			}),
+			Some(500_000_000.into()),
+		);
+	}
+
+	#[test]
+	fn block_with_transactions_json() {
+		let rpc: RpcTransaction = serde_json::from_value(json!({
+			"blockHash": null,
+			"blockNumber": null,
+			"from": "0x17c5185167401ed00cf5f5b2fc97d9bbfdb7d025",
+			"gas": "0x5208",
+			"gasPrice": "0x77359400",
+			"maxFeePerGas": "0x77359400",
+			"maxPriorityFeePerGas": "0x3b9aca00",
+			"hash": "0xd7ca7b4f7a9d476ae1d6153eff0ef99780da47c92f2fca7c4bb99c0c28d29f69",
+			"input": "0x",
+			"nonce": "0x6",
+			"to": "0x5df9b87991262f6ba471f09758cde1c0fc1de734",
+			"transactionIndex": null,
+			"value": "0x1",
+			"type": "0x2",
+			"accessList": [],
+			"chainId": "0x1",
+			"v": "0x0",
+			"r": "0x61a384ff27f14c8e30ba0fb053ad52ef34a33d19f155aea93dbfb4a7c032ca28",
+			"s": "0x6528c19cef3331271970db31f3f8ef68b61011d355558d43051e38141b40ae14",
+			"yParity": "0x0"
+		}))
+		.unwrap();
+		let from = rpc.from;
+		let transaction = TransactionV2::try_from(rpc).unwrap();
+
+		let mut header = mainnet_block_one();
+		header.base_fee_per_gas = Some(500_000_000.into());
+		header.withdrawals_root = Some(withdrawals_root(&[]));
+		header.blob_gas_used = Some(0);
+		header.excess_blob_gas = Some(0x20000);
+		header.parent_beacon_block_root = Some(H256::repeat_byte(1));
+		let block = Block {
+			header,
+			transactions: vec![transaction],
+			ommers: Vec::new(),
+			withdrawals: Some(Vec::new()),
+		};
+
+		let rpc = RpcBlock::with_transactions(&block, &[from]).unwrap();
+		let value = serde_json::to_value(&rpc).unwrap();
+		assert_eq!(value["baseFeePerGas"], "0x1dcd6500");
+		assert_eq!(value["excessBlobGas"], "0x20000");
+		assert_eq!(value["withdrawals"], json!([]));
+		assert_eq!(value["transactions"][0]["gasPrice"], "0x59682f00");
+		assert!(value.get("requestsHash").is_none());
+
+		let decoded: RpcBlock = serde_json::from_value(value).unwrap();
+		assert_eq!(decoded.into_block(Vec::new()), Ok(block.clone()));
+
+		assert_eq!(
+			RpcBlock::with_transactions(&block, &[]),
+			Err(RpcConversionError::SendersMismatch {
+				transactions: 1,
+				senders: 0,
+			})
+		);
+	}
+}
@@ -0,0 +1,460 @@
+//! Messages of the devp2p `snap` protocol, version 1.
+//!
+//! Messages are encoded without the devp2p message id and without snappy
+//! compression. Range responses can be checked against the requested root
+//! with [`AccountRange::verify`] and [`StorageRanges::verify`].
+
+use alloc::vec::Vec;
+
+use ethereum_types::{H256, U256};
+use rlp::{DecoderError, Rlp, RlpStream};
+
+use crate::{
+	account::{Account, EMPTY_CODE_HASH},
+	trie::{verify_range_proof, ProofError, EMPTY_TRIE_ROOT},
+	Bytes,
+};
+
+/// Message ids of the `snap` protocol, relative to the protocol offset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageId {
+	GetAccountRange = 0x00,
+	AccountRange = 0x01,
+	GetStorageRanges = 0x02,
+	StorageRanges = 0x03,
+	GetByteCodes = 0x04,
+	ByteCodes = 0x05,
+	GetTrieNodes = 0x06,
+	TrieNodes = 0x07,
+}
+
+impl TryFrom<u8> for MessageId {
+	type Error = DecoderError;
+
+	fn try_from(id: u8) -> Result<Self, Self::Error> {
+		Ok(match id {
+			0x00 => Self::GetAccountRange,
+			0x01 => Self::AccountRange,
+			0x02 => Self::GetStorageRanges,
+			0x03 => Self::StorageRanges,
+			0x04 => Self::GetByteCodes,
+			0x05 => Self::ByteCodes,
+			0x06 => Self::GetTrieNodes,
+			0x07 => Self::TrieNodes,
+			_ => return Err(DecoderError::Custom("unknown snap message id")),
+		})
+	}
+}
+
+/// Account in the slim encoding of the snap protocol.
+///
+/// The storage root and code hash are encoded as empty strings when they are
+/// the empty trie root and the empty code hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlimAccount(pub Account);
+
+impl rlp::Encodable for SlimAccount {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.0.nonce);
+		s.append(&self.0.balance);
+		if self.0.storage_root == EMPTY_TRIE_ROOT {
+			s.append_empty_data();
+		} else {
+			s.append(&self.0.storage_root);
+		}
+		if self.0.code_hash == EMPTY_CODE_HASH {
+			s.append_empty_data();
+		} else {
+			s.append(&self.0.code_hash);
+		}
+	}
+}
+
+impl rlp::Decodable for SlimAccount {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 4 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let hash_or = |index: usize, default: H256| -> Result<H256, DecoderError> {
+			let item = rlp.at(index)?;
+			if item.is_empty() {
+				Ok(default)
+			} else {
+				item.as_val()
+			}
+		};
+
+		Ok(Self(Account {
+			nonce: rlp.val_at::<U256>(0)?,
+			balance: rlp.val_at::<U256>(1)?,
+			storage_root: hash_or(2, EMPTY_TRIE_ROOT)?,
+			code_hash: hash_or(3, EMPTY_CODE_HASH)?,
+		}))
+	}
+}
+
+/// Request for the accounts of a state trie in a hash range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetAccountRange {
+	pub request_id: u64,
+	pub root_hash: H256,
+	pub starting_hash: H256,
+	pub limit_hash: H256,
+	/// Soft limit on the size of the response.
+	pub response_bytes: u64,
+}
+
+/// Account and the hash of its address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct AccountData {
+	pub hash: H256,
+	pub account: SlimAccount,
+}
+
+/// Response to [`GetAccountRange`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct AccountRange {
+	pub request_id: u64,
+	pub accounts: Vec<AccountData>,
+	/// Trie nodes proving the edges of the range.
+	pub proof: Vec<Bytes>,
+}
+
+impl AccountRange {
+	/// Verify that the accounts are all the accounts of the state trie at
+	/// `root` from `origin` up to the last returned account.
+	///
+	/// Returns whether the trie holds more accounts after the range.
+	pub fn verify(&self, root: H256, origin: H256) -> Result<bool, ProofError> {
+		let keys: Vec<_> = self.accounts.iter().map(|account| account.hash).collect();
+		let values: Vec<_> = self
+			.accounts
+			.iter()
+			.map(|account| rlp::encode(&account.account.0))
+			.collect();
+		let proof = (!self.proof.is_empty()).then_some(&self.proof[..]);
+
+		verify_range_proof(root, origin.as_bytes(), &keys, &values, proof)
+	}
+}
+
+/// Request for the storage slots of accounts in a hash range.
+///
+/// The range applies to the first account only, the storage of the other
+/// accounts is requested in full.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetStorageRanges {
+	pub request_id: u64,
+	pub root_hash: H256,
+	pub account_hashes: Vec<H256>,
+	/// Start of the range, empty for the first slot.
+	pub starting_hash: Bytes,
+	/// End of the range, empty for the last slot.
+	pub limit_hash: Bytes,
+	/// Soft limit on the size of the response.
+	pub response_bytes: u64,
+}
+
+/// Storage slot value and the hash of its key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct StorageData {
+	pub hash: H256,
+	/// RLP encoded slot value, as stored in the storage trie.
+	pub data: Bytes,
+}
+
+/// Response to [`GetStorageRanges`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageRanges {
+	pub request_id: u64,
+	/// Storage slots of each requested account, in order.
+	pub slots: Vec<Vec<StorageData>>,
+	/// Trie nodes proving the edges of the last slot range.
+	pub proof: Vec<Bytes>,
+}
+
+impl rlp::Encodable for StorageRanges {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(3);
+		s.append(&self.request_id);
+		s.begin_list(self.slots.len());
+		for slots in &self.slots {
+			s.append_list(slots);
+		}
+		s.append_list::<Bytes, _>(&self.proof);
+	}
+}
+
+impl rlp::Decodable for StorageRanges {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 3 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			request_id: rlp.val_at(0)?,
+			slots: rlp
+				.at(1)?
+				.iter()
+				.map(|slots| slots.as_list())
+				.collect::<Result<_, _>>()?,
+			proof: rlp.list_at(2)?,
+		})
+	}
+}
+
+impl StorageRanges {
+	/// Verify the slots against the storage roots of the requested accounts.
+	///
+	/// Every slot range but the last one must be complete, the last one is
+	/// checked against the proof if any. `origin` is the start of the range of
+	/// the first account. Returns whether the last account holds more slots
+	/// after the range.
+	pub fn verify(&self, roots: &[H256], origin: H256) -> Result<bool, ProofError> {
+		if self.slots.len() > roots.len() {
+			return Err(ProofError::LengthMismatch);
+		}
+
+		let mut more = false;
+		for (index, (slots, root)) in self.slots.iter().zip(roots).enumerate() {
+			let keys: Vec<_> = slots.iter().map(|slot| slot.hash).collect();
+			let values: Vec<_> = slots.iter().map(|slot| &slot.data).collect();
+
+			more = if index + 1 == self.slots.len() && !self.proof.is_empty() {
+				let origin = if index == 0 { origin } else { H256::zero() };
+				verify_range_proof(*root, origin.as_bytes(), &keys, &values, Some(&self.proof))?
+			} else {
+				verify_range_proof::<_, _, Bytes>(*root, &[], &keys, &values, None)?
+			};
+		}
+		Ok(more)
+	}
+}
+
+/// Request for contract code by hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetByteCodes {
+	pub request_id: u64,
+	pub hashes: Vec<H256>,
+	/// Soft limit on the size of the response.
+	pub response_bytes: u64,
+}
+
+/// Response to [`GetByteCodes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct ByteCodes {
+	pub request_id: u64,
+	pub codes: Vec<Bytes>,
+}
+
+/// Trie node paths below one account: the account path in the state trie,
+/// followed by storage trie paths, all in compact encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(rlp::RlpEncodableWrapper, rlp::RlpDecodableWrapper)]
+pub struct TrieNodePaths(pub Vec<Bytes>);
+
+/// Request for trie nodes by path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct GetTrieNodes {
+	pub request_id: u64,
+	pub root_hash: H256,
+	pub paths: Vec<TrieNodePaths>,
+	/// Soft limit on the size of the response.
+	pub response_bytes: u64,
+}
+
+/// Response to [`GetTrieNodes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+pub struct TrieNodes {
+	pub request_id: u64,
+	pub nodes: Vec<Bytes>,
+}
+
+/// Any message of the `snap` protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapMessage {
+	GetAccountRange(GetAccountRange),
+	AccountRange(AccountRange),
+	GetStorageRanges(GetStorageRanges),
+	StorageRanges(StorageRanges),
+	GetByteCodes(GetByteCodes),
+	ByteCodes(ByteCodes),
+	GetTrieNodes(GetTrieNodes),
+	TrieNodes(TrieNodes),
+}
+
+impl SnapMessage {
+	/// Id of the message.
+	#[must_use]
+	pub fn id(&self) -> MessageId {
+		match self {
+			Self::GetAccountRange(_) => MessageId::GetAccountRange,
+			Self::AccountRange(_) => MessageId::AccountRange,
+			Self::GetStorageRanges(_) => MessageId::GetStorageRanges,
+			Self::StorageRanges(_) => MessageId::StorageRanges,
+			Self::GetByteCodes(_) => MessageId::GetByteCodes,
+			Self::ByteCodes(_) => MessageId::ByteCodes,
+			Self::GetTrieNodes(_) => MessageId::GetTrieNodes,
+			Self::TrieNodes(_) => MessageId::TrieNodes,
+		}
+	}
+
+	/// RLP encoded message data, without the message id.
+	#[must_use]
+	pub fn encode(&self) -> Vec<u8> {
+		match self {
+			Self::GetAccountRange(m) => rlp::encode(m),
+			Self::AccountRange(m) => rlp::encode(m),
+			Self::GetStorageRanges(m) => rlp::encode(m),
+			Self::StorageRanges(m) => rlp::encode(m),
+			Self::GetByteCodes(m) => rlp::encode(m),
+			Self::ByteCodes(m) => rlp::encode(m),
+			Self::GetTrieNodes(m) => rlp::encode(m),
+			Self::TrieNodes(m) => rlp::encode(m),
+		}
+		.to_vec()
+	}
+
+	/// Decode the message data of a message with the given id.
+	pub fn decode(id: MessageId, data: &[u8]) -> Result<Self, DecoderError> {
+		Ok(match id {
+			MessageId::GetAccountRange => Self::GetAccountRange(rlp::decode(data)?),
+			MessageId::AccountRange => Self::AccountRange(rlp::decode(data)?),
+			MessageId::GetStorageRanges => Self::GetStorageRanges(rlp::decode(data)?),
+			MessageId::StorageRanges => Self::StorageRanges(rlp::decode(data)?),
+			MessageId::GetByteCodes => Self::GetByteCodes(rlp::decode(data)?),
+			MessageId::ByteCodes => Self::ByteCodes(rlp::decode(data)?),
+			MessageId::GetTrieNodes => Self::GetTrieNodes(rlp::decode(data)?),
+			MessageId::TrieNodes => Self::TrieNodes(rlp::decode(data)?),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::util::trie_root;
+	use sha3::{Digest, Keccak256};
+
+	fn round_trip(message: SnapMessage) {
+		let data = message.encode();
+		assert_eq!(SnapMessage::decode(message.id(), &data), Ok(message));
+	}
+
+	fn accounts() -> Vec<AccountData> {
+		let mut accounts: Vec<_> = (0..32_u64)
+			.map(|i| AccountData {
+				hash: H256::from_slice(&Keccak256::digest(i.to_be_bytes())),
+				account: SlimAccount(Account {
+					nonce: i.into(),
+					balance: (i * 1_000).into(),
+					storage_root: if i % 3 == 0 {
+						H256::repeat_byte(i as u8)
+					} else {
+						EMPTY_TRIE_ROOT
+					},
+					code_hash: EMPTY_CODE_HASH,
+				}),
+			})
+			.collect();
+		accounts.sort_by_key(|account| account.hash);
+		accounts
+	}
+
+	#[test]
+	fn slim_account() {
+		assert_eq!(H256::from_slice(&Keccak256::digest([])), EMPTY_CODE_HASH);
+
+		let account = SlimAccount(Account {
+			nonce: 1.into(),
+			balance: 2.into(),
+			storage_root: EMPTY_TRIE_ROOT,
+			code_hash: EMPTY_CODE_HASH,
+		});
+		let encoded = rlp::encode(&account);
+		assert_eq!(&encoded[..], &[0xc4, 0x01, 0x02, 0x80, 0x80]);
+		assert_eq!(rlp::decode::<SlimAccount>(&encoded), Ok(account.clone()));
+
+		let full = rlp::encode(&account.0);
+		assert!(rlp::decode::<SlimAccount>(&full).is_ok());
+	}
+
+	#[test]
+	fn verify_complete_ranges() {
+		let accounts = accounts();
+		let root = trie_root(
+			accounts
+				.iter()
+				.map(|account| (account.hash, rlp::encode(&account.account.0))),
+		);
+
+		let mut range = AccountRange {
+			request_id: 1,
+			accounts,
+			proof: Vec::new(),
+		};
+		assert_eq!(range.verify(root, H256::zero()), Ok(false));
+		round_trip(SnapMessage::AccountRange(range.clone()));
+
+		range.accounts[3].account.0.balance += U256::one();
+		assert!(matches!(
+			range.verify(root, H256::zero()),
+			Err(ProofError::RootMismatch { .. })
+		));
+
+		let slots: Vec<_> = (1..4_u8)
+			.map(|i| StorageData {
+				hash: H256::repeat_byte(i),
+				data: rlp::encode(&i).to_vec(),
+			})
+			.collect();
+		let storage_root = trie_root(slots.iter().map(|slot| (slot.hash, slot.data.clone())));
+		let ranges = StorageRanges {
+			request_id: 2,
+			slots: vec![slots, Vec::new()],
+			proof: Vec::new(),
+		};
+		assert_eq!(
+			ranges.verify(&[storage_root, EMPTY_TRIE_ROOT], H256::zero()),
+			Ok(false)
+		);
+		round_trip(SnapMessage::StorageRanges(ranges));
+	}
+
+	#[test]
+	fn message_round_trips() {
+		round_trip(SnapMessage::GetStorageRanges(GetStorageRanges {
+			request_id: 3,
+			root_hash: H256::repeat_byte(1),
+			account_hashes: vec![H256::repeat_byte(2)],
+			starting_hash: Vec::new(),
+			limit_hash: vec![0xff; 32],
+			response_bytes: 512 * 1024,
+		}));
+		round_trip(SnapMessage::GetTrieNodes(GetTrieNodes {
+			request_id: 4,
+			root_hash: H256::repeat_byte(1),
+			paths: vec![
+				TrieNodePaths(vec![vec![0x01]]),
+				TrieNodePaths(vec![vec![0x02], vec![0x13, 0x37]]),
+			],
+			response_bytes: 1024,
+		}));
+		round_trip(SnapMessage::ByteCodes(ByteCodes {
+			request_id: 5,
+			codes: vec![vec![0x60, 0x00], Vec::new()],
+		}));
+	}
+}
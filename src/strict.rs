@@ -0,0 +1,156 @@
+//! Strict decoding, accepting only canonical encodings.
+//!
+//! The regular decoders are lenient: among others, they accept legacy items
+//! wrapped in byte strings. The strict decoders below accept an input only if
+//! it is exactly the encoding of the decoded value, so that decoding then
+//! encoding is byte-identical.
+
+use rlp::{Decodable, DecoderError, Encodable};
+
+use crate::enveloped::{EnvelopedDecodable, EnvelopedDecoderError, EnvelopedEncodable};
+
+const NON_CANONICAL: DecoderError = DecoderError::Custom("non-canonical encoding");
+
+/// Decode an RLP value, rejecting anything but its canonical encoding.
+pub fn decode_strict<T: Decodable + Encodable>(bytes: &[u8]) -> Result<T, DecoderError> {
+	let value: T = rlp::decode(bytes)?;
+	if rlp::encode(&value)[..] != *bytes {
+		return Err(NON_CANONICAL);
+	}
+
+	Ok(value)
+}
+
+/// Decode an enveloped item, rejecting anything but its canonical encoding.
+pub fn decode_enveloped_strict<T>(
+	bytes: &[u8],
+) -> Result<T, EnvelopedDecoderError<T::PayloadDecoderError>>
+where
+	T: EnvelopedDecodable + EnvelopedEncodable,
+	T::PayloadDecoderError: From<DecoderError>,
+{
+	let value = T::decode(bytes)?;
+	if value.encode()[..] != *bytes {
+		return Err(EnvelopedDecoderError::Payload(NON_CANONICAL.into()));
+	}
+
+	Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::{
+		block::Block,
+		header::PartialHeader,
+		receipt::{EIP658ReceiptData, ReceiptAny},
+		transaction::{
+			EIP1559Transaction, LegacyTransaction, TransactionAction, TransactionAny,
+			TransactionSignature,
+		},
+	};
+	use ethereum_types::{H160, H256, U256};
+	use rlp::RlpStream;
+
+	fn legacy() -> LegacyTransaction {
+		LegacyTransaction {
+			nonce: 1.into(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Call(H160::repeat_byte(1)),
+			value: 1.into(),
+			input: Vec::new(),
+			signature: TransactionSignature::new(27, H256::repeat_byte(2), H256::repeat_byte(3))
+				.unwrap(),
+		}
+	}
+
+	#[test]
+	fn reject_trailing_data() {
+		let tx = TransactionAny::EIP1559(EIP1559Transaction {
+			chain_id: 1,
+			nonce: 1.into(),
+			max_priority_fee_per_gas: 1.into(),
+			max_fee_per_gas: 2.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: Vec::new(),
+			access_list: Vec::new(),
+			odd_y_parity: false,
+			r: H256::repeat_byte(2),
+			s: H256::repeat_byte(3),
+		});
+		let mut bytes = EnvelopedEncodable::encode(&tx).to_vec();
+		assert_eq!(
+			decode_enveloped_strict::<TransactionAny>(&bytes),
+			Ok(tx.clone())
+		);
+
+		bytes.push(0x00);
+		assert!(decode_enveloped_strict::<TransactionAny>(&bytes).is_err());
+
+		let receipt = ReceiptAny::EIP658(EIP658ReceiptData::new(1, 21_000.into(), Vec::new()));
+		let mut bytes = EnvelopedEncodable::encode(&receipt).to_vec();
+		assert_eq!(decode_enveloped_strict::<ReceiptAny>(&bytes), Ok(receipt));
+		bytes.extend_from_slice(&[0xc0]);
+		assert!(decode_enveloped_strict::<ReceiptAny>(&bytes).is_err());
+
+		let mut bytes = rlp::encode(&legacy()).to_vec();
+		assert_eq!(decode_strict::<LegacyTransaction>(&bytes), Ok(legacy()));
+		bytes.push(0x80);
+		assert!(decode_strict::<LegacyTransaction>(&bytes).is_err());
+	}
+
+	#[test]
+	fn reject_non_canonical_block() {
+		let block: Block<TransactionAny> = Block::new(
+			PartialHeader {
+				gas_used: 21_000.into(),
+				..partial_header()
+			},
+			vec![TransactionAny::Legacy(legacy())],
+			Vec::new(),
+		);
+		let bytes = rlp::encode(&block);
+		assert_eq!(
+			decode_strict::<Block<TransactionAny>>(&bytes),
+			Ok(block.clone())
+		);
+
+		// Legacy transaction wrapped in a byte string.
+		let mut s = RlpStream::new_list(3);
+		s.append(&block.header);
+		s.begin_list(1);
+		s.append(&rlp::encode(&legacy()).as_ref());
+		s.begin_list(0);
+		let bytes = s.out();
+		assert_eq!(
+			rlp::decode::<Block<TransactionAny>>(&bytes),
+			Ok(block.clone())
+		);
+		assert_eq!(
+			decode_strict::<Block<TransactionAny>>(&bytes),
+			Err(NON_CANONICAL)
+		);
+
+		// Unknown trailing block field, after the withdrawals.
+		let mut s = RlpStream::new_list(5);
+		s.append(&block.header);
+		s.begin_list(1);
+		s.append(&legacy());
+		s.begin_list(0);
+		s.begin_list(0);
+		s.begin_list(0);
+		let bytes = s.out();
+		assert_eq!(
+			rlp::decode::<Block<TransactionAny>>(&bytes),
+			Err(DecoderError::RlpIncorrectListLen)
+		);
+		assert_eq!(
+			decode_strict::<Block<TransactionAny>>(&bytes),
+			Err(DecoderError::RlpIncorrectListLen)
+		);
+	}
+}
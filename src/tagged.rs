@@ -0,0 +1,271 @@
+//! Tagged serde representation of the transaction and receipt enums.
+//!
+//! The enums themselves serialize untagged, which is ambiguous whenever two
+//! variants share a shape. Wrapping them in [`Tagged`] adds a `type` field
+//! holding the variant name, so every variant round-trips losslessly.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+	receipt::{
+		EIP1559ReceiptData, EIP2930ReceiptData, EIP658ReceiptData, FrontierReceiptData, ReceiptAny,
+		ReceiptV2, ReceiptV3,
+	},
+	transaction::{
+		EIP1559Transaction, EIP2930Transaction, LegacyTransaction, TransactionV1, TransactionV2,
+	},
+};
+
+/// Wrapper serializing a transaction or receipt enum with a `type` field.
+///
+/// ```json
+/// { "type": "EIP1559", "chain_id": 1, "nonce": "0x0", ... }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tagged<T>(pub T);
+
+impl<T> Tagged<T> {
+	/// Unwrap the inner value.
+	#[must_use]
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T> From<T> for Tagged<T> {
+	fn from(value: T) -> Self {
+		Self(value)
+	}
+}
+
+macro_rules! impl_tagged {
+	($name:ident { $($variant:ident($inner:ty)),+ $(,)? }) => {
+		const _: () = {
+			#[derive(Serialize)]
+			#[serde(tag = "type")]
+			enum Ref<'a> {
+				$($variant(&'a $inner)),+
+			}
+
+			#[derive(Deserialize)]
+			#[serde(tag = "type")]
+			enum Owned {
+				$($variant($inner)),+
+			}
+
+			impl Serialize for Tagged<$name> {
+				fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+					match &self.0 {
+						$($name::$variant(inner) => Ref::$variant(inner)),+
+					}
+					.serialize(serializer)
+				}
+			}
+
+			impl<'de> Deserialize<'de> for Tagged<$name> {
+				fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+					Ok(Tagged(match Owned::deserialize(deserializer)? {
+						$(Owned::$variant(inner) => $name::$variant(inner)),+
+					}))
+				}
+			}
+		};
+	};
+}
+
+impl_tagged!(TransactionV1 {
+	Legacy(LegacyTransaction),
+	EIP2930(EIP2930Transaction),
+});
+
+impl_tagged!(TransactionV2 {
+	Legacy(LegacyTransaction),
+	EIP2930(EIP2930Transaction),
+	EIP1559(EIP1559Transaction),
+});
+
+impl_tagged!(ReceiptV2 {
+	Legacy(EIP658ReceiptData),
+	EIP2930(EIP2930ReceiptData),
+});
+
+impl_tagged!(ReceiptV3 {
+	Legacy(EIP658ReceiptData),
+	EIP2930(EIP2930ReceiptData),
+	EIP1559(EIP1559ReceiptData),
+});
+
+impl_tagged!(ReceiptAny {
+	Frontier(FrontierReceiptData),
+	EIP658(EIP658ReceiptData),
+	EIP2930(EIP2930ReceiptData),
+	EIP1559(EIP1559ReceiptData),
+});
+
+#[cfg(feature = "optimism")]
+mod optimism {
+	use super::*;
+	use crate::optimism::{DepositReceiptData, DepositTransaction, OpReceipt, OpTransaction};
+
+	impl_tagged!(OpTransaction {
+		Legacy(LegacyTransaction),
+		EIP2930(EIP2930Transaction),
+		EIP1559(EIP1559Transaction),
+		Deposit(DepositTransaction),
+	});
+
+	impl_tagged!(OpReceipt {
+		Legacy(EIP658ReceiptData),
+		EIP2930(EIP2930ReceiptData),
+		EIP1559(EIP1559ReceiptData),
+		Deposit(DepositReceiptData),
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::transaction::{AccessListItem, TransactionAction, TransactionSignature};
+	use ethereum_types::{H160, H256, U256};
+
+	fn round_trip<T>(value: T) -> serde_json::Value
+	where
+		Tagged<T>: Serialize + for<'de> Deserialize<'de> + PartialEq + core::fmt::Debug,
+	{
+		let tagged = Tagged(value);
+		let json = serde_json::to_value(&tagged).unwrap();
+		assert_eq!(
+			serde_json::from_value::<Tagged<T>>(json.clone()).unwrap(),
+			tagged
+		);
+		json
+	}
+
+	fn legacy() -> LegacyTransaction {
+		LegacyTransaction {
+			nonce: 1.into(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Call(H160::repeat_byte(1)),
+			value: 1.into(),
+			input: Vec::new(),
+			signature: TransactionSignature::new(37, H256::repeat_byte(2), H256::repeat_byte(3))
+				.unwrap(),
+		}
+	}
+
+	#[test]
+	fn legacy_transaction() {
+		let json = round_trip(TransactionV2::Legacy(legacy()));
+		assert_eq!(json["type"], "Legacy");
+		assert_eq!(
+			serde_json::from_value::<Tagged<TransactionV1>>(json)
+				.unwrap()
+				.into_inner(),
+			TransactionV1::Legacy(legacy())
+		);
+	}
+
+	#[test]
+	fn transaction_v1() {
+		assert_eq!(
+			round_trip(TransactionV1::Legacy(legacy()))["type"],
+			"Legacy"
+		);
+
+		let transaction = TransactionV1::EIP2930(EIP2930Transaction {
+			chain_id: 1,
+			nonce: U256::zero(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: Vec::new(),
+			access_list: Vec::new(),
+			odd_y_parity: false,
+			r: H256::repeat_byte(2),
+			s: H256::repeat_byte(3),
+		});
+		let mut json = round_trip(transaction);
+		assert_eq!(json["type"], "EIP2930");
+
+		json["type"] = "EIP1559".into();
+		assert!(serde_json::from_value::<Tagged<TransactionV1>>(json).is_err());
+	}
+
+	#[test]
+	fn receipt_v2() {
+		let data = EIP658ReceiptData::new(1, 21_000.into(), Vec::new());
+		assert_eq!(
+			round_trip(ReceiptV2::Legacy(data.clone()))["type"],
+			"Legacy"
+		);
+		assert_eq!(
+			round_trip(ReceiptV2::EIP2930(data.clone()))["type"],
+			"EIP2930"
+		);
+
+		// Untagged, the typed receipt is read back as a legacy one.
+		let json = serde_json::to_value(ReceiptV2::EIP2930(data.clone())).unwrap();
+		assert_eq!(
+			serde_json::from_value::<ReceiptV2>(json).unwrap(),
+			ReceiptV2::Legacy(data)
+		);
+	}
+
+	#[test]
+	fn transaction_without_access_list() {
+		let transaction = TransactionV2::EIP2930(EIP2930Transaction {
+			chain_id: 1,
+			nonce: U256::zero(),
+			gas_price: 1.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Call(H160::repeat_byte(1)),
+			value: U256::zero(),
+			input: Vec::new(),
+			access_list: Vec::new(),
+			odd_y_parity: false,
+			r: H256::repeat_byte(2),
+			s: H256::repeat_byte(3),
+		});
+		assert_eq!(round_trip(transaction)["type"], "EIP2930");
+
+		let transaction = TransactionV2::EIP1559(EIP1559Transaction {
+			chain_id: 1,
+			nonce: U256::zero(),
+			max_priority_fee_per_gas: 1.into(),
+			max_fee_per_gas: 2.into(),
+			gas_limit: 21_000.into(),
+			action: TransactionAction::Create,
+			value: U256::zero(),
+			input: vec![0x60],
+			access_list: vec![AccessListItem {
+				address: H160::repeat_byte(4),
+				storage_keys: vec![H256::zero()],
+			}],
+			odd_y_parity: true,
+			r: H256::repeat_byte(2),
+			s: H256::repeat_byte(3),
+		});
+		assert_eq!(round_trip(transaction)["type"], "EIP1559");
+	}
+
+	#[test]
+	fn receipts_with_same_shape() {
+		let data = EIP658ReceiptData::new(1, 21_000.into(), Vec::new());
+
+		for receipt in [
+			ReceiptAny::EIP658(data.clone()),
+			ReceiptAny::EIP2930(data.clone()),
+			ReceiptAny::EIP1559(data.clone()),
+		] {
+			round_trip(receipt);
+		}
+		round_trip(ReceiptAny::Frontier(FrontierReceiptData::new(
+			H256::repeat_byte(5),
+			21_000.into(),
+			Vec::new(),
+		)));
+		assert_eq!(round_trip(ReceiptV3::EIP2930(data))["type"], "EIP2930");
+	}
+}
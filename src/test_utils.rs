@@ -0,0 +1,61 @@
+//! Fixtures shared by the unit tests.
+
+use ethereum_types::{Bloom, H160, H256, H64, U256};
+use hex_literal::hex;
+
+use crate::header::{Header, PartialHeader};
+
+/// Partial header of an empty block 1 with a 30M gas limit.
+pub fn partial_header() -> PartialHeader {
+	PartialHeader {
+		parent_hash: H256::zero(),
+		beneficiary: H160::zero(),
+		state_root: H256::zero(),
+		receipts_root: H256::zero(),
+		logs_bloom: Bloom::zero(),
+		difficulty: U256::zero(),
+		number: U256::one(),
+		gas_limit: 30_000_000.into(),
+		gas_used: U256::zero(),
+		timestamp: 1,
+		extra_data: Vec::new(),
+		mix_hash: H256::zero(),
+		nonce: H64::zero(),
+		base_fee_per_gas: None,
+		blob_gas_used: None,
+		excess_blob_gas: None,
+		parent_beacon_block_root: None,
+		requests_hash: None,
+	}
+}
+
+/// Header of Ethereum mainnet block 1.
+pub fn mainnet_block_one() -> Header {
+	Header {
+		parent_hash: hex!("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3")
+			.into(),
+		ommers_hash: hex!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")
+			.into(),
+		beneficiary: hex!("05a56e2d52c817161883f50c441c3228cfe54d9f").into(),
+		state_root: hex!("d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3").into(),
+		transactions_root: hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+			.into(),
+		receipts_root: hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+			.into(),
+		logs_bloom: Bloom::zero(),
+		difficulty: 17_171_480_576_u64.into(),
+		number: 1.into(),
+		gas_limit: 5000.into(),
+		gas_used: U256::zero(),
+		timestamp: 1_438_269_988,
+		extra_data: hex!("476574682f76312e302e302f6c696e75782f676f312e342e32").to_vec(),
+		mix_hash: hex!("969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59").into(),
+		nonce: hex!("539bd4979fef1ec4").into(),
+		base_fee_per_gas: None,
+		withdrawals_root: None,
+		blob_gas_used: None,
+		excess_blob_gas: None,
+		parent_beacon_block_root: None,
+		requests_hash: None,
+	}
+}
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use ethereum_types::{H256, U256};
 use rlp::{DecoderError, Rlp, RlpStream};
 use sha3::{Digest, Keccak256};
@@ -30,10 +32,9 @@ pub struct EIP1559Transaction {
 
 impl EIP1559Transaction {
 	pub fn hash(&self) -> H256 {
-		let encoded = rlp::encode(self);
-		let mut out = alloc::vec![0; 1 + encoded.len()];
-		out[0] = 2;
-		out[1..].copy_from_slice(&encoded);
+		let mut out = Vec::with_capacity(1 + self.encoded_len());
+		out.push(2);
+		self.encode_to(&mut out);
 		H256::from_slice(Keccak256::digest(&out).as_slice())
 	}
 
@@ -58,10 +58,9 @@ pub struct EIP2930Transaction {
 
 impl EIP2930Transaction {
 	pub fn hash(&self) -> H256 {
-		let encoded = rlp::encode(self);
-		let mut out = alloc::vec![0; 1 + encoded.len()];
-		out[0] = 1;
-		out[1..].copy_from_slice(&encoded);
+		let mut out = Vec::with_capacity(1 + self.encoded_len());
+		out.push(1);
+		self.encode_to(&mut out);
 		H256::from_slice(Keccak256::digest(&out).as_slice())
 	}
 
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use core::ops::Deref;
 
 use ethereum_types::{H160, H256, U256};
@@ -186,7 +187,9 @@ pub struct LegacyTransaction {
 
 impl LegacyTransaction {
 	pub fn hash(&self) -> H256 {
-		H256::from_slice(Keccak256::digest(&rlp::encode(self)).as_slice())
+		let mut out = Vec::with_capacity(self.encoded_len());
+		self.encode_to(&mut out);
+		H256::from_slice(Keccak256::digest(&out).as_slice())
 	}
 
 	pub fn to_message(self) -> LegacyTransactionMessage {
@@ -262,7 +265,7 @@ pub struct LegacyTransactionMessage {
 
 impl LegacyTransactionMessage {
 	pub fn hash(&self) -> H256 {
-		H256::from_slice(Keccak256::digest(&rlp::encode(self)).as_slice())
+		H256::from_slice(Keccak256::digest(rlp::encode(self)).as_slice())
 	}
 }
 
@@ -2,7 +2,7 @@ mod eip1559;
 mod eip2930;
 mod legacy;
 
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 use ethereum_types::H256;
 use rlp::{DecoderError, Rlp};
 
@@ -25,6 +25,12 @@ impl EnvelopedEncodable for TransactionV0 {
 	fn encode_payload(&self) -> BytesMut {
 		rlp::encode(self)
 	}
+	fn payload_len(&self) -> usize {
+		self.encoded_len()
+	}
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		self.encode_to(out);
+	}
 }
 
 impl EnvelopedDecodable for TransactionV0 {
@@ -75,6 +81,20 @@ impl EnvelopedEncodable for TransactionV1 {
 			Self::EIP2930(tx) => rlp::encode(tx),
 		}
 	}
+
+	fn payload_len(&self) -> usize {
+		match self {
+			Self::Legacy(tx) => tx.encoded_len(),
+			Self::EIP2930(tx) => tx.encoded_len(),
+		}
+	}
+
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		match self {
+			Self::Legacy(tx) => tx.encode_to(out),
+			Self::EIP2930(tx) => tx.encode_to(out),
+		}
+	}
 }
 
 impl EnvelopedDecodable for TransactionV1 {
@@ -147,6 +167,22 @@ impl EnvelopedEncodable for TransactionV2 {
 			Self::EIP1559(tx) => rlp::encode(tx),
 		}
 	}
+
+	fn payload_len(&self) -> usize {
+		match self {
+			Self::Legacy(tx) => tx.encoded_len(),
+			Self::EIP2930(tx) => tx.encoded_len(),
+			Self::EIP1559(tx) => tx.encoded_len(),
+		}
+	}
+
+	fn encode_payload_to(&self, out: &mut impl BufMut) {
+		match self {
+			Self::Legacy(tx) => tx.encode_to(out),
+			Self::EIP2930(tx) => tx.encode_to(out),
+			Self::EIP1559(tx) => tx.encode_to(out),
+		}
+	}
 }
 
 impl EnvelopedDecodable for TransactionV2 {
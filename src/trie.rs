@@ -0,0 +1,752 @@
+//! Merkle proof verification for the Ethereum Merkle Patricia trie.
+//!
+//! Proofs are given as the RLP encoded trie nodes on the path from the root
+//! to the proven key, in any order.
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::cmp::Ordering;
+
+use ethereum_types::H256;
+use rlp::{Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+use crate::util::{hex_prefix_encode, trie_root};
+
+/// Root hash of the empty trie.
+pub const EMPTY_TRIE_ROOT: H256 = H256([
+	0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+	0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Nibble terminating the path of a leaf.
+const TERMINATOR: u8 = 16;
+
+/// Error returned when a proof does not verify.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProofError {
+	/// Node needed to walk the path is not part of the proof.
+	MissingNode(H256),
+	/// Proof node is not a valid trie node.
+	InvalidNode,
+	/// Number of keys and values differ.
+	LengthMismatch,
+	/// Keys are not strictly increasing.
+	NotMonotonic,
+	/// Range contains an empty value.
+	EmptyValue,
+	/// First key of the range is before the origin.
+	KeyBeforeOrigin,
+	/// Edge keys are not ordered or have different lengths.
+	InvalidEdgeKeys,
+	/// Edge proofs do not enclose any part of the trie.
+	EmptyRange,
+	/// Proven key or value differs from the given one.
+	ValueMismatch,
+	/// Trie holds entries after the origin although the range is empty.
+	MoreEntries,
+	/// Root hash rebuilt from the range differs from the expected one.
+	RootMismatch { expected: H256, computed: H256 },
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+	Short { key: Vec<u8>, val: Box<Node> },
+	Full(Box<[Option<Node>; 17]>),
+	Hash(H256),
+	Value(Vec<u8>),
+}
+
+fn keccak(data: &[u8]) -> H256 {
+	H256::from_slice(Keccak256::digest(data).as_slice())
+}
+
+fn key_to_nibbles(key: &[u8]) -> Vec<u8> {
+	let mut nibbles = Vec::with_capacity(key.len() * 2 + 1);
+	for byte in key {
+		nibbles.push(byte >> 4);
+		nibbles.push(byte & 0x0f);
+	}
+	nibbles.push(TERMINATOR);
+	nibbles
+}
+
+fn compact_to_nibbles(compact: &[u8]) -> Result<Vec<u8>, ProofError> {
+	let (&flags, rest) = compact.split_first().ok_or(ProofError::InvalidNode)?;
+	let (leaf, odd) = match flags >> 4 {
+		0 => (false, false),
+		1 => (false, true),
+		2 => (true, false),
+		3 => (true, true),
+		_ => return Err(ProofError::InvalidNode),
+	};
+
+	let mut nibbles = Vec::with_capacity(rest.len() * 2 + 2);
+	if odd {
+		nibbles.push(flags & 0x0f);
+	}
+	for byte in rest {
+		nibbles.push(byte >> 4);
+		nibbles.push(byte & 0x0f);
+	}
+	if leaf {
+		nibbles.push(TERMINATOR);
+	}
+	Ok(nibbles)
+}
+
+fn decode_node(rlp: &Rlp) -> Result<Node, ProofError> {
+	match rlp.item_count().map_err(|_| ProofError::InvalidNode)? {
+		2 => {
+			let key = compact_to_nibbles(
+				rlp.at(0)
+					.and_then(|key| key.data())
+					.map_err(|_| ProofError::InvalidNode)?,
+			)?;
+			let val = if key.last() == Some(&TERMINATOR) {
+				Node::Value(rlp.val_at(1).map_err(|_| ProofError::InvalidNode)?)
+			} else {
+				decode_ref(&rlp.at(1).map_err(|_| ProofError::InvalidNode)?)?
+					.ok_or(ProofError::InvalidNode)?
+			};
+			Ok(Node::Short {
+				key,
+				val: Box::new(val),
+			})
+		}
+		17 => {
+			let mut children: [Option<Node>; 17] = Default::default();
+			for (i, child) in children.iter_mut().enumerate().take(16) {
+				*child = decode_ref(&rlp.at(i).map_err(|_| ProofError::InvalidNode)?)?;
+			}
+			let value: Vec<u8> = rlp.val_at(16).map_err(|_| ProofError::InvalidNode)?;
+			if !value.is_empty() {
+				children[16] = Some(Node::Value(value));
+			}
+			Ok(Node::Full(Box::new(children)))
+		}
+		_ => Err(ProofError::InvalidNode),
+	}
+}
+
+fn decode_ref(rlp: &Rlp) -> Result<Option<Node>, ProofError> {
+	if rlp.is_list() {
+		if rlp.as_raw().len() >= 32 {
+			return Err(ProofError::InvalidNode);
+		}
+		return decode_node(rlp).map(Some);
+	}
+
+	match rlp.data().map_err(|_| ProofError::InvalidNode)? {
+		[] => Ok(None),
+		hash if hash.len() == 32 => Ok(Some(Node::Hash(H256::from_slice(hash)))),
+		_ => Err(ProofError::InvalidNode),
+	}
+}
+
+fn encode_node(node: &Node) -> Vec<u8> {
+	let mut s = RlpStream::new();
+	match node {
+		Node::Short { key, val } => {
+			s.begin_list(2);
+			match key.split_last() {
+				Some((&TERMINATOR, path)) => s.append_iter(hex_prefix_encode(path, true)),
+				_ => s.append_iter(hex_prefix_encode(key, false)),
+			};
+			append_ref(&mut s, Some(val));
+		}
+		Node::Full(children) => {
+			s.begin_list(17);
+			for child in children.iter() {
+				append_ref(&mut s, child.as_ref());
+			}
+		}
+		Node::Hash(hash) => {
+			s.append(hash);
+		}
+		Node::Value(value) => {
+			s.append(value);
+		}
+	}
+	s.out().to_vec()
+}
+
+fn append_ref(s: &mut RlpStream, node: Option<&Node>) {
+	match node {
+		None => {
+			s.append_empty_data();
+		}
+		Some(Node::Hash(hash)) => {
+			s.append(hash);
+		}
+		Some(Node::Value(value)) => {
+			s.append(value);
+		}
+		Some(node) => {
+			let encoded = encode_node(node);
+			if encoded.len() < 32 {
+				s.append_raw(&encoded, 1);
+			} else {
+				s.append(&keccak(&encoded));
+			}
+		}
+	}
+}
+
+fn root_hash(root: Option<&Node>) -> H256 {
+	match root {
+		None => EMPTY_TRIE_ROOT,
+		Some(Node::Hash(hash)) => *hash,
+		Some(node) => keccak(&encode_node(node)),
+	}
+}
+
+struct ProofDb<'a>(BTreeMap<H256, &'a [u8]>);
+
+impl<'a> ProofDb<'a> {
+	fn new<P: AsRef<[u8]>>(proof: &'a [P]) -> Self {
+		Self(
+			proof
+				.iter()
+				.map(|node| (keccak(node.as_ref()), node.as_ref()))
+				.collect(),
+		)
+	}
+
+	fn resolve(&self, hash: H256) -> Result<Node, ProofError> {
+		let encoded = self.0.get(&hash).ok_or(ProofError::MissingNode(hash))?;
+		decode_node(&Rlp::new(encoded))
+	}
+}
+
+/// Resolve the hashed nodes on the path of `key` below `node`, returning the
+/// value at the end of the path if the key is in the trie.
+fn resolve_path(node: &mut Node, key: &[u8], db: &ProofDb) -> Result<Option<Vec<u8>>, ProofError> {
+	let (child, rest) = match node {
+		Node::Short { key: prefix, val } => {
+			if !key.starts_with(prefix) {
+				return Ok(None);
+			}
+			(val.as_mut(), &key[prefix.len()..])
+		}
+		Node::Full(children) => {
+			let (&index, rest) = key.split_first().ok_or(ProofError::InvalidNode)?;
+			match children[usize::from(index)].as_mut() {
+				Some(child) => (child, rest),
+				None => return Ok(None),
+			}
+		}
+		Node::Value(value) => return Ok(Some(value.clone())),
+		Node::Hash(_) => return Err(ProofError::InvalidNode),
+	};
+
+	if let Node::Hash(hash) = child {
+		*child = db.resolve(*hash)?;
+	}
+	if let Node::Value(value) = child {
+		return Ok(Some(value.clone()));
+	}
+	resolve_path(child, rest, db)
+}
+
+fn compare_prefix(key: &[u8], prefix: &[u8]) -> Ordering {
+	key[..key.len().min(prefix.len())].cmp(prefix)
+}
+
+/// Remove the references strictly between the `left` and `right` paths below
+/// `node`. Returns whether `node` itself has to be removed from its parent.
+fn unset_internal(node: &mut Node, left: &[u8], right: &[u8]) -> Result<bool, ProofError> {
+	match node {
+		Node::Short { key, val } => {
+			let fork_left = compare_prefix(left, key);
+			let fork_right = compare_prefix(right, key);
+
+			match (fork_left, fork_right) {
+				(Ordering::Equal, Ordering::Equal) => {
+					let len = key.len();
+					if unset_internal(val, &left[len..], &right[len..])? {
+						return Err(ProofError::InvalidNode);
+					}
+					Ok(false)
+				}
+				(Ordering::Less, Ordering::Less) | (Ordering::Greater, Ordering::Greater) => {
+					Err(ProofError::EmptyRange)
+				}
+				(Ordering::Equal, _) => match val.as_mut() {
+					Node::Value(_) => Ok(true),
+					val => {
+						let len = key.len();
+						if unset(val, &left[len..], false)? {
+							return Err(ProofError::InvalidNode);
+						}
+						Ok(false)
+					}
+				},
+				(_, Ordering::Equal) => match val.as_mut() {
+					Node::Value(_) => Ok(true),
+					val => {
+						let len = key.len();
+						if unset(val, &right[len..], true)? {
+							return Err(ProofError::InvalidNode);
+						}
+						Ok(false)
+					}
+				},
+				_ => Ok(true),
+			}
+		}
+		Node::Full(children) => {
+			let (l, r) = (usize::from(left[0]), usize::from(right[0]));
+			if l == r {
+				if let Some(child) = children[l].as_mut() {
+					if unset_internal(child, &left[1..], &right[1..])? {
+						children[l] = None;
+					}
+				}
+				return Ok(false);
+			}
+
+			for child in &mut children[l + 1..r] {
+				*child = None;
+			}
+			for (index, key, remove_left) in [(l, left, false), (r, right, true)] {
+				if let Some(child) = children[index].as_mut() {
+					if unset(child, &key[1..], remove_left)? {
+						children[index] = None;
+					}
+				}
+			}
+			Ok(false)
+		}
+		Node::Hash(_) | Node::Value(_) => Err(ProofError::InvalidNode),
+	}
+}
+
+/// Remove the references on one side of the path of `key` below `node`: on
+/// the left of the path if `remove_left`, on the right otherwise. Returns
+/// whether `node` itself has to be removed from its parent.
+fn unset(node: &mut Node, key: &[u8], remove_left: bool) -> Result<bool, ProofError> {
+	match node {
+		Node::Full(children) => {
+			let index = usize::from(key[0]);
+			let range = if remove_left {
+				0..index
+			} else {
+				(index + 1).min(16)..16
+			};
+			for child in &mut children[range] {
+				*child = None;
+			}
+
+			if let Some(child) = children[index].as_mut() {
+				if unset(child, &key[1..], remove_left)? {
+					children[index] = None;
+				}
+			}
+			Ok(false)
+		}
+		Node::Short { key: prefix, val } => {
+			if !key.starts_with(prefix) {
+				// The path leaves the trie here. The node is inside the range
+				// if it lies on the inner side of the path.
+				return Ok(if remove_left {
+					prefix.as_slice() < key
+				} else {
+					prefix.as_slice() > key
+				});
+			}
+
+			match val.as_mut() {
+				Node::Value(_) => Ok(true),
+				val => {
+					if unset(val, &key[prefix.len()..], remove_left)? {
+						return Err(ProofError::InvalidNode);
+					}
+					Ok(false)
+				}
+			}
+		}
+		Node::Hash(_) | Node::Value(_) => Err(ProofError::InvalidNode),
+	}
+}
+
+/// Whether the trie holds an entry after the path of `key`.
+fn has_right_element(mut node: Option<&Node>, key: &[u8]) -> bool {
+	let mut key = key;
+	while let Some(current) = node {
+		match current {
+			Node::Full(children) => {
+				let index = usize::from(key[0]);
+				if children
+					.iter()
+					.take(16)
+					.skip(index + 1)
+					.any(Option::is_some)
+				{
+					return true;
+				}
+				node = children[index].as_ref();
+				key = &key[1..];
+			}
+			Node::Short { key: prefix, val } => {
+				if !key.starts_with(prefix) {
+					return prefix.as_slice() > key;
+				}
+				node = Some(val);
+				key = &key[prefix.len()..];
+			}
+			Node::Value(_) | Node::Hash(_) => return false,
+		}
+	}
+	false
+}
+
+fn insert(node: Option<Node>, key: &[u8], value: Node) -> Result<Node, ProofError> {
+	if key.is_empty() {
+		return Ok(value);
+	}
+
+	match node {
+		None => Ok(Node::Short {
+			key: key.to_vec(),
+			val: Box::new(value),
+		}),
+		Some(Node::Short { key: prefix, val }) => {
+			let matched = prefix.iter().zip(key).take_while(|(a, b)| a == b).count();
+			if matched == prefix.len() {
+				return Ok(Node::Short {
+					val: Box::new(insert(Some(*val), &key[matched..], value)?),
+					key: prefix,
+				});
+			}
+
+			let mut children: [Option<Node>; 17] = Default::default();
+			children[usize::from(prefix[matched])] =
+				Some(insert(None, &prefix[matched + 1..], *val)?);
+			children[usize::from(key[matched])] = Some(insert(None, &key[matched + 1..], value)?);
+			let branch = Node::Full(Box::new(children));
+
+			if matched == 0 {
+				Ok(branch)
+			} else {
+				Ok(Node::Short {
+					key: key[..matched].to_vec(),
+					val: Box::new(branch),
+				})
+			}
+		}
+		Some(Node::Full(mut children)) => {
+			let index = usize::from(key[0]);
+			children[index] = Some(insert(children[index].take(), &key[1..], value)?);
+			Ok(Node::Full(children))
+		}
+		Some(Node::Hash(hash)) => Err(ProofError::MissingNode(hash)),
+		Some(Node::Value(_)) => Err(ProofError::InvalidNode),
+	}
+}
+
+/// Verify a Merkle proof for `key` against `root`.
+///
+/// Returns the value stored at `key`, or `None` if the proof shows that the
+/// key is not in the trie.
+pub fn verify_proof<P: AsRef<[u8]>>(
+	root: H256,
+	key: &[u8],
+	proof: &[P],
+) -> Result<Option<Vec<u8>>, ProofError> {
+	if root == EMPTY_TRIE_ROOT {
+		return Ok(None);
+	}
+
+	let db = ProofDb::new(proof);
+	let mut node = db.resolve(root)?;
+	resolve_path(&mut node, &key_to_nibbles(key), &db)
+}
+
+/// Verify that `keys` and `values` are all the entries of the trie at `root`
+/// from `first_key` up to the last key, given the proofs of both edges.
+///
+/// Without a proof, the range has to be the whole trie. Returns whether the
+/// trie holds more entries after the range.
+pub fn verify_range_proof<K, V, P>(
+	root: H256,
+	first_key: &[u8],
+	keys: &[K],
+	values: &[V],
+	proof: Option<&[P]>,
+) -> Result<bool, ProofError>
+where
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+	P: AsRef<[u8]>,
+{
+	if keys.len() != values.len() {
+		return Err(ProofError::LengthMismatch);
+	}
+	if keys
+		.windows(2)
+		.any(|pair| pair[0].as_ref() >= pair[1].as_ref())
+	{
+		return Err(ProofError::NotMonotonic);
+	}
+	if values.iter().any(|value| value.as_ref().is_empty()) {
+		return Err(ProofError::EmptyValue);
+	}
+	if keys.first().is_some_and(|key| key.as_ref() < first_key) {
+		return Err(ProofError::KeyBeforeOrigin);
+	}
+
+	let Some(proof) = proof else {
+		let computed = trie_root(
+			keys.iter()
+				.map(AsRef::as_ref)
+				.zip(values.iter().map(AsRef::as_ref)),
+		);
+		if computed != root {
+			return Err(ProofError::RootMismatch {
+				expected: root,
+				computed,
+			});
+		}
+		return Ok(false);
+	};
+
+	let db = ProofDb::new(proof);
+	let first = key_to_nibbles(first_key);
+
+	let Some(last_key) = keys.last() else {
+		if root == EMPTY_TRIE_ROOT {
+			return Ok(false);
+		}
+		let mut node = db.resolve(root)?;
+		let value = resolve_path(&mut node, &first, &db)?;
+		if value.is_some() || has_right_element(Some(&node), &first) {
+			return Err(ProofError::MoreEntries);
+		}
+		return Ok(false);
+	};
+	let last = key_to_nibbles(last_key.as_ref());
+
+	if keys.len() == 1 && first_key == last_key.as_ref() {
+		let mut node = db.resolve(root)?;
+		let value = resolve_path(&mut node, &first, &db)?;
+		if value.as_deref() != Some(values[0].as_ref()) {
+			return Err(ProofError::ValueMismatch);
+		}
+		return Ok(has_right_element(Some(&node), &first));
+	}
+
+	if first_key >= last_key.as_ref() || first_key.len() != last_key.as_ref().len() {
+		return Err(ProofError::InvalidEdgeKeys);
+	}
+
+	let mut node = db.resolve(root)?;
+	resolve_path(&mut node, &first, &db)?;
+	resolve_path(&mut node, &last, &db)?;
+
+	let mut trie = if unset_internal(&mut node, &first, &last)? {
+		None
+	} else {
+		Some(node)
+	};
+	for (key, value) in keys.iter().zip(values) {
+		trie = Some(insert(
+			trie,
+			&key_to_nibbles(key.as_ref()),
+			Node::Value(value.as_ref().to_vec()),
+		)?);
+	}
+
+	let computed = root_hash(trie.as_ref());
+	if computed != root {
+		return Err(ProofError::RootMismatch {
+			expected: root,
+			computed,
+		});
+	}
+	Ok(has_right_element(trie.as_ref(), &last))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entries(count: u64) -> Vec<(H256, Vec<u8>)> {
+		let mut entries: Vec<_> = (0..count)
+			.map(|i| (keccak(&i.to_be_bytes()), rlp::encode(&(i + 1)).to_vec()))
+			.collect();
+		entries.sort();
+		entries
+	}
+
+	fn build(entries: &[(H256, Vec<u8>)]) -> Node {
+		let mut trie = None;
+		for (key, value) in entries {
+			trie = Some(
+				insert(
+					trie,
+					&key_to_nibbles(key.as_bytes()),
+					Node::Value(value.clone()),
+				)
+				.unwrap(),
+			);
+		}
+		trie.unwrap()
+	}
+
+	fn prove(root: &Node, key: &[u8], proof: &mut Vec<Vec<u8>>) {
+		let key = key_to_nibbles(key);
+		let mut key = key.as_slice();
+		let mut node = Some(root);
+		proof.push(encode_node(root));
+
+		while let Some(current) = node {
+			let next = match current {
+				Node::Short { key: prefix, val } if key.starts_with(prefix) => {
+					key = &key[prefix.len()..];
+					Some(val.as_ref())
+				}
+				Node::Full(children) => {
+					let child = children[usize::from(key[0])].as_ref();
+					key = &key[1..];
+					child
+				}
+				_ => None,
+			};
+			if let Some(next) = next {
+				let encoded = encode_node(next);
+				if encoded.len() >= 32 {
+					proof.push(encoded);
+				}
+			}
+			node = next;
+		}
+	}
+
+	fn range(
+		root: H256,
+		first_key: H256,
+		entries: &[(H256, Vec<u8>)],
+		proof: &[Vec<u8>],
+	) -> Result<bool, ProofError> {
+		let keys: Vec<_> = entries.iter().map(|(key, _)| key.as_bytes()).collect();
+		let values: Vec<_> = entries.iter().map(|(_, value)| value.as_slice()).collect();
+		verify_range_proof(root, first_key.as_bytes(), &keys, &values, Some(proof))
+	}
+
+	#[test]
+	fn single_proofs() {
+		let entries = entries(100);
+		let trie = build(&entries);
+		let root = root_hash(Some(&trie));
+		assert_eq!(root, trie_root(entries.clone()));
+		assert_eq!(keccak(&[0x80]), EMPTY_TRIE_ROOT);
+
+		let (key, value) = &entries[42];
+		let mut proof = Vec::new();
+		prove(&trie, key.as_bytes(), &mut proof);
+		assert_eq!(
+			verify_proof(root, key.as_bytes(), &proof),
+			Ok(Some(value.clone()))
+		);
+
+		let absent = H256::repeat_byte(0xab);
+		let mut proof = Vec::new();
+		prove(&trie, absent.as_bytes(), &mut proof);
+		assert_eq!(verify_proof(root, absent.as_bytes(), &proof), Ok(None));
+		assert!(matches!(
+			verify_proof(root, key.as_bytes(), &proof[..1]),
+			Err(ProofError::MissingNode(_))
+		));
+	}
+
+	#[test]
+	fn whole_range_without_proof() {
+		let mut entries = entries(50);
+		let root = root_hash(Some(&build(&entries)));
+		let keys: Vec<_> = entries.iter().map(|(key, _)| *key).collect();
+		let values: Vec<_> = entries.iter().map(|(_, value)| value.clone()).collect();
+
+		assert_eq!(
+			verify_range_proof::<_, _, Vec<u8>>(root, &[], &keys, &values, None),
+			Ok(false)
+		);
+
+		entries.pop();
+		let keys: Vec<_> = entries.iter().map(|(key, _)| *key).collect();
+		assert!(matches!(
+			verify_range_proof::<_, _, Vec<u8>>(root, &[], &keys, &values[..49], None),
+			Err(ProofError::RootMismatch { .. })
+		));
+	}
+
+	#[test]
+	fn ranges_with_edge_proofs() {
+		let entries = entries(200);
+		let trie = build(&entries);
+		let root = root_hash(Some(&trie));
+
+		for (start, end) in [
+			(0, 200),
+			(0, 10),
+			(17, 18),
+			(30, 120),
+			(150, 200),
+			(199, 200),
+		] {
+			let mut proof = Vec::new();
+			prove(&trie, entries[start].0.as_bytes(), &mut proof);
+			prove(&trie, entries[end - 1].0.as_bytes(), &mut proof);
+
+			assert_eq!(
+				range(root, entries[start].0, &entries[start..end], &proof),
+				Ok(end < entries.len()),
+				"range {}..{}",
+				start,
+				end
+			);
+
+			// Origin before the first key, not itself in the trie.
+			let mut origin = entries[start].0;
+			origin.0[31] = origin.0[31].wrapping_sub(1);
+			if start > 0 && origin <= entries[start - 1].0 || origin > entries[start].0 {
+				continue;
+			}
+			let mut proof = Vec::new();
+			prove(&trie, origin.as_bytes(), &mut proof);
+			prove(&trie, entries[end - 1].0.as_bytes(), &mut proof);
+			assert_eq!(
+				range(root, origin, &entries[start..end], &proof),
+				Ok(end < entries.len())
+			);
+
+			if end - start > 2 {
+				let mut gapped = entries[start..end].to_vec();
+				gapped.remove(1);
+				assert!(matches!(
+					range(root, origin, &gapped, &proof),
+					Err(ProofError::RootMismatch { .. })
+				));
+			}
+		}
+	}
+
+	#[test]
+	fn empty_range() {
+		let entries = entries(20);
+		let trie = build(&entries);
+		let root = root_hash(Some(&trie));
+
+		let origin = H256::repeat_byte(0xff);
+		let mut proof = Vec::new();
+		prove(&trie, origin.as_bytes(), &mut proof);
+		assert_eq!(range(root, origin, &[], &proof), Ok(false));
+
+		let origin = H256::zero();
+		let mut proof = Vec::new();
+		prove(&trie, origin.as_bytes(), &mut proof);
+		assert_eq!(
+			range(root, origin, &[], &proof),
+			Err(ProofError::MoreEntries)
+		);
+	}
+}
@@ -110,7 +110,7 @@ impl trie_root::TrieStream for Hash256RlpTrieStream {
 ///  [1,2,3,4,5,T]     0x312345   // 5 > 3
 ///  [1,2,3,4,T]       0x201234   // 4 > 3
 /// ```
-fn hex_prefix_encode(nibbles: &[u8], leaf: bool) -> impl Iterator<Item = u8> + '_ {
+pub(crate) fn hex_prefix_encode(nibbles: &[u8], leaf: bool) -> impl Iterator<Item = u8> + '_ {
 	let inlen = nibbles.len();
 	let oddness_factor = inlen % 2;
 
@@ -0,0 +1,396 @@
+//! Borrowed views over RLP encoded blocks and transactions.
+//!
+//! Views check the list structure upfront and decode fields only when they
+//! are accessed, without copying the underlying bytes.
+
+use alloc::vec::Vec;
+
+use ethereum_types::{H256, U256};
+use rlp::{DecoderError, Rlp};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+	block::Block,
+	enveloped::{EnvelopedDecodable, EnvelopedDecoderError},
+	header::Header,
+	transaction::{TransactionAction, TransactionAny, TransactionRecoveryId},
+};
+
+/// Check that an item spans all of its bytes.
+fn check_length(rlp: &Rlp) -> Result<(), DecoderError> {
+	let info = rlp.payload_info()?;
+	if info.header_len + info.value_len != rlp.as_raw().len() {
+		return Err(DecoderError::RlpInconsistentLengthAndData);
+	}
+	Ok(())
+}
+
+/// Borrowed view over an RLP encoded block.
+///
+/// Fields are decoded lazily from the underlying bytes when accessed, and
+/// transactions are only indexed, not decoded.
+#[derive(Clone, Debug)]
+pub struct BlockRef<'a> {
+	rlp: Rlp<'a>,
+}
+
+impl<'a> BlockRef<'a> {
+	/// Create a view over an RLP encoded block.
+	///
+	/// Only the outer structure is checked here, fields are checked when
+	/// accessed.
+	pub fn new(bytes: &'a [u8]) -> Result<Self, DecoderError> {
+		let rlp = Rlp::new(bytes);
+		check_length(&rlp)?;
+		if rlp.item_count()? < 3 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+		if !rlp.at(0)?.is_list() || !rlp.at(1)?.is_list() || !rlp.at(2)?.is_list() {
+			return Err(DecoderError::RlpExpectedToBeList);
+		}
+
+		Ok(Self { rlp })
+	}
+
+	#[must_use]
+	pub fn as_bytes(&self) -> &'a [u8] {
+		self.rlp.as_raw()
+	}
+
+	/// RLP encoding of the header.
+	pub fn header_bytes(&self) -> Result<&'a [u8], DecoderError> {
+		Ok(self.rlp.at(0)?.as_raw())
+	}
+
+	/// Hash of the header, computed without decoding it.
+	pub fn hash(&self) -> Result<H256, DecoderError> {
+		Ok(H256::from_slice(
+			Keccak256::digest(self.header_bytes()?).as_slice(),
+		))
+	}
+
+	pub fn header(&self) -> Result<Header, DecoderError> {
+		self.rlp.val_at(0)
+	}
+
+	pub fn parent_hash(&self) -> Result<H256, DecoderError> {
+		self.rlp.at(0)?.val_at(0)
+	}
+
+	pub fn number(&self) -> Result<U256, DecoderError> {
+		self.rlp.at(0)?.val_at(8)
+	}
+
+	pub fn timestamp(&self) -> Result<u64, DecoderError> {
+		self.rlp.at(0)?.val_at(11)
+	}
+
+	pub fn transaction_count(&self) -> Result<usize, DecoderError> {
+		self.rlp.at(1)?.item_count()
+	}
+
+	/// Iterator over views of the transactions of the block.
+	pub fn transactions(&self) -> Result<TransactionRefs<'a>, DecoderError> {
+		let list = self.rlp.at(1)?;
+		let count = list.item_count()?;
+		Ok(TransactionRefs {
+			list,
+			index: 0,
+			count,
+		})
+	}
+
+	pub fn ommers(&self) -> Result<Vec<Header>, DecoderError> {
+		self.rlp.list_at(2)
+	}
+
+	/// Decode the whole block.
+	pub fn to_owned(&self) -> Result<Block<TransactionAny>, DecoderError> {
+		rlp::Decodable::decode(&self.rlp)
+	}
+}
+
+/// Iterator over the transactions of a [`BlockRef`].
+#[derive(Clone, Debug)]
+pub struct TransactionRefs<'a> {
+	list: Rlp<'a>,
+	index: usize,
+	count: usize,
+}
+
+impl<'a> Iterator for TransactionRefs<'a> {
+	type Item = Result<TransactionRef<'a>, DecoderError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index == self.count {
+			return None;
+		}
+
+		let item = self.list.at(self.index);
+		self.index += 1;
+		Some(item.and_then(|item| {
+			let bytes = if item.is_list() {
+				item.as_raw()
+			} else {
+				item.data()?
+			};
+			TransactionRef::new(bytes)
+		}))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = self.count - self.index;
+		(remaining, Some(remaining))
+	}
+}
+
+impl ExactSizeIterator for TransactionRefs<'_> {}
+
+/// Borrowed view over an enveloped transaction.
+///
+/// Fields are decoded lazily from the underlying bytes when accessed.
+#[derive(Clone, Debug)]
+pub struct TransactionRef<'a> {
+	bytes: &'a [u8],
+	tx_type: u8,
+	payload: Rlp<'a>,
+}
+
+impl<'a> TransactionRef<'a> {
+	/// Create a view over the enveloped encoding of a transaction.
+	pub fn new(bytes: &'a [u8]) -> Result<Self, DecoderError> {
+		let (tx_type, payload, item_count) = match bytes.first() {
+			None => return Err(DecoderError::RlpIsTooShort),
+			Some(0xc0..) => (0, bytes, 9),
+			Some(0x01) => (1, &bytes[1..], 11),
+			Some(0x02) => (2, &bytes[1..], 12),
+			Some(_) => return Err(DecoderError::Custom("invalid tx type")),
+		};
+
+		let payload = Rlp::new(payload);
+		check_length(&payload)?;
+		if payload.item_count()? != item_count {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			bytes,
+			tx_type,
+			payload,
+		})
+	}
+
+	/// Enveloped encoding of the transaction.
+	#[must_use]
+	pub fn as_bytes(&self) -> &'a [u8] {
+		self.bytes
+	}
+
+	/// EIP-2718 transaction type, `0` for legacy transactions.
+	#[must_use]
+	pub fn tx_type(&self) -> u8 {
+		self.tx_type
+	}
+
+	#[must_use]
+	pub fn hash(&self) -> H256 {
+		H256::from_slice(Keccak256::digest(self.bytes).as_slice())
+	}
+
+	/// Index of a field in the payload, given its index in a legacy
+	/// transaction. Typed transactions start with the chain id, and EIP-1559
+	/// transactions replace the gas price by two fee fields.
+	fn field(&self, legacy_index: usize) -> Result<Rlp<'a>, DecoderError> {
+		let index = match self.tx_type {
+			0 => legacy_index,
+			1 => legacy_index + 1,
+			_ if legacy_index == 0 => 1,
+			_ => legacy_index + 2,
+		};
+		self.payload.at(index)
+	}
+
+	pub fn chain_id(&self) -> Result<Option<u64>, DecoderError> {
+		if self.tx_type == 0 {
+			Ok(TransactionRecoveryId(self.payload.val_at(6)?).chain_id())
+		} else {
+			self.payload.val_at(0).map(Some)
+		}
+	}
+
+	pub fn nonce(&self) -> Result<U256, DecoderError> {
+		self.field(0)?.as_val()
+	}
+
+	/// Gas price of legacy and EIP-2930 transactions, or max fee per gas of
+	/// EIP-1559 transactions.
+	pub fn max_fee_per_gas(&self) -> Result<U256, DecoderError> {
+		self.field(1)?.as_val()
+	}
+
+	pub fn gas_limit(&self) -> Result<U256, DecoderError> {
+		self.field(2)?.as_val()
+	}
+
+	pub fn action(&self) -> Result<TransactionAction, DecoderError> {
+		self.field(3)?.as_val()
+	}
+
+	pub fn value(&self) -> Result<U256, DecoderError> {
+		self.field(4)?.as_val()
+	}
+
+	pub fn input(&self) -> Result<&'a [u8], DecoderError> {
+		self.field(5)?.data()
+	}
+
+	/// Decode the whole transaction.
+	pub fn to_owned(&self) -> Result<TransactionAny, DecoderError> {
+		TransactionAny::decode(self.bytes).map_err(|e| match e {
+			EnvelopedDecoderError::UnknownTypeId => DecoderError::Custom("invalid tx type"),
+			EnvelopedDecoderError::Payload(e) => e,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::partial_header;
+	use crate::{
+		header::PartialHeader,
+		transaction::{
+			EIP1559Transaction, EIP2930Transaction, LegacyTransaction, TransactionSignature,
+		},
+	};
+	use ethereum_types::H160;
+
+	fn block() -> Block<TransactionAny> {
+		let partial_header = PartialHeader {
+			parent_hash: H256::repeat_byte(1),
+			number: 1234.into(),
+			gas_used: 63_000.into(),
+			timestamp: 1_700_000_000,
+			..partial_header()
+		};
+		let transactions = vec![
+			TransactionAny::Legacy(LegacyTransaction {
+				nonce: 1.into(),
+				gas_price: 10.into(),
+				gas_limit: 21_000.into(),
+				action: TransactionAction::Call(H160::repeat_byte(2)),
+				value: 5.into(),
+				input: vec![0xaa],
+				signature: TransactionSignature::new(
+					37,
+					H256::repeat_byte(3),
+					H256::repeat_byte(4),
+				)
+				.unwrap(),
+			}),
+			TransactionAny::EIP2930(EIP2930Transaction {
+				chain_id: 1,
+				nonce: 2.into(),
+				gas_price: 11.into(),
+				gas_limit: 21_001.into(),
+				action: TransactionAction::Create,
+				value: 6.into(),
+				input: vec![0xbb, 0xbb],
+				access_list: Vec::new(),
+				odd_y_parity: false,
+				r: H256::repeat_byte(3),
+				s: H256::repeat_byte(4),
+			}),
+			TransactionAny::EIP1559(EIP1559Transaction {
+				chain_id: 5,
+				nonce: 3.into(),
+				max_priority_fee_per_gas: 1.into(),
+				max_fee_per_gas: 12.into(),
+				gas_limit: 21_002.into(),
+				action: TransactionAction::Call(H160::repeat_byte(7)),
+				value: 7.into(),
+				input: vec![0xcc, 0xcc, 0xcc],
+				access_list: Vec::new(),
+				odd_y_parity: true,
+				r: H256::repeat_byte(3),
+				s: H256::repeat_byte(4),
+			}),
+		];
+
+		Block::new(partial_header, transactions, Vec::new())
+	}
+
+	#[test]
+	fn block_fields() {
+		let block = block();
+		let encoded = rlp::encode(&block);
+		let view = BlockRef::new(&encoded).unwrap();
+
+		assert_eq!(view.hash().unwrap(), block.header.hash());
+		assert_eq!(view.header().unwrap(), block.header);
+		assert_eq!(view.parent_hash().unwrap(), H256::repeat_byte(1));
+		assert_eq!(view.number().unwrap(), 1234.into());
+		assert_eq!(view.timestamp().unwrap(), 1_700_000_000);
+		assert_eq!(view.transaction_count().unwrap(), 3);
+		assert!(view.ommers().unwrap().is_empty());
+		assert_eq!(view.to_owned().unwrap(), block);
+
+		assert_eq!(
+			BlockRef::new(&encoded[..encoded.len() - 1]).unwrap_err(),
+			DecoderError::RlpIsTooShort
+		);
+	}
+
+	#[test]
+	fn transaction_fields() {
+		let block = block();
+		let encoded = rlp::encode(&block);
+		let view = BlockRef::new(&encoded).unwrap();
+
+		let transactions = view
+			.transactions()
+			.unwrap()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(transactions.len(), 3);
+
+		let expected = [
+			(0, Some(1), 1, 10, 21_000, 5, 1),
+			(1, Some(1), 2, 11, 21_001, 6, 2),
+			(2, Some(5), 3, 12, 21_002, 7, 3),
+		];
+		for ((tx, owned), expected) in transactions.iter().zip(&block.transactions).zip(expected) {
+			let (tx_type, chain_id, nonce, fee, gas_limit, value, input_len) = expected;
+			assert_eq!(tx.tx_type(), tx_type);
+			assert_eq!(tx.chain_id().unwrap(), chain_id);
+			assert_eq!(tx.nonce().unwrap(), nonce.into());
+			assert_eq!(tx.max_fee_per_gas().unwrap(), fee.into());
+			assert_eq!(tx.gas_limit().unwrap(), gas_limit.into());
+			assert_eq!(tx.value().unwrap(), value.into());
+			assert_eq!(tx.input().unwrap().len(), input_len);
+			assert_eq!(tx.hash(), owned.hash());
+			assert_eq!(&tx.to_owned().unwrap(), owned);
+		}
+		assert_eq!(transactions[1].action().unwrap(), TransactionAction::Create);
+		assert_eq!(
+			transactions[2].action().unwrap(),
+			TransactionAction::Call(H160::repeat_byte(7))
+		);
+	}
+
+	#[test]
+	fn transaction_decoding_error() {
+		// Legacy transaction with an empty signature.
+		let mut s = rlp::RlpStream::new_list(9);
+		for _ in 0..9 {
+			s.append_empty_data();
+		}
+		let encoded = s.out();
+
+		let tx = TransactionRef::new(&encoded).unwrap();
+		assert_eq!(
+			tx.to_owned(),
+			Err(DecoderError::Custom("Invalid transaction signature format"))
+		);
+	}
+}
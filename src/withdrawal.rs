@@ -0,0 +1,31 @@
+use ethereum_types::{Address, H256};
+
+use crate::util::ordered_trie_root;
+
+/// Validator withdrawal, introduced by EIP-4895.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(rlp::RlpEncodable, rlp::RlpDecodable)]
+#[cfg_attr(
+	feature = "with-scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+	feature = "with-serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct Withdrawal {
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub index: u64,
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub validator_index: u64,
+	pub address: Address,
+	/// Amount in Gwei.
+	#[cfg_attr(feature = "with-serde", serde(with = "crate::hex::quantity"))]
+	pub amount: u64,
+}
+
+/// Compute the withdrawals root of a list of withdrawals.
+pub fn withdrawals_root(withdrawals: &[Withdrawal]) -> H256 {
+	ordered_trie_root(withdrawals.iter().map(rlp::encode))
+}